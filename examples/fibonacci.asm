@@ -1,3 +1,5 @@
+START main
+
 last:  DAT -1 # starts undefined
 curr:  DAT  0
 next:  DAT  1
@@ -7,13 +9,14 @@ decr:  DAT -1
 ascii: DAT -0x30
 
 # Read count, convert from ASCII to binary number and store
+main:
 LDV stdin.getc
 ADD ascii
 STV count
 
 loop:
 
-# Check if done
+# Check if done (count - 1 only becomes negative after "count" iterations)
 LDV count
 ADD decr
 JMN out
@@ -0,0 +1,67 @@
+// The canonical end-to-end example:
+// Assemble "fibonacci.asm", link it against a console device, run it to HLT and print the Fibonacci terms.
+// Usage: cargo run --example fibonacci [count] (a single digit, 9 by default)
+
+use std::env;
+use std::process;
+use mimasim::{assembly::ObjectCode, mima::Mima, types::*, unit::Device};
+
+// The console is mapped to the start of the device I/O address space.
+// Reading "getc" yields the next input character, writing "putc" prints a word as decimal number.
+const CONSOLE_BASE: Word = DEVICE_IO_ADDRESS_SPACE_RANGE.start;
+const CONSOLE_GETC: Word = Word(0);
+const CONSOLE_PUTC: Word = Word(1);
+
+struct Console
+{
+	input: Vec<u8>,
+}
+
+impl Device for Console
+{
+	fn read(&mut self, offset: Word) -> Word
+	{
+		match offset
+		{
+			CONSOLE_GETC if !self.input.is_empty() 	=> Word(self.input.remove(0) as u32),
+			_ 										=> Word(0),
+		}
+	}
+
+	fn write(&mut self, offset: Word, value: Word)
+	{
+		if offset == CONSOLE_PUTC
+		{
+			println!("{:}", value.0);
+		}
+	}
+}
+
+fn main()
+{
+	let count = env::args().nth(1).unwrap_or_else(|| String::from("9"));
+
+	if (count.len() != 1) || !count.as_bytes()[0].is_ascii_digit()
+	{
+		eprintln!("The count must be a single digit.");
+		process::exit(1);
+	}
+
+	let (object_code, diagnostics) = ObjectCode::assemble(include_str!("fibonacci.asm")).unwrap();
+
+	for diagnostic in diagnostics.iter()
+	{
+		eprintln!("{:}", diagnostic);
+	}
+
+	// Attach the console and expose it to the program as "stdin" and "stdout":
+	let mut mima = Mima::new();
+	let console_range = CONSOLE_BASE..Word(CONSOLE_BASE.0 + 2);
+
+	mima.memory_unit.attach_device(console_range, Box::new(Console { input: count.into_bytes() }));
+	mima.memory_unit.register_device_labels("stdin", CONSOLE_BASE, &[("getc", CONSOLE_GETC)]);
+	mima.memory_unit.register_device_labels("stdout", CONSOLE_BASE, &[("putc", CONSOLE_PUTC)]);
+
+	mima.load_code(&object_code).unwrap();
+	mima.run_to_halt(10_000).unwrap();
+}
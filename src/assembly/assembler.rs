@@ -1,9 +1,14 @@
 use std::collections::{hash_map, HashMap};
+use std::iter;
+use std::ops::Range;
 use crate::types::*;
 use crate::assembly::error::*;
 use crate::assembly::parser::*;
+use crate::assembly::listing::*;
+use crate::assembly::include::FileId;
 
 // A fully-qualified label consists of a device namespace prefix and a name suffix:
+#[derive(PartialEq)]
 pub struct Label
 {
 	pub prefix: String,
@@ -24,6 +29,7 @@ impl Label
 
 // A symbol table contains a bunch of symbols (fully-qualified labels) and maps them to instruction addresses.
 // It allows the memory unit to "link" the object code into an executable program.
+#[derive(PartialEq)]
 pub struct Symbol
 {
 	pub instruction_address: Word,
@@ -42,53 +48,299 @@ impl Symbol
 	}
 }
 
-// Object code consists of raw code and a symbol table:
+// The link status of a symbol:
+// It is resolved as soon as the placeholder operand of its instruction has been replaced by a real address.
+pub struct SymbolStatus<'oc>
+{
+	pub label: &'oc Label,
+	pub instruction_address: Word,
+	pub resolved: bool,
+}
+
+// The kind of the words in a section:
+// Instructions are code, data and string statements are data.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SectionKind
+{
+	Code,
+	Data,
+}
+
+// A section is a run of consecutive words of the same kind in the raw code:
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section
+{
+	pub kind: SectionKind,
+	pub range: Range<Word>,
+}
+
+// Object code consists of raw code and a symbol table.
+// The source map holds the file and line number of the originating statement for every word of the raw code (programs from plain strings only have the root file 0).
+// The entry point is the address of the first instruction to execute (declared via "start", 0 by default).
+// The local symbols map the local labels to their addresses (sorted by address).
+// The relocations are the addresses of all instructions whose operand is the address of a local label (i. e. the operands to adjust if the code is moved).
+// The sections split the raw code into code and data.
+#[derive(PartialEq)]
 pub struct ObjectCode
 {
 	pub raw_code: Box<[Word]>,
 	pub symbol_table: Vec<Symbol>,
+	pub source_map: Box<[(FileId, usize)]>,
+	pub entry: Word,
+	pub local_symbols: Vec<(String, Word)>,
+	pub relocations: Vec<Word>,
+	pub sections: Vec<Section>,
 }
 
 // The string representation of a program:
 pub type ProgramRepr = String;
 
+// Options to configure the assembler.
+// The defaults match the behaviour of the plain "assemble" functions.
+#[derive(Clone)]
+pub struct AssembleOptions
+{
+	// Warn about data cells that are jumped to, but do not decode to a valid instruction:
+	pub strict: bool,
+
+	// Opt-in style lints (e. g. bit masks written in decimal):
+	pub style_lints: bool,
+
+	// Mnemonics and directives are always case-insensitive, but label and constant names are case-sensitive by default.
+	// This flag makes the names case-insensitive as well (the first definition determines the spelling in the symbol map).
+	// Device prefixes and device labels are resolved by the MiMA and stay case-sensitive.
+	pub case_insensitive_labels: bool,
+
+	// Turn warnings about unused local labels into hard errors:
+	pub warnings_as_errors: bool,
+
+	// Do not warn about labels that are named like mnemonics or directives:
+	pub allow_keyword_labels: bool,
+
+	// The number of machine words a program may occupy (defaults to the whole linear memory):
+	pub linear_words: usize,
+
+	// The program runs on a MiMA that sign-extends LDC constants (see "Mima::sign_extend_constants").
+	// Warnings about constants that are not loaded as written depend on it.
+	pub sign_extend_constants: bool,
+}
+
+impl Default for AssembleOptions
+{
+	fn default() -> Self
+	{
+		AssembleOptions
+		{
+			strict: false,
+			style_lints: false,
+			case_insensitive_labels: false,
+			warnings_as_errors: false,
+			allow_keyword_labels: false,
+			linear_words: LINEAR_ADDRESS_SPACE_WORDS,
+			sign_extend_constants: false,
+		}
+	}
+}
+
+// Local labels with a leading dot (e. g. ".loop") are scoped: They belong to the last label without dot that has been defined before them.
+// This way, every subroutine can have its own ".loop". A label key holds that enclosing label (None for unscoped labels) and the name.
+type LabelKey<'src> = (Option<&'src str>, &'src str);
+
 // A label map contains the line numbers and addresses of all local labels (no associated types in impls yet, not even private ...):
-type LabelMap<'src> = HashMap<&'src str, (usize, Word)>;
+type LabelMap<'src> = HashMap<LabelKey<'src>, (usize, Word)>;
+
+// A constant map contains the line numbers and word tokens of all named constants:
+type ConstantMap<'src> = HashMap<&'src str, (usize, WordToken)>;
 
 impl ObjectCode
 {
 	// This placeholder address is inserted for yet unresolved device symbols.
 	// Reads from and writes to this address will always trigger an error.
-	const PLACEHOLDER_ADDR: Word = Word(ADDRESS_SPACE_RANGE.end.0 - 1);
+	pub const PLACEHOLDER_ADDR: Word = Word(ADDRESS_SPACE_RANGE.end.0 - 1);
+
+	// The widths of the payload fields in the basic and extended instruction formats:
+	pub(crate) const BASIC_PAYLOAD_BITS: u32 = 28;
+	pub(crate) const EXTENDED_PAYLOAD_BITS: u32 = 24;
+
+	// Iterate the symbols and their link status in the raw code:
+	pub fn symbols(&self) -> impl Iterator<Item = SymbolStatus<'_>>
+	{
+		self.symbols_in(&self.raw_code)
+	}
+
+	// Iterate the symbols and their link status in the given code (e. g. the linear memory the object code has been loaded into).
+	// Symbols whose instruction lies beyond the given code count as unresolved.
+	pub fn symbols_in<'oc>(&'oc self, code: &'oc [Word]) -> impl Iterator<Item = SymbolStatus<'oc>>
+	{
+		self.symbol_table.iter().map(move |symbol|
+		{
+			let payload = code.get(symbol.instruction_address.0 as usize).map(|word| word.0 & 0x0F_FF_FF_FFu32);
+
+			SymbolStatus
+			{
+				label: &symbol.label,
+				instruction_address: symbol.instruction_address,
+				resolved: payload.is_some_and(|payload| payload != ObjectCode::PLACEHOLDER_ADDR.0),
+			}
+		})
+	}
+
+	// Emit the local symbols as "name = 0xADDR" lines:
+	pub fn symbol_map(&self) -> String
+	{
+		self.local_symbols.iter().map(|(name, addr)| format!("{:} = {:}\n", name, addr)).collect()
+	}
+
+	// Encode instructions into object code without the assembler (e. g. to feed generated programs to Mima::load_code).
+	// There are no symbols, every instruction counts as its own line and the entry point is 0.
+	// Payloads that exceed their field are rejected (the line number is the index of the instruction), programs that exceed the linear memory are rejected when they are loaded.
+	pub fn from_instructions(instructions: &[Instruction]) -> Result<ObjectCode, OperandError>
+	{
+		for (line_number, instruction) in instructions.iter().enumerate()
+		{
+			let bits = if instruction.is_extended_format() { ObjectCode::EXTENDED_PAYLOAD_BITS } else { ObjectCode::BASIC_PAYLOAD_BITS };
+
+			// Illegal instructions keep their whole word, so only the payloads of valid ones are checked:
+			if let Some(payload) = instruction.payload().filter(|payload| !matches!(instruction, Instruction::Illegal(_)) && ((payload.0 >> bits) != 0))
+			{
+				return Err(OperandError::new(line_number, OperandErrorType::TooLarge(payload, bits)));
+			}
+		}
+
+		Ok(ObjectCode
+		{
+			raw_code: instructions.iter().map(|&instruction| Word::from(instruction)).collect(),
+			symbol_table: vec![],
+			source_map: (0..instructions.len()).map(|line_number| (0, line_number)).collect(),
+			entry: Word(0),
+			local_symbols: vec![],
+			relocations: vec![],
+			sections: ObjectCode::code_section(instructions.len()),
+		})
+	}
+
+	// A single code section that spans the given number of words (none for empty code):
+	fn code_section(number_of_words: usize) -> Vec<Section>
+	{
+		if number_of_words == 0
+		{
+			return vec![];
+		}
+
+		vec![Section { kind: SectionKind::Code, range: Word(0)..Word(number_of_words as u32) }]
+	}
+
+	// Decode the raw code into instructions (e. g. for MemoryUnit::load_instructions).
+	// Data words are decoded like the MiMA would execute them (reserved opcodes become illegal instructions) and unlinked symbols keep their placeholder address.
+	pub fn to_instructions(&self) -> Vec<Instruction>
+	{
+		self.raw_code.iter().map(|&word| Instruction::from(word)).collect()
+	}
+
+	pub fn assemble_with_repr(input: &str) -> Result<(ObjectCode, Vec<Diagnostics<'_>>, ProgramRepr), AssemblerError<'_>>
+	{
+		let (object_code, diagnostics, program) = ObjectCode::assemble_program(input, &AssembleOptions::default())?;
+		Ok((object_code, diagnostics, format!("{:}", program)))
+	}
+
+	// Assemble the program and list every emitted word along with its address and source line:
+	pub fn assemble_with_listing(input: &str) -> Result<(ObjectCode, Vec<Diagnostics<'_>>, Listing), AssemblerError<'_>>
+	{
+		let (object_code, diagnostics, program) = ObjectCode::assemble_program(input, &AssembleOptions::default())?;
+		let listing = build_listing(&program, &object_code);
+
+		Ok((object_code, diagnostics, listing))
+	}
+
+	pub fn assemble(input: &str) -> Result<(ObjectCode, Vec<Diagnostics<'_>>), AssemblerError<'_>>
+	{
+		ObjectCode::assemble_with_options(input, &AssembleOptions::default())
+	}
 
-	pub fn assemble_with_repr(input: &str) -> Result<(ObjectCode, Vec<Diagnostics>, ProgramRepr), AssemblerError>
+	pub fn assemble_with_options<'src>(input: &'src str, options: &AssembleOptions) -> Result<(ObjectCode, Vec<Diagnostics<'src>>), AssemblerError<'src>>
+	{
+		// Omit the program token:
+		let (object_code, diagnostics, _) = ObjectCode::assemble_program(input, options)?;
+		Ok((object_code, diagnostics))
+	}
+
+	// Assemble everything that can be parsed.
+	// Bad statements are skipped, their errors are returned alongside the object code.
+	// Errors beyond the parser (i. e. label errors) still fail the assembly.
+	pub fn assemble_recover(input: &str) -> Result<(ObjectCode, Vec<Diagnostics<'_>>, Vec<ParserError<'_>>), AssemblerError<'_>>
+	{
+		let program = ProgramToken::parse_recover(input);
+		let parser_errors = program.errors();
+
+		let (object_code, diagnostics, _) = ObjectCode::assemble_program_token(program, &AssembleOptions::default())?;
+		Ok((object_code, diagnostics, parser_errors))
+	}
+
+	fn assemble_program<'src>(input: &'src str, options: &AssembleOptions) -> Result<(ObjectCode, Vec<Diagnostics<'src>>, ProgramToken<'src>), AssemblerError<'src>>
 	{
 		// First, try to parse the program token from the input:
 		let program = ProgramToken::parse(input)?;
+		ObjectCode::assemble_program_token(program, options)
+	}
+
+	// Besides the object code and the diagnostics, return the program token (with all constants resolved):
+	fn assemble_program_token<'src>(mut program: ProgramToken<'src>, options: &AssembleOptions) -> Result<(ObjectCode, Vec<Diagnostics<'src>>, ProgramToken<'src>), AssemblerError<'src>>
+	{
+		// Style lints only look at literals, so they must run before the constants are replaced:
+		let mut diagnostics = vec![];
+
+		if options.style_lints
+		{
+			ObjectCode::find_decimal_masks(&program, &mut diagnostics);
+		}
+
+		// Unify the spelling of all label and constant names if they are case-insensitive:
+		if options.case_insensitive_labels
+		{
+			ObjectCode::fold_name_case(&mut program);
+		}
+
+		// Collect all the named constants and replace every reference to them by their words.
+		// This must happen before the labels are collected because constants may be used as repetition counts.
+		let constant_map = ObjectCode::build_constant_map(&program)?;
+		ObjectCode::resolve_constants(&mut program, &constant_map)?;
+
+		// Negative constants only load correctly if the MiMA sign-extends them (and large positive ones only if it does not):
+		ObjectCode::find_changed_constants(&program, options.sign_extend_constants, &mut diagnostics);
 
 		// Collect all the "locally" defined labels, their line numbers and addresses into a map.
 		// The function also tells us the total number of words that is necessary to hold the program.
-		let (label_map, number_of_words) = ObjectCode::build_label_map(&program)?;
+		let (label_map, number_of_words) = ObjectCode::build_label_map(&program, options, &mut diagnostics)?;
 
-		// Collect diagnostics into a vector:
-		let mut diagnostics = vec![];
+		// Determine the entry point (if it has been declared):
+		let entry = ObjectCode::resolve_entry(&program, &label_map)?;
 
-		// Create a word vector with the given capacity (=> avoids unnecessary allocations) and an empty symbol table:
+		// Create a word vector with the given capacity (=> avoids unnecessary allocations), an empty symbol table and no relocations:
 		let mut raw_code = Vec::with_capacity(number_of_words);
 		let mut symbols = vec![];
+		let mut relocations = vec![];
 
 		// This helpful little closure takes an address token as it occurs in most instructions (and the address + line number of the corresponding instruction).
 		// It resolves it into an address resp. creates a symbol table entry if necessary.
-		// Because it might encounter a missing label, it returns a Result.
-		let mut resolve_addr = |addr, instruction_address, line_number| -> Result<Word, LabelError>
+		// Because it might encounter a missing label (or an address literal that is too large), it returns a Result.
+		let mut resolve_addr = |addr, instruction_address, line_number, scope| -> Result<Word, AssemblerError<'src>>
 		{
 			match addr
 			{
-				AddressToken::Address(w) => Ok(w.0),
-				AddressToken::Label(LabelIdentifierToken(prefix, name)) =>
+				AddressToken::Address(w) => ObjectCode::check_address(w, line_number),
+				AddressToken::Label(LabelIdentifierToken(prefix, name)) | AddressToken::LabelOffset(LabelIdentifierToken(prefix, name), _) =>
 				{
+					// References without offset are treated like an offset of 0:
+					let offset = if let AddressToken::LabelOffset(_, offset) = addr { offset } else { 0 };
+
 					if let Some(prefix) = prefix
 					{
+						// Device labels are resolved by the linker, which knows nothing about offsets:
+						if offset != 0
+						{
+							return Err(LabelError::new(line_number, LabelErrorType::DeviceLabelOffset(prefix, name)).into());
+						}
+
 						// Append this position to the symbol table.
 						// It must be resolved later.
 						let label = Label::new(prefix, name);
@@ -100,23 +352,29 @@ impl ObjectCode
 					else
 					{
 						// We have a local label.
-						// It must be located in our label map.
-						if let Some((_, addr)) = label_map.get(name)
+						// It must be located in our label map (scoped labels relative to the enclosing label).
+						if let Some((_, addr)) = label_map.get(&ObjectCode::label_key(scope, name, line_number)?)
 						{
-							Ok(*addr)
+							// The operand depends on where the program is placed:
+							relocations.push(instruction_address);
+							Ok(ObjectCode::apply_offset(*addr, offset, line_number)?)
 						}
 						else
 						{
-							Err(LabelError::new(line_number, LabelErrorType::NotResolved(name)))
+							Err(LabelError::new(line_number, LabelErrorType::NotResolved(name)).into())
 						}
 					}
 				},
 			}
 		};
 
-		// Iterate through the program:
+		// Iterate through the program (and track the enclosing label for scoped labels):
+		let mut scope = None;
+
 		for stmt in program.0.iter()
 		{
+			ObjectCode::enter_scope(&mut scope, stmt);
+
 			match stmt.content
 			{
 				Some(StatementContentToken::Data(data)) =>
@@ -127,6 +385,8 @@ impl ObjectCode
 					}
 				},
 
+				Some(StatementContentToken::String(string)) => raw_code.extend(string.words()),
+
 				Some(StatementContentToken::Instruction(instruction)) =>
 				{
 					// Get addr and line number of the instruction:
@@ -136,19 +396,25 @@ impl ObjectCode
 					// Assemble it:
 					let word: Word = match instruction
 					{
-						InstructionToken::Add(a) 				=> Instruction::Add(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::And(a) 				=> Instruction::And(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::Or(a) 				=> Instruction::Or(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::Xor(a) 				=> Instruction::Xor(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::LoadValue(a) 			=> Instruction::LoadValue(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::StoreValue(a) 		=> Instruction::StoreValue(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::LoadConstant(w) 		=> Instruction::LoadConstant(w.0).into(),
-						InstructionToken::Jump(a) 				=> Instruction::Jump(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::JumpIfNegative(a) 	=> Instruction::JumpIfNegative(resolve_addr(a, addr, line_number)?).into(),
-						InstructionToken::Equals(a) 			=> Instruction::Equals(resolve_addr(a, addr, line_number)?).into(),
+						InstructionToken::Add(a) 				=> Instruction::Add(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::And(a) 				=> Instruction::And(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::Or(a) 				=> Instruction::Or(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::Xor(a) 				=> Instruction::Xor(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::LoadValue(a) 			=> Instruction::LoadValue(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::StoreValue(a) 		=> Instruction::StoreValue(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::LoadConstant(v) 		=> Instruction::LoadConstant(ObjectCode::check_operand(v.word_token(), ObjectCode::BASIC_PAYLOAD_BITS, line_number)?).into(),
+						InstructionToken::Jump(a) 				=> Instruction::Jump(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::JumpIfNegative(a) 	=> Instruction::JumpIfNegative(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::JumpIfZero(a) 		=> Instruction::JumpIfZero(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::JumpIfEqual(a) 		=> Instruction::JumpIfEqual(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::Equals(a) 			=> Instruction::Equals(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::Compare(a) 			=> Instruction::Compare(resolve_addr(a, addr, line_number, scope)?).into(),
+						InstructionToken::Subtract(a) 			=> Instruction::Subtract(resolve_addr(a, addr, line_number, scope)?).into(),
 						InstructionToken::Halt 					=> Instruction::Halt.into(),
 						InstructionToken::Not 					=> Instruction::Not.into(),
-						InstructionToken::RotateRight(w) 		=> Instruction::RotateRight(w.0).into(),
+						InstructionToken::RotateRight(v) 		=> Instruction::RotateRight(ObjectCode::check_operand(v.word_token(), ObjectCode::EXTENDED_PAYLOAD_BITS, line_number)?).into(),
+						InstructionToken::RotateLeft(v) 		=> Instruction::RotateLeft(ObjectCode::check_operand(v.word_token(), ObjectCode::EXTENDED_PAYLOAD_BITS, line_number)?).into(),
+						InstructionToken::SkipIfNegative 		=> Instruction::SkipIfNegative.into(),
 						InstructionToken::NoOperation 			=> Instruction::NoOperation.into(),
 					};
 
@@ -159,28 +425,278 @@ impl ObjectCode
 		}
 
 		// We did it :)
+		// In strict mode, look for jumps into data that cannot be executed:
+		if options.strict
+		{
+			ObjectCode::find_undecodable_jump_targets(&program, &label_map, &raw_code, &mut diagnostics);
+		}
+
+		// Preserve the local labels before the map is consumed:
+		// Scoped labels are qualified by their enclosing label (e. g. "main.loop").
+		let mut local_symbols: Vec<_> = label_map.iter().map(|(&(scope, name), &(_, addr))| (format!("{:}{:}", scope.unwrap_or(""), name), addr)).collect();
+		local_symbols.sort_by(|(name_a, addr_a), (name_b, addr_b)| addr_a.0.cmp(&addr_b.0).then_with(|| name_a.cmp(name_b)));
+
 		// Now consume the list of local labels and generate warning diagnostics for unused ones:
-		ObjectCode::find_unused_labels(&program, label_map, &mut diagnostics);
+		ObjectCode::find_unused_labels(&program, label_map, &mut diagnostics, options.warnings_as_errors)?;
+
+		// Map every word back to the line of its statement and merge the words of the same kind into sections:
+		let mut source_map = Vec::with_capacity(number_of_words);
+		let mut sections: Vec<Section> = vec![];
+
+		for stmt in program.0.iter()
+		{
+			let start = Word(source_map.len() as u32);
+			source_map.extend(iter::repeat_n((0, stmt.line_number), stmt.required_words()));
+			let end = Word(source_map.len() as u32);
+
+			let kind = match stmt.content
+			{
+				Some(StatementContentToken::Instruction(_)) 	=> SectionKind::Code,
+				_ 												=> SectionKind::Data,
+			};
 
-		// Bundle code and symbol table into an object code struct and return it, along with the diagnostics:
+			match sections.last_mut()
+			{
+				_ if start == end 						=> (),
+				Some(section) if section.kind == kind 	=> section.range.end = end,
+				_ 										=> sections.push(Section { kind, range: start..end }),
+			}
+		}
+
+		// Bundle code, symbol table and source map into an object code struct and return it, along with the diagnostics:
 		let object_code = ObjectCode
 		{
 			raw_code: raw_code.into_boxed_slice(),
 			symbol_table: symbols,
+			source_map: source_map.into_boxed_slice(),
+			entry,
+			local_symbols,
+			relocations,
+			sections,
 		};
 
-		Ok((object_code, diagnostics, format!("{:}", program)))
+		Ok((object_code, diagnostics, program))
 	}
 
-	pub fn assemble(input: &str) -> Result<(ObjectCode, Vec<Diagnostics>), AssemblerError>
+	// Replace every local label / constant name by the spelling of its first case-insensitive definition.
+	// Afterwards, the remaining passes can compare names case-sensitively (e. g. "Loop" and "loop" are duplicates now).
+	fn fold_name_case<'src>(program: &mut ProgramToken<'src>)
 	{
-		// Omit the string representation of the program:
-		let (object_code, diagnostics, _) = ObjectCode::assemble_with_repr(input)?;
-		Ok((object_code, diagnostics))
+		let mut spellings = HashMap::new();
+
+		for stmt in program.0.iter()
+		{
+			let label_names = stmt.label_defs.iter().filter_map(|&LabelDefinitionToken(LabelIdentifierToken(prefix, name))| if prefix.is_none() { Some(name) } else { None });
+			let constant_name = match stmt.content
+			{
+				Some(StatementContentToken::Constant(ConstantDefinitionToken(name, _))) => Some(name),
+				_ 																		=> None,
+			};
+
+			for name in label_names.chain(constant_name)
+			{
+				spellings.entry(name.to_lowercase()).or_insert(name);
+			}
+		}
+
+		// Names without definition keep their spelling (and fail later):
+		let fold = |name: &mut &'src str|
+		{
+			if let Some(&spelling) = spellings.get(&name.to_lowercase())
+			{
+				*name = spelling;
+			}
+		};
+
+		let fold_addr = |addr: &mut AddressToken<'src>|
+		{
+			if let AddressToken::Label(LabelIdentifierToken(None, name)) | AddressToken::LabelOffset(LabelIdentifierToken(None, name), _) = addr
+			{
+				fold(name);
+			}
+		};
+
+		let fold_value = |value: &mut ValueToken<'src>|
+		{
+			if let ValueToken::Constant(name) | ValueToken::ConstantOffset(name, _) = value
+			{
+				fold(name);
+			}
+		};
+
+		for stmt in program.0.iter_mut()
+		{
+			for LabelDefinitionToken(LabelIdentifierToken(prefix, name)) in stmt.label_defs.iter_mut()
+			{
+				if prefix.is_none()
+				{
+					fold(name);
+				}
+			}
+
+			match stmt.content.as_mut()
+			{
+				Some(StatementContentToken::Constant(ConstantDefinitionToken(name, _))) 	=> fold(name),
+				Some(StatementContentToken::Start(a)) 										=> fold_addr(a),
+				Some(StatementContentToken::Data(DataToken(word, times))) =>
+				{
+					fold_value(word);
+
+					if let Some(times) = times
+					{
+						fold_value(times);
+					}
+				},
+
+				Some(StatementContentToken::Instruction(instruction)) => match instruction
+				{
+					InstructionToken::Add(a) 				|
+					InstructionToken::And(a) 				|
+					InstructionToken::Or(a) 				|
+					InstructionToken::Xor(a) 				|
+					InstructionToken::LoadValue(a) 			|
+					InstructionToken::StoreValue(a) 		|
+					InstructionToken::Jump(a) 				|
+					InstructionToken::JumpIfNegative(a) 	|
+					InstructionToken::JumpIfZero(a) 		|
+					InstructionToken::JumpIfEqual(a) 		|
+					InstructionToken::Equals(a) 			|
+					InstructionToken::Compare(a) 			|
+					InstructionToken::Subtract(a) 			=> fold_addr(a),
+					InstructionToken::LoadConstant(v) 		|
+					InstructionToken::RotateRight(v) 		|
+					InstructionToken::RotateLeft(v) 		=> fold_value(v),
+					_ 										=> (),
+				},
+
+				_ => ()
+			}
+		}
 	}
 
-	fn build_label_map<'src>(program: &ProgramToken<'src>) -> Result<(LabelMap<'src>, usize), AssemblerError<'src>>
+	fn build_constant_map<'src>(program: &ProgramToken<'src>) -> Result<ConstantMap<'src>, AssemblerError<'src>>
 	{
+		let mut constant_map = ConstantMap::new();
+
+		// Collect the constant definitions.
+		// Defining the same constant twice is an error.
+		for stmt in program.0.iter()
+		{
+			if let Some(StatementContentToken::Constant(ConstantDefinitionToken(name, word))) = stmt.content
+			{
+				match constant_map.entry(name)
+				{
+					hash_map::Entry::Occupied(_) 	=> return Err(LabelError::new(stmt.line_number, LabelErrorType::Duplicate(name)).into()),
+					hash_map::Entry::Vacant(entry) 	=> { entry.insert((stmt.line_number, word)); },
+				}
+			}
+		}
+
+		// Constants and local labels share a namespace.
+		// Reject every label definition that collides with a constant:
+		for stmt in program.0.iter()
+		{
+			for &LabelDefinitionToken(LabelIdentifierToken(_, name)) in stmt.label_defs.iter()
+			{
+				if constant_map.contains_key(name)
+				{
+					return Err(LabelError::new(stmt.line_number, LabelErrorType::CollidesWithConstant(name)).into());
+				}
+			}
+		}
+
+		Ok(constant_map)
+	}
+
+	fn resolve_constants<'src>(program: &mut ProgramToken<'src>, constant_map: &ConstantMap<'src>) -> Result<(), AssemblerError<'src>>
+	{
+		for stmt in program.0.iter_mut()
+		{
+			let line_number = stmt.line_number;
+
+			// Replace a value token by the word of its constant (an offset is folded into the word like in "10 - 1"):
+			let resolve_value = |value: &mut ValueToken<'src>| -> Result<(), AssemblerError<'src>>
+			{
+				let (name, offset) = match *value
+				{
+					ValueToken::Constant(name) 					=> (name, 0),
+					ValueToken::ConstantOffset(name, offset) 	=> (name, offset),
+					ValueToken::Word(_) 						=> return Ok(()),
+				};
+
+				let &(_, word) = constant_map.get(name).ok_or_else(|| LabelError::new(line_number, LabelErrorType::ConstantNotResolved(name)))?;
+				let word = word.offset(offset).ok_or_else(|| OperandError::new(line_number, OperandErrorType::ValueOutOfRange(word.0, offset)))?;
+				*value = ValueToken::Word(word);
+
+				Ok(())
+			};
+
+			// Address tokens refer to constants via local labels without prefix.
+			// Those are replaced by plain addresses (an offset is folded into the word):
+			let resolve_addr = |addr: &mut AddressToken<'src>| -> Result<(), OperandError>
+			{
+				let (name, offset) = match *addr
+				{
+					AddressToken::Label(LabelIdentifierToken(None, name)) 				=> (name, 0),
+					AddressToken::LabelOffset(LabelIdentifierToken(None, name), offset) => (name, offset),
+					_ 																	=> return Ok(()),
+				};
+
+				if let Some(&(_, word)) = constant_map.get(name)
+				{
+					// Plain constants may name any address (e. g. device addresses), but offsets are only valid inside of linear memory:
+					let word = if offset == 0 { word } else { WordToken(ObjectCode::apply_offset(word.0, offset, line_number)?, word.1, false) };
+					*addr = AddressToken::Address(word);
+				}
+
+				Ok(())
+			};
+
+			match stmt.content.as_mut()
+			{
+				Some(StatementContentToken::Data(DataToken(word, times))) =>
+				{
+					resolve_value(word)?;
+
+					if let Some(times) = times
+					{
+						resolve_value(times)?;
+						ObjectCode::check_repetition(*times, line_number)?;
+					}
+				},
+
+				Some(StatementContentToken::Instruction(instruction)) => match instruction
+				{
+					InstructionToken::Add(a) 				|
+					InstructionToken::And(a) 				|
+					InstructionToken::Or(a) 				|
+					InstructionToken::Xor(a) 				|
+					InstructionToken::LoadValue(a) 			|
+					InstructionToken::StoreValue(a) 		|
+					InstructionToken::Jump(a) 				|
+					InstructionToken::JumpIfNegative(a) 	|
+					InstructionToken::JumpIfZero(a) 		|
+					InstructionToken::JumpIfEqual(a) 		|
+					InstructionToken::Equals(a) 			|
+					InstructionToken::Compare(a) 			|
+					InstructionToken::Subtract(a) 			=> resolve_addr(a)?,
+					InstructionToken::LoadConstant(v) 		|
+					InstructionToken::RotateRight(v) 		|
+					InstructionToken::RotateLeft(v) 		=> resolve_value(v)?,
+					_ 										=> (),
+				},
+
+				_ => ()
+			}
+		}
+
+		Ok(())
+	}
+
+	fn build_label_map<'src>(program: &ProgramToken<'src>, options: &AssembleOptions, diagnostics: &mut Vec<Diagnostics<'src>>) -> Result<(LabelMap<'src>, usize), AssemblerError<'src>>
+	{
+		let linear_words = options.linear_words;
+
 		let mut label_map = LabelMap::new();
 
 		// Iterate through the program statements.
@@ -188,6 +704,9 @@ impl ObjectCode
 		// Use a 64-bit value to detect overflows.
 		let mut number_of_words: u64 = 0;
 
+		// The enclosing label of scoped labels:
+		let mut scope = None;
+
 		for stmt in program.0.iter()
 		{
 			// Iterate through the statement's label definitions.
@@ -203,9 +722,21 @@ impl ObjectCode
 					}
 				}
 
+				// Labels like "add:" are almost always a mistake:
+				if !options.allow_keyword_labels && KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(name))
+				{
+					diagnostics.push(Diagnostics::new(stmt.line_number, DiagnosticsType::ShadowsMnemonic(name)));
+				}
+
+				// A label without dot opens a new scope (including itself):
+				if !ObjectCode::is_scoped(name)
+				{
+					scope = Some(name);
+				}
+
 				// Try to insert the label into our hashmap.
 				// We have another error case if it is already present.
-				match label_map.entry(name)
+				match label_map.entry(ObjectCode::label_key(scope, name, stmt.line_number)?)
 				{
 					hash_map::Entry::Occupied(_) 	=> return Err(LabelError::new(stmt.line_number, LabelErrorType::Duplicate(name)).into()),
 					hash_map::Entry::Vacant(entry) 	=>
@@ -214,7 +745,7 @@ impl ObjectCode
 						// Only validating the number of words at the increment after the loop is not enough:
 						// A program that fills the complete linear memory of the MiMA is totally valid.
 						// But if it is followed by a label, that label has an invalid address.
-						if number_of_words >= (LINEAR_ADDRESS_SPACE_WORDS as u64)
+						if number_of_words >= (linear_words as u64)
 						{
 							return Err(LabelError::new(stmt.line_number, LabelErrorType::BehindFullMemory(name)).into());
 						}
@@ -226,57 +757,566 @@ impl ObjectCode
 				}
 			}
 
-			// Increment the number of words and check if it is still valid:
+			// Increment the number of words and check if it is still valid.
+			// Statements without words (e. g. "dat 0 times 0") do not advance the address.
 			number_of_words += stmt.required_words() as u64;
 
-			if number_of_words > (LINEAR_ADDRESS_SPACE_WORDS as u64)
+			if number_of_words > (linear_words as u64)
 			{
-				return Err(AssemblerError::OverflowError(stmt.line_number));
+				return Err(AssemblerError::OverflowError(None, stmt.line_number, number_of_words, linear_words));
 			}
 		}
 
 		Ok((label_map, number_of_words as usize))
 	}
 
-	fn find_unused_labels<'src>(program: &ProgramToken, mut label_map: LabelMap<'src>, diagnostics: &mut Vec<Diagnostics<'src>>)
+	// Add the offset of a label reference to the address of the label.
+	// The target must stay inside the linear address space:
+	fn apply_offset(address: Word, offset: i32, line_number: usize) -> Result<Word, OperandError>
 	{
-		// Iterate another time through the statements.
-		// Remove every local label we encounter from the label map.
+		let target = (address.0 as i64) + (offset as i64);
+
+		if (0..(LINEAR_ADDRESS_SPACE_WORDS as i64)).contains(&target)
+		{
+			Ok(Word(target as u32))
+		}
+		else
+		{
+			Err(OperandError::new(line_number, OperandErrorType::OffsetOutOfRange(address, offset)))
+		}
+	}
+
+	fn is_scoped(name: &str) -> bool
+	{
+		name.starts_with('.')
+	}
+
+	// Build the key of a local label that is defined or referenced inside the given scope:
+	fn label_key<'src>(scope: Option<&'src str>, name: &'src str, line_number: usize) -> Result<LabelKey<'src>, LabelError<'src>>
+	{
+		match (ObjectCode::is_scoped(name), scope)
+		{
+			(false, _) 				=> Ok((None, name)),
+			(true, Some(scope)) 	=> Ok((Some(scope), name)),
+			(true, None) 			=> Err(LabelError::new(line_number, LabelErrorType::NoEnclosingScope(name))),
+		}
+	}
+
+	// The passes after "build_label_map" track the scope statement by statement.
+	// References inside a statement are resolved in the scope after its label definitions:
+	fn enter_scope<'src>(scope: &mut Option<&'src str>, stmt: &StatementToken<'src>)
+	{
+		for &LabelDefinitionToken(LabelIdentifierToken(_, name)) in stmt.label_defs.iter()
+		{
+			if !ObjectCode::is_scoped(name)
+			{
+				*scope = Some(name);
+			}
+		}
+	}
+
+	// Make sure that an operand literal fits into a payload field of the given width.
+	// Negative literals are encoded in 2's complement and masked to the field (e. g. "ldc -1" loads 0x0FFFFFFF unless the MiMA sign-extends constants).
+	// Large unsigned decimals get a dedicated error because they are most likely meant to be negative.
+	pub(crate) fn check_operand<'src>(token: WordToken, bits: u32, line_number: usize) -> Result<Word, AssemblerError<'src>>
+	{
+		let WordToken(word, radix, negative) = token;
+		let mask = (1u32 << bits) - 1;
+
+		// The smallest negative number of the field (2's complement), e. g. 0xF8000000 for 28 bits:
+		let min_negative = !(mask >> 1);
+
+		if (!negative && (word.0 <= mask)) || (negative && (word.0 >= min_negative))
+		{
+			Ok(Word(word.0 & mask))
+		}
+		else if (radix == Radix::Decimal) && !negative && (word.0 >= min_negative)
+		{
+			Err(OperandError::new(line_number, OperandErrorType::UnsignedDecimal(word, bits)).into())
+		}
+		else
+		{
+			Err(OperandError::new(line_number, OperandErrorType::TooLarge(word, bits)).into())
+		}
+	}
+
+	// Make sure that an address literal fits into the payload of a basic instruction.
+	// Unlike values (see above), addresses are not wrapped: A negative address is an error.
+	pub(crate) fn check_address<'src>(token: WordToken, line_number: usize) -> Result<Word, AssemblerError<'src>>
+	{
+		let WordToken(word, _, negative) = token;
+
+		if negative
+		{
+			Err(OperandError::new(line_number, OperandErrorType::NegativeAddress(word)).into())
+		}
+		else if (word.0 >> ObjectCode::BASIC_PAYLOAD_BITS) != 0
+		{
+			// Large unsigned decimals are not suspected to be negative numbers here (the negative address would be rejected, too):
+			Err(OperandError::new(line_number, OperandErrorType::TooLarge(word, ObjectCode::BASIC_PAYLOAD_BITS)).into())
+		}
+		else
+		{
+			ObjectCode::check_operand(token, ObjectCode::BASIC_PAYLOAD_BITS, line_number)
+		}
+	}
+
+	// Make sure that the repetition count of a data statement is not negative and does not exceed the linear memory.
+	// Without this check, "dat 0 times -1" would request 0xFFFFFFFF words.
+	fn check_repetition<'src>(times: ValueToken, line_number: usize) -> Result<(), AssemblerError<'src>>
+	{
+		match times
+		{
+			ValueToken::Word(WordToken(word, _, true)) 												=> Err(OperandError::new(line_number, OperandErrorType::NegativeRepetition(word)).into()),
+			ValueToken::Word(WordToken(word, _, _)) if word.0 as usize > LINEAR_ADDRESS_SPACE_WORDS => Err(OperandError::new(line_number, OperandErrorType::RepetitionTooLarge(word)).into()),
+			_ 																						=> Ok(()),
+		}
+	}
+
+	fn resolve_entry<'src>(program: &ProgramToken<'src>, label_map: &LabelMap<'src>) -> Result<Word, AssemblerError<'src>>
+	{
+		let mut entry = None;
+
+		// There may be at most one "start" directive.
+		// It must reference a local label or a literal address (device labels are not known before linking).
+		let mut scope = None;
+
 		for stmt in program.0.iter()
 		{
-			// Iterate through the instructions:
-			let instruction = match stmt.content
+			ObjectCode::enter_scope(&mut scope, stmt);
+
+			let addr_token = match stmt.content
 			{
-				Some(StatementContentToken::Instruction(i)) => i,
-				_ => continue,
+				Some(StatementContentToken::Start(a)) 	=> a,
+				_ 										=> continue,
+			};
+
+			if entry.is_some()
+			{
+				return Err(LabelError::new(stmt.line_number, LabelErrorType::DuplicateEntry).into());
+			}
+
+			let addr = match addr_token
+			{
+				AddressToken::Address(w) 											=> ObjectCode::check_address(w, stmt.line_number)?,
+				AddressToken::Label(LabelIdentifierToken(Some(prefix), _)) 	|
+				AddressToken::LabelOffset(LabelIdentifierToken(Some(prefix), _), _) => return Err(LabelError::new(stmt.line_number, LabelErrorType::BadEntryPrefix(prefix)).into()),
+				AddressToken::Label(LabelIdentifierToken(None, name)) 				=> match label_map.get(&ObjectCode::label_key(scope, name, stmt.line_number)?)
+				{
+					Some((_, addr)) 	=> *addr,
+					None 				=> return Err(LabelError::new(stmt.line_number, LabelErrorType::NotResolved(name)).into()),
+				},
+				AddressToken::LabelOffset(LabelIdentifierToken(None, name), offset) => match label_map.get(&ObjectCode::label_key(scope, name, stmt.line_number)?)
+				{
+					Some((_, addr)) 	=> ObjectCode::apply_offset(*addr, offset, stmt.line_number)?,
+					None 				=> return Err(LabelError::new(stmt.line_number, LabelErrorType::NotResolved(name)).into()),
+				},
 			};
 
-			// Get an address token from the instruction:
-			let addr_token = match instruction
-			{
-				InstructionToken::Add(a) 				|
-				InstructionToken::And(a) 				|
-				InstructionToken::Or(a) 				|
-				InstructionToken::Xor(a) 				|
-				InstructionToken::LoadValue(a) 			|
-				InstructionToken::StoreValue(a) 		|
-				InstructionToken::Jump(a) 				|
-				InstructionToken::JumpIfNegative(a) 	|
-				InstructionToken::Equals(a) => a,
+			entry = Some(addr);
+		}
+
+		Ok(entry.unwrap_or(Word(0)))
+	}
+
+	fn find_decimal_masks<'src>(program: &ProgramToken<'src>, diagnostics: &mut Vec<Diagnostics<'src>>)
+	{
+		// The operands of AND, OR and XOR are bit masks. Those are easier to read in hexadecimal:
+		for stmt in program.0.iter()
+		{
+			match stmt.content
+			{
+				Some(StatementContentToken::Instruction(InstructionToken::And(AddressToken::Address(WordToken(word, Radix::Decimal, _))))) 	|
+				Some(StatementContentToken::Instruction(InstructionToken::Or(AddressToken::Address(WordToken(word, Radix::Decimal, _))))) 	|
+				Some(StatementContentToken::Instruction(InstructionToken::Xor(AddressToken::Address(WordToken(word, Radix::Decimal, _))))) 	=>
+				{
+					diagnostics.push(Diagnostics::new(stmt.line_number, DiagnosticsType::MaskInDecimal(word)));
+				},
+				_ => ()
+			}
+		}
+	}
+
+	// LDC loads the 28 bit payload (sign-extended or not), so a literal is only loaded as written if it fits that range.
+	// Literals that fit neither range are rejected later on (see "check_operand").
+	fn find_changed_constants<'src>(program: &ProgramToken<'src>, sign_extend: bool, diagnostics: &mut Vec<Diagnostics<'src>>)
+	{
+		let mask = Word((1 << ObjectCode::BASIC_PAYLOAD_BITS) - 1);
+
+		for stmt in program.0.iter()
+		{
+			if let Some(StatementContentToken::Instruction(InstructionToken::LoadConstant(ValueToken::Word(WordToken(word, _, _))))) = stmt.content
+			{
+				let payload = Word(word.0 & mask.0);
+				let loaded = if sign_extend { payload.sign_extend(mask) } else { payload };
+
+				if loaded != word
+				{
+					diagnostics.push(Diagnostics::new(stmt.line_number, DiagnosticsType::ConstantChanged(word, loaded)));
+				}
+			}
+		}
+	}
+
+	fn find_undecodable_jump_targets<'src>(program: &ProgramToken<'src>, label_map: &LabelMap<'src>, raw_code: &[Word], diagnostics: &mut Vec<Diagnostics<'src>>)
+	{
+		// Collect the address ranges of all data statements:
+		let mut data_ranges: Vec<Range<u32>> = vec![];
+		let mut number_of_words = 0;
+
+		for stmt in program.0.iter()
+		{
+			let required_words = stmt.required_words() as u32;
+
+			if let Some(StatementContentToken::Data(_)) | Some(StatementContentToken::String(_)) = stmt.content
+			{
+				data_ranges.push(number_of_words..(number_of_words + required_words));
+			}
+
+			number_of_words += required_words;
+		}
+
+		// Now check the targets of all jumps that stay inside the program:
+		let mut scope = None;
+
+		for stmt in program.0.iter()
+		{
+			ObjectCode::enter_scope(&mut scope, stmt);
+
+			let addr_token = match stmt.content
+			{
+				Some(StatementContentToken::Instruction(InstructionToken::Jump(a))) 			|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfNegative(a))) 	|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfZero(a))) 		|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfEqual(a))) 		=> a,
+				_ 																		=> continue,
+			};
+
+			let target = match addr_token
+			{
+				AddressToken::Address(w) 												=> w.0,
+				AddressToken::Label(LabelIdentifierToken(None, name)) 					=> match ObjectCode::label_key(scope, name, stmt.line_number).ok().and_then(|key| label_map.get(&key))
+				{
+					Some(&(_, addr)) 	=> addr,
+					None 				=> continue,
+				},
+				AddressToken::LabelOffset(LabelIdentifierToken(None, name), offset) 	=> match ObjectCode::label_key(scope, name, stmt.line_number).ok().and_then(|key| label_map.get(&key))
+				{
+					Some(&(_, addr)) 	=> match ObjectCode::apply_offset(addr, offset, stmt.line_number)
+					{
+						Ok(addr) 	=> addr,
+						Err(_) 		=> continue,
+					},
+					None 				=> continue,
+				},
+				_ 																		=> continue,
+			};
+
+			// Is the target a data cell that does not decode to a valid instruction?
+			if data_ranges.iter().any(|range| range.contains(&target.0)) && Instruction::decode(raw_code[target.0 as usize]).is_none()
+			{
+				diagnostics.push(Diagnostics::new(stmt.line_number, DiagnosticsType::DataNotDecodable(target)));
+			}
+		}
+	}
+
+	fn find_unused_labels<'src>(program: &ProgramToken<'src>, mut label_map: LabelMap<'src>, diagnostics: &mut Vec<Diagnostics<'src>>, warnings_as_errors: bool) -> Result<(), AssemblerError<'src>>
+	{
+		// Iterate another time through the statements.
+		// Remove every local label we encounter from the label map.
+		let mut scope = None;
+
+		for stmt in program.0.iter()
+		{
+			ObjectCode::enter_scope(&mut scope, stmt);
+
+			// Get an address token from the instruction (or the entry point directive):
+			let addr_token = match stmt.content
+			{
+				Some(StatementContentToken::Instruction(InstructionToken::Add(a))) 				|
+				Some(StatementContentToken::Instruction(InstructionToken::And(a))) 				|
+				Some(StatementContentToken::Instruction(InstructionToken::Or(a))) 				|
+				Some(StatementContentToken::Instruction(InstructionToken::Xor(a))) 				|
+				Some(StatementContentToken::Instruction(InstructionToken::LoadValue(a))) 		|
+				Some(StatementContentToken::Instruction(InstructionToken::StoreValue(a))) 		|
+				Some(StatementContentToken::Instruction(InstructionToken::Jump(a))) 			|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfNegative(a))) 	|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfZero(a))) 		|
+				Some(StatementContentToken::Instruction(InstructionToken::JumpIfEqual(a))) 		|
+				Some(StatementContentToken::Instruction(InstructionToken::Equals(a))) 			|
+				Some(StatementContentToken::Instruction(InstructionToken::Compare(a))) 			|
+				Some(StatementContentToken::Instruction(InstructionToken::Subtract(a))) 		|
+				Some(StatementContentToken::Start(a)) => a,
 				_ => continue,
 			};
 
 			// If there is a local label inside, remove it from the map:
-			if let AddressToken::Label(LabelIdentifierToken(_, name)) = addr_token
+			if let AddressToken::Label(LabelIdentifierToken(_, name)) | AddressToken::LabelOffset(LabelIdentifierToken(_, name), _) = addr_token
 			{
-				label_map.remove(name);
+				if let Ok(key) = ObjectCode::label_key(scope, name, stmt.line_number)
+				{
+					label_map.remove(&key);
+				}
 			}
 		}
 
 		// Create a diagnostic entry for every remaining label (sorted by line):
-		for (name, (line_number, _)) in label_map
+		let mut unused_labels: Vec<_> = label_map.into_iter().map(|((_, name), (line_number, _))| (line_number, name)).collect();
+		unused_labels.sort_unstable();
+
+		// If warnings are errors, the first unused label fails the assembly:
+		if let (true, Some(&(line_number, name))) = (warnings_as_errors, unused_labels.first())
+		{
+			return Err(LabelError::new(line_number, LabelErrorType::Unused(name)).into());
+		}
+
+		for (line_number, name) in unused_labels
 		{
 			diagnostics.push(Diagnostics::new(line_number, DiagnosticsType::UnusedLocalLabel(name)));
 		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::unit::MemoryUnit;
+
+	fn strict() -> AssembleOptions
+	{
+		AssembleOptions { strict: true, ..AssembleOptions::default() }
+	}
+
+	#[test]
+	fn strict_accepts_jump_into_decodable_data()
+	{
+		// 0x00000003 decodes to "ADD 3":
+		let (_, diagnostics) = ObjectCode::assemble_with_options("jmp target\ntarget: dat 3\nhlt", &strict()).unwrap();
+		assert!(diagnostics.is_empty());
+	}
+
+	#[test]
+	fn strict_warns_about_jump_into_reserved_opcode()
+	{
+		let source = "jmp target\ntarget: dat 0xE0000000\nhlt";
+
+		let (_, diagnostics) = ObjectCode::assemble_with_options(source, &strict()).unwrap();
+		assert_eq!(diagnostics.len(), 1);
+		assert_eq!(diagnostics[0].line_number(), 0);
+		assert!(matches!(diagnostics[0].diag_type(), DiagnosticsType::DataNotDecodable(Word(1))));
+
+		// The check is off by default:
+		let (_, diagnostics) = ObjectCode::assemble(source).unwrap();
+		assert!(diagnostics.is_empty());
+	}
+
+	#[test]
+	fn recover_assembles_valid_statements()
+	{
+		let (object_code, _, parser_errors) = ObjectCode::assemble_recover("ldc 1\nthis is not mima\nhlt").unwrap();
+
+		assert_eq!(parser_errors.len(), 1);
+		assert_eq!(object_code.raw_code.len(), 2);
+		assert_eq!(&*object_code.source_map, &[(0, 0), (0, 2)]);
+	}
+
+	#[test]
+	fn style_lint_warns_about_decimal_masks()
+	{
+		let style_lints = AssembleOptions { style_lints: true, ..AssembleOptions::default() };
+
+		let (_, diagnostics) = ObjectCode::assemble_with_options("and 15\nhlt", &style_lints).unwrap();
+		assert_eq!(diagnostics.len(), 1);
+		assert!(matches!(diagnostics[0].diag_type(), DiagnosticsType::MaskInDecimal(Word(15))));
+
+		let (_, diagnostics) = ObjectCode::assemble_with_options("and 0x0F\nhlt", &style_lints).unwrap();
+		assert!(diagnostics.is_empty());
+
+		// The lint is opt-in:
+		let (_, diagnostics) = ObjectCode::assemble("and 15\nhlt").unwrap();
+		assert!(diagnostics.is_empty());
+	}
+
+	#[test]
+	fn symbols_are_resolved_by_linking()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 1\nstv uart.data\nhlt").unwrap();
+
+		let symbols: Vec<_> = code.symbols().collect();
+		assert_eq!(symbols.len(), 1);
+		assert_eq!((symbols[0].label.prefix.as_str(), symbols[0].label.name.as_str()), ("uart", "data"));
+		assert_eq!(symbols[0].instruction_address, Word(1));
+		assert!(!symbols[0].resolved);
+
+		let mut memory_unit = MemoryUnit::new();
+		memory_unit.register_device_labels("uart", Word(0xC000000), &[("data", Word(0))]);
+		memory_unit.load_code(&code).unwrap();
+
+		assert!(code.symbols_in(memory_unit.linear_memory()).all(|symbol| symbol.resolved));
+	}
+
+	#[test]
+	fn symbols_beyond_the_code_are_unresolved()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 1\nstv uart.data\nhlt").unwrap();
+
+		let mut memory_unit = MemoryUnit::new();
+		memory_unit.register_device_labels("uart", Word(0xC000000), &[("data", Word(0))]);
+		memory_unit.load_code(&code).unwrap();
+
+		// The symbol sits at address 1, which the truncated code does not contain:
+		let truncated = &memory_unit.linear_memory()[..1];
+		assert!(code.symbols_in(truncated).all(|symbol| !symbol.resolved));
+		assert!(code.symbols_in(&[]).all(|symbol| !symbol.resolved));
+	}
+
+	#[test]
+	fn from_instructions_checks_payloads()
+	{
+		let code = ObjectCode::from_instructions(&[Instruction::LoadConstant(Word(0x0FFFFFFF)), Instruction::RotateRight(Word(3)), Instruction::Illegal(Word(0xE0000001)), Instruction::Halt]).unwrap();
+		assert_eq!(&*code.raw_code, &[Word(0x6FFFFFFF), Word(0xF2000003), Word(0xE0000001), Word(0xF0000000)]);
+		assert_eq!(code.sections, vec![Section { kind: SectionKind::Code, range: Word(0)..Word(4) }]);
+
+		// Oversized payloads are errors instead of panics:
+		let err = ObjectCode::from_instructions(&[Instruction::Halt, Instruction::Jump(Word(0x10000000))]).err().unwrap();
+		assert_eq!(err.line_number(), 1);
+		assert!(matches!(err.err_type(), OperandErrorType::TooLarge(Word(0x10000000), 28)));
+
+		let err = ObjectCode::from_instructions(&[Instruction::RotateLeft(Word(0x01000000))]).err().unwrap();
+		assert!(matches!(err.err_type(), OperandErrorType::TooLarge(Word(0x01000000), 24)));
+	}
+
+	fn operand_error(source: &str) -> OperandError
+	{
+		match ObjectCode::assemble(source)
+		{
+			Err(AssemblerError::OperandError(err)) 	=> err,
+			Err(err) 								=> panic!("Expected an operand error, got \"{:}\".", err),
+			Ok(_) 									=> panic!("Expected an operand error for \"{:}\".", source),
+		}
+	}
+
+	#[test]
+	fn large_unsigned_decimal_operand()
+	{
+		assert!(matches!(operand_error("ldc 4294967295").err_type(), OperandErrorType::UnsignedDecimal(Word(0xFFFFFFFF), 28)));
+		assert!(operand_error("ldc 4294967295").err_type().to_string().contains("please write \"-1\""));
+
+		// Addresses cannot be negative, so there is no hint to write "-1":
+		assert!(matches!(operand_error("add 4294967295").err_type(), OperandErrorType::TooLarge(Word(0xFFFFFFFF), 28)));
+		assert_eq!(operand_error("add 4294967295").err_type().to_string(), "The operand 0xFFFFFFFF does not fit into the 28 bit payload.");
+
+		// Hex literals are not suspected to be negative numbers:
+		assert!(matches!(operand_error("ldc 0xFFFFFFFF").err_type(), OperandErrorType::TooLarge(Word(0xFFFFFFFF), 28)));
+	}
+
+	#[test]
+	fn negative_operands()
+	{
+		// Addresses are never wrapped:
+		assert!(matches!(operand_error("add -1").err_type(), OperandErrorType::NegativeAddress(Word(0xFFFFFFFF))));
+
+		// Constants are masked to the payload in 2's complement:
+		let (code, _) = ObjectCode::assemble("ldc -1").unwrap();
+		assert_eq!(code.raw_code[0], Instruction::LoadConstant(Word(0x0FFFFFFF)).into());
+	}
+
+	#[test]
+	fn largest_operand_fits()
+	{
+		let (code, _) = ObjectCode::assemble("add 0x0FFFFFFF").unwrap();
+		assert_eq!(code.raw_code[0], Instruction::Add(Word(0x0FFFFFFF)).into());
+	}
+
+	fn address_of(object_code: &ObjectCode, name: &str) -> Word
+	{
+		object_code.local_symbols.iter().find(|(label, _)| label == name).map(|&(_, addr)| addr).unwrap()
+	}
+
+	#[test]
+	fn zero_repetitions_emit_no_words()
+	{
+		let (object_code, _) = ObjectCode::assemble("dat 5 times 0\nldc 1\ndat 0x10 times 0\nhlt").unwrap();
+
+		assert_eq!(object_code.raw_code.len(), 2);
+		assert!(matches!(Instruction::from(object_code.raw_code[0]), Instruction::LoadConstant(Word(1))));
+		assert!(matches!(Instruction::from(object_code.raw_code[1]), Instruction::Halt));
+		assert_eq!(&*object_code.source_map, &[(0, 1), (0, 3)]);
+	}
+
+	#[test]
+	fn label_of_zero_repetitions_points_to_next_statement()
+	{
+		let (object_code, _) = ObjectCode::assemble("jmp empty\nempty: dat 7 times 0\nnext: hlt\nlast: dat 1 times 0").unwrap();
+
+		assert_eq!(address_of(&object_code, "empty"), Word(1));
+		assert_eq!(address_of(&object_code, "next"), Word(1));
+		assert!(matches!(Instruction::from(object_code.raw_code[0]), Instruction::Jump(Word(1))));
+
+		// A label at the end points behind the program:
+		assert_eq!(address_of(&object_code, "last"), Word(2));
+		assert_eq!(object_code.raw_code.len(), 2);
+	}
+
+	#[test]
+	fn bad_repetition_counts()
+	{
+		assert!(matches!(operand_error("dat 0 times -1").err_type(), OperandErrorType::NegativeRepetition(Word(0xFFFFFFFF))));
+		assert!(matches!(operand_error("dat 0 times 0xFFFFFFFF").err_type(), OperandErrorType::RepetitionTooLarge(Word(0xFFFFFFFF))));
+
+		// Hexadecimal and binary counts are fine:
+		let (object_code, _) = ObjectCode::assemble("dat 0 times 0x10\ndat 1 times 0b101").unwrap();
+		assert_eq!(object_code.raw_code.len(), 21);
+	}
+
+	fn changed(source: &str, options: &AssembleOptions) -> Vec<(Word, Word)>
+	{
+		let (_, diagnostics) = ObjectCode::assemble_with_options(source, options).unwrap();
+
+		diagnostics.iter().filter_map(|diag| match diag.diag_type()
+		{
+			DiagnosticsType::ConstantChanged(w, l) 	=> Some((*w, *l)),
+			_ 										=> None,
+		}).collect()
+	}
+
+	#[test]
+	fn constants_that_are_not_loaded_as_written()
+	{
+		let sign_extend = AssembleOptions { sign_extend_constants: true, ..AssembleOptions::default() };
+
+		// Without sign extension, only negative constants change:
+		assert_eq!(changed("ldc -1\nldc 0x0FFFFFFF\nldc 5\nhlt", &AssembleOptions::default()), [(Word(0xFFFFFFFF), Word(0x0FFFFFFF))]);
+
+		// With sign extension, only positive constants with bit 27 set change:
+		assert_eq!(changed("ldc -1\nldc 0x0FFFFFFF\nldc 5\nhlt", &sign_extend), [(Word(0x0FFFFFFF), Word(0xFFFFFFFF))]);
+		assert!(changed("ldc -0x8000000\nldc 0x7FFFFFF\nhlt", &sign_extend).is_empty());
+
+		// Positive literals above 28 bits are rejected:
+		assert!(matches!(operand_error("ldc 0x10000000").err_type(), OperandErrorType::TooLarge(Word(0x10000000), 28)));
+	}
+
+	#[test]
+	fn constants_with_offsets()
+	{
+		let (object_code, _) = ObjectCode::assemble("N: equ 5\nldc N+1\nldc N - 6\nadd N-1\ndat N-1 times N - 3\nhlt").unwrap();
+
+		assert!(matches!(Instruction::from(object_code.raw_code[0]), Instruction::LoadConstant(Word(6))));
+		assert!(matches!(Instruction::from(object_code.raw_code[1]), Instruction::LoadConstant(Word(0x0FFFFFFF))));
+		assert!(matches!(Instruction::from(object_code.raw_code[2]), Instruction::Add(Word(4))));
+		assert_eq!(&object_code.raw_code[3..5], &[Word(4), Word(4)]);
+		assert_eq!(object_code.raw_code.len(), 6);
+
+		// The result must still be a representable literal:
+		assert!(matches!(operand_error("N: equ 0xFFFFFFFF\ndat N+1").err_type(), OperandErrorType::ValueOutOfRange(Word(0xFFFFFFFF), 1)));
+	}
+
+	#[test]
+	fn offset_out_of_range_shows_the_target()
+	{
+		assert_eq!(operand_error("N: equ 1\nadd N - 5").err_type().to_string(), "The address 0x00000001 - 5 (= -4) is outside of the linear address space.");
 	}
 }
@@ -0,0 +1,69 @@
+use std::fmt::Write;
+use crate::types::*;
+
+// Disassemble raw code into assembly source text (one statement per word).
+// Words that cannot be reproduced from their decoded instruction (reserved opcodes, ignored payload bits, ...) are emitted as data.
+// This way, assembling the result yields the original words again.
+pub fn disassemble(code: &[Word]) -> String
+{
+	let mut source = String::new();
+
+	for (address, &word) in code.iter().enumerate()
+	{
+		let (statement, annotation) = render_word(word);
+
+		writeln!(source, "{:<24} # {:}{:}", statement, Word(address as u32), annotation).expect("Writing to a string must not fail.");
+	}
+
+	source
+}
+
+// Render a single word as statement (plus an annotation that explains why it has been emitted as data):
+pub(crate) fn render_word(word: Word) -> (String, String)
+{
+	match Instruction::decode(word)
+	{
+		Some(instruction) if Word::from(instruction) == word 	=> (format!("{:}", instruction), String::new()),
+		Some(instruction) 										=> (format!("DAT {:}", word), format!(" (non-canonical {:})", instruction.format_opcode())),
+		None if (word.0 >> 28) == 0x0F 							=> (format!("DAT {:}", word), format!(" (unknown extended opcode 0x{:X})", (word.0 >> 24) & 0x0F)),
+		None 													=> (format!("DAT {:}", word), format!(" (unknown opcode 0x{:X})", word.0 >> 28)),
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::assembly::ObjectCode;
+
+	#[test]
+	fn round_trip()
+	{
+		let source = "loop: ldv 0x100\nadd 0x101\nstv 0x100\nrar 3\nnot\njmn loop\nhlt\ndat 0xE0000000\ndat 0x30000001";
+		let (original, _) = ObjectCode::assemble(source).unwrap();
+
+		let disassembly = disassemble(&original.raw_code);
+		let (reassembled, _) = ObjectCode::assemble(&disassembly).unwrap();
+
+		assert_eq!(original.raw_code, reassembled.raw_code);
+	}
+
+	#[test]
+	fn render_reserved_opcode_as_data()
+	{
+		let (statement, annotation) = render_word(Word(0xE0000000));
+		assert_eq!(statement, "DAT 0xE0000000");
+		assert_eq!(annotation, " (unknown opcode 0xE)");
+
+		let (statement, annotation) = render_word(Word(0xFE000000));
+		assert_eq!(statement, "DAT 0xFE000000");
+		assert_eq!(annotation, " (unknown extended opcode 0xE)");
+	}
+
+	#[test]
+	fn render_instruction_with_mnemonic()
+	{
+		assert_eq!(render_word(Instruction::Add(Word(0x10)).into()), (String::from("ADD 0x00000010"), String::new()));
+		assert_eq!(render_word(Instruction::Halt.into()), (String::from("HLT"), String::new()));
+	}
+}
@@ -2,10 +2,13 @@ use std::convert::From;
 use std::error::Error;
 use std::fmt;
 use crate::types::*;
+use crate::assembly::include::{FileId, SourceSet};
 
 // Diagnostics (warnings) help users to improve their otherwise correct code:
+// The file is only known for programs that have been read by a "SourceSet" (it is None for plain strings).
 pub struct Diagnostics<'src>
 {
+	file: Option<FileId>,
 	line_number: usize,
 	diag_type: DiagnosticsType<'src>,
 }
@@ -16,16 +19,57 @@ impl<'src> Diagnostics<'src>
 	{
 		Diagnostics
 		{
+			file: None,
 			line_number,
 			diag_type,
 		}
 	}
+
+	pub fn file(&self) -> Option<FileId>
+	{
+		self.file
+	}
+
+	pub fn line_number(&self) -> usize
+	{
+		self.line_number
+	}
+
+	pub fn diag_type(&self) -> &DiagnosticsType<'src>
+	{
+		&self.diag_type
+	}
+
+	// Move the line number from the spliced text to its original file:
+	pub(crate) fn locate(&mut self, sources: &SourceSet)
+	{
+		let (file, line_number) = sources.origin(self.line_number);
+		self.file = Some(file);
+		self.line_number = line_number;
+	}
+}
+
+// Prefix a location with the file (if it is known):
+fn write_file(f: &mut fmt::Formatter, file: Option<FileId>) -> fmt::Result
+{
+	match file
+	{
+		Some(file) 	=> write!(f, "[File {:}] ", file),
+		None 		=> Ok(()),
+	}
+}
+
+// Offsets are written like in the source (e. g. "- 5"):
+fn offset_sign(offset: i32) -> char
+{
+	if offset < 0 { '-' } else { '+' }
 }
 
 impl<'src> fmt::Display for Diagnostics<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
+		write_file(f, self.file)?;
 		write!(f, "[Line {:}] Warning: {:}", self.line_number, self.diag_type)
 	}
 }
@@ -33,6 +77,10 @@ impl<'src> fmt::Display for Diagnostics<'src>
 pub enum DiagnosticsType<'src>
 {
 	UnusedLocalLabel(&'src str),
+	DataNotDecodable(Word),
+	MaskInDecimal(Word),
+	ShadowsMnemonic(&'src str),
+	ConstantChanged(Word, Word),
 }
 
 impl<'src> fmt::Display for DiagnosticsType<'src>
@@ -41,46 +89,105 @@ impl<'src> fmt::Display for DiagnosticsType<'src>
 	{
 		match self
 		{
-			DiagnosticsType::UnusedLocalLabel(s) => write!(f, "The local label \"{:}\" is never referenced.", s)
+			DiagnosticsType::UnusedLocalLabel(s) => write!(f, "The local label \"{:}\" is never referenced.", s),
+			DiagnosticsType::DataNotDecodable(a) => write!(f, "The jump target {:} is a data cell that does not decode to a valid instruction.", a),
+			DiagnosticsType::MaskInDecimal(w) => write!(f, "The bit mask {:} is written in decimal (hexadecimal is easier to read).", w),
+			DiagnosticsType::ShadowsMnemonic(s) => write!(f, "The label \"{:}\" is named like a mnemonic or directive.", s),
+			DiagnosticsType::ConstantChanged(w, l) => write!(f, "The constant {:} is loaded as {:} (check the sign extension of constants).", w, l),
 		}
 	}
 }
 
 // Parsing is error-prone.
 // We use this custom error type to return some diagnostics.
-#[derive(Debug)]
+// The column is the character offset of the failing token inside its line.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub struct ParserError<'src>
 {
+	file: Option<FileId>,
 	line_number: usize,
+	column: usize,
 	token: Option<&'src str>,
+	err_type: ParserErrorType,
 }
 
 impl<'src> ParserError<'src>
 {
-	pub fn new(line_number: usize, token: Option<&'src str>) -> ParserError
+	pub fn new(line_number: usize, column: usize, token: Option<&'src str>, err_type: ParserErrorType) -> ParserError<'src>
 	{
 		ParserError
 		{
+			file: None,
 			line_number,
+			column,
 			token,
+			err_type,
 		}
 	}
+
+	pub fn file(&self) -> Option<FileId>
+	{
+		self.file
+	}
+
+	pub fn line_number(&self) -> usize
+	{
+		self.line_number
+	}
+
+	pub fn column(&self) -> usize
+	{
+		self.column
+	}
+
+	pub fn token(&self) -> Option<&'src str>
+	{
+		self.token
+	}
+
+	pub fn err_type(&self) -> ParserErrorType
+	{
+		self.err_type
+	}
+
+	pub(crate) fn locate(&mut self, sources: &SourceSet)
+	{
+		let (file, line_number) = sources.origin(self.line_number);
+		self.file = Some(file);
+		self.line_number = line_number;
+	}
 }
 
 impl<'src> fmt::Display for ParserError<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
-		write!(f, "[Line {:03}] Error: Failed to parse token starting at \"{:32}\".", self.line_number, self.token.unwrap_or("???"))
+		write_file(f, self.file)?;
+
+		match self.err_type
+		{
+			ParserErrorType::BadToken 			=> write!(f, "[Line {:03}, Col {:03}] Error: Failed to parse token starting at \"{:32}\".", self.line_number, self.column, self.token.unwrap_or("???")),
+			ParserErrorType::LiteralOutOfRange 	=> write!(f, "[Line {:03}, Col {:03}] Error: The literal starting at \"{:32}\" is out of the range [-2147483648, 4294967295].", self.line_number, self.column, self.token.unwrap_or("???")),
+		}
 	}
 }
 
 impl<'src> Error for ParserError<'src> { }
 
+// What went wrong while parsing?
+// Numeric literals must be representable as machine word (either as signed or as unsigned number).
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum ParserErrorType
+{
+	BadToken,
+	LiteralOutOfRange,
+}
+
 // A wrong usage of a label in a syntactically correct program:
 #[derive(Debug)]
 pub struct LabelError<'src>
 {
+	file: Option<FileId>,
 	line_number: usize,
 	err_type: LabelErrorType<'src>,
 }
@@ -91,16 +198,35 @@ impl<'src> LabelError<'src>
 	{
 		LabelError
 		{
+			file: None,
 			line_number,
 			err_type,
 		}
 	}
+
+	pub fn file(&self) -> Option<FileId>
+	{
+		self.file
+	}
+
+	pub fn line_number(&self) -> usize
+	{
+		self.line_number
+	}
+
+	pub(crate) fn locate(&mut self, sources: &SourceSet)
+	{
+		let (file, line_number) = sources.origin(self.line_number);
+		self.file = Some(file);
+		self.line_number = line_number;
+	}
 }
 
 impl<'src> fmt::Display for LabelError<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
+		write_file(f, self.file)?;
 		write!(f, "[Line {:}] {:}", self.line_number, self.err_type)
 	}
 }
@@ -114,6 +240,13 @@ pub enum LabelErrorType<'src>
 	Duplicate(&'src str),
 	BehindFullMemory(&'src str),
 	NotResolved(&'src str),
+	ConstantNotResolved(&'src str),
+	CollidesWithConstant(&'src str),
+	BadEntryPrefix(&'src str),
+	DuplicateEntry,
+	Unused(&'src str),
+	NoEnclosingScope(&'src str),
+	DeviceLabelOffset(&'src str, &'src str),
 }
 
 impl<'src> fmt::Display for LabelErrorType<'src>
@@ -130,6 +263,111 @@ impl<'src> fmt::Display for LabelErrorType<'src>
 			LabelErrorType::Duplicate(s) => write!(f, "The label definition \"{:}\" is a duplicate.", s),
 			LabelErrorType::BehindFullMemory(s) => write!(f, "The label definition \"{:}\" is located at an invalid address.", s),
 			LabelErrorType::NotResolved(s) => write!(f, "The label reference \"{:}\" cannot be resolved.", s),
+			LabelErrorType::ConstantNotResolved(s) => write!(f, "The constant reference \"{:}\" cannot be resolved.", s),
+			LabelErrorType::CollidesWithConstant(s) => write!(f, "The label definition \"{:}\" collides with a constant of the same name.", s),
+			LabelErrorType::BadEntryPrefix(s) => write!(f, "The entry point cannot be located in the device namespace \"{:}\".", s),
+			LabelErrorType::DuplicateEntry => write!(f, "The entry point has already been defined by another \"start\" directive."),
+			LabelErrorType::Unused(s) => write!(f, "The local label \"{:}\" is never referenced (warnings are treated as errors).", s),
+			LabelErrorType::DeviceLabelOffset(p, s) => write!(f, "The device label \"{:}.{:}\" is resolved while linking and cannot have an offset.", p, s),
+			LabelErrorType::NoEnclosingScope(s) => write!(f, "The scoped label \"{:}\" is not preceded by a label without dot that could enclose it.", s),
+		}
+	}
+}
+
+// An operand literal that does not fit into the payload field of its instruction:
+#[derive(Debug)]
+pub struct OperandError
+{
+	file: Option<FileId>,
+	line_number: usize,
+	err_type: OperandErrorType,
+}
+
+impl OperandError
+{
+	pub fn new(line_number: usize, err_type: OperandErrorType) -> OperandError
+	{
+		OperandError
+		{
+			file: None,
+			line_number,
+			err_type,
+		}
+	}
+
+	pub fn file(&self) -> Option<FileId>
+	{
+		self.file
+	}
+
+	pub fn line_number(&self) -> usize
+	{
+		self.line_number
+	}
+
+	pub fn err_type(&self) -> &OperandErrorType
+	{
+		&self.err_type
+	}
+
+	pub(crate) fn locate(&mut self, sources: &SourceSet)
+	{
+		let (file, line_number) = sources.origin(self.line_number);
+		self.file = Some(file);
+		self.line_number = line_number;
+	}
+}
+
+impl fmt::Display for OperandError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write_file(f, self.file)?;
+		write!(f, "[Line {:}] {:}", self.line_number, self.err_type)
+	}
+}
+
+impl Error for OperandError { }
+
+// The payload variants hold the operand word and the width of the payload field in bits:
+#[derive(Debug)]
+pub enum OperandErrorType
+{
+	// A large unsigned decimal that would fit as negative number (e. g. "4294967295" instead of "-1"):
+	UnsignedDecimal(Word, u32),
+	TooLarge(Word, u32),
+
+	// Addresses are checked instead of wrapped, so a negative literal is an error (the word holds its 2's complement):
+	NegativeAddress(Word),
+
+	// Repetition counts of data statements must be in [0, LINEAR_ADDRESS_SPACE_WORDS]:
+	NegativeRepetition(Word),
+	RepetitionTooLarge(Word),
+
+	// The target of "label + offset" (label address and offset) is outside of the linear address space:
+	OffsetOutOfRange(Word, i32),
+
+	// The result of "constant + offset" (constant word and offset) is not representable as a literal:
+	ValueOutOfRange(Word, i32),
+}
+
+impl fmt::Display for OperandErrorType
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			OperandErrorType::UnsignedDecimal(w, bits) =>
+			{
+				write!(f, "The operand {:} does not fit into the {:} bit payload.", w.0, bits)?;
+				write!(f, " If you meant the negative number, please write \"-{:}\" (or \"0x{:X}\").", w.0.wrapping_neg(), w.0 & ((1u32 << bits) - 1))
+			},
+			OperandErrorType::TooLarge(w, bits) => write!(f, "The operand {:} does not fit into the {:} bit payload.", w, bits),
+			OperandErrorType::NegativeAddress(w) => write!(f, "The address -{:} is negative.", w.0.wrapping_neg()),
+			OperandErrorType::NegativeRepetition(w) => write!(f, "The repetition count -{:} is negative.", w.0.wrapping_neg()),
+			OperandErrorType::RepetitionTooLarge(w) => write!(f, "The repetition count {:} exceeds the {:} words of linear memory.", w.0, LINEAR_ADDRESS_SPACE_WORDS),
+			OperandErrorType::OffsetOutOfRange(w, offset) => write!(f, "The address {:} {:} {:} (= {:}) is outside of the linear address space.", w, offset_sign(*offset), offset.unsigned_abs(), (w.0 as i64) + (*offset as i64)),
+			OperandErrorType::ValueOutOfRange(w, offset) => write!(f, "The value {:} {:} {:} is outside of [{:}, {:}].", w, offset_sign(*offset), offset.unsigned_abs(), i32::MIN, u32::MAX),
 		}
 	}
 }
@@ -140,7 +378,29 @@ pub enum AssemblerError<'src>
 {
 	ParserError(ParserError<'src>),
 	LabelError(LabelError<'src>),
-	OverflowError(usize),
+	OperandError(OperandError),
+	// The statement in the given line makes the program exceed the linear memory (number of words needed and limit):
+	OverflowError(Option<FileId>, usize, u64, usize),
+}
+
+impl<'src> AssemblerError<'src>
+{
+	// Move the line number from the spliced text to its original file:
+	pub(crate) fn locate(&mut self, sources: &SourceSet)
+	{
+		match self
+		{
+			AssemblerError::ParserError(err) 						=> err.locate(sources),
+			AssemblerError::LabelError(err) 						=> err.locate(sources),
+			AssemblerError::OperandError(err) 						=> err.locate(sources),
+			AssemblerError::OverflowError(file, line_number, _, _) 	=>
+			{
+				let (origin_file, origin_line) = sources.origin(*line_number);
+				*file = Some(origin_file);
+				*line_number = origin_line;
+			},
+		}
+	}
 }
 
 impl<'src> From<ParserError<'src>> for AssemblerError<'src>
@@ -159,15 +419,28 @@ impl<'src> From<LabelError<'src>> for AssemblerError<'src>
 	}
 }
 
+impl<'src> From<OperandError> for AssemblerError<'src>
+{
+	fn from(err: OperandError) -> Self
+	{
+		AssemblerError::OperandError(err)
+	}
+}
+
 impl<'src> fmt::Display for AssemblerError<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
 		match self
 		{
-			AssemblerError::ParserError(err) 			=> write!(f, "{:}", err),
-			AssemblerError::LabelError(err) 			=> write!(f, "{:}", err),
-			AssemblerError::OverflowError(line_number) 	=> write!(f, "[Line {:}] The maximum number of machine words ({:}) is exceeded.", line_number, LINEAR_ADDRESS_SPACE_WORDS),
+			AssemblerError::ParserError(err) 								=> write!(f, "{:}", err),
+			AssemblerError::LabelError(err) 								=> write!(f, "{:}", err),
+			AssemblerError::OperandError(err) 								=> write!(f, "{:}", err),
+			AssemblerError::OverflowError(file, line_number, needed, limit) =>
+			{
+				write_file(f, *file)?;
+				write!(f, "[Line {:}] The program exceeds the linear memory: It needs {:} words, the limit is {:}.", line_number, needed, limit)
+			},
 		}
 	}
 }
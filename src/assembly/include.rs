@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::assembly::error::*;
+use crate::assembly::parser::include_lines;
+use crate::assembly::assembler::ObjectCode;
+
+// Files are identified by their index into the file table of a source set (the root file is 0):
+pub type FileId = usize;
+
+// A multi-file program with all "include" directives spliced away.
+// Every line of the text remembers its origin (file and line inside that file).
+pub struct SourceSet
+{
+	paths: Vec<PathBuf>,
+	text: String,
+	origins: Vec<(FileId, usize)>,
+}
+
+// This error type occurs when the files of a program cannot be read:
+#[derive(Debug)]
+pub enum IncludeError
+{
+	Io(PathBuf, io::Error),
+
+	// A file includes itself (directly or via other files). The directive is located at the given file and line:
+	Cycle(FileId, usize, PathBuf),
+}
+
+impl fmt::Display for IncludeError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			IncludeError::Io(path, err) 					=> write!(f, "Failed to read \"{:}\": {:}", path.display(), err),
+			IncludeError::Cycle(file, line_number, path) 	=> write!(f, "[File {:}] [Line {:}] Including \"{:}\" would create a cycle.", file, line_number, path.display()),
+		}
+	}
+}
+
+impl Error for IncludeError { }
+
+impl SourceSet
+{
+	// Read the root file and (recursively) splice the files it includes.
+	// Paths in "include" directives are relative to the directory of the including file.
+	pub fn read<P: AsRef<Path>>(path: P) -> Result<SourceSet, IncludeError>
+	{
+		let mut sources = SourceSet
+		{
+			paths: vec![],
+			text: String::new(),
+			origins: vec![],
+		};
+
+		sources.splice(path.as_ref(), &mut HashSet::new())?;
+		Ok(sources)
+	}
+
+	// The visited set holds the (canonical) files on the current include chain.
+	// Including one of them again would never terminate.
+	fn splice(&mut self, path: &Path, visited: &mut HashSet<PathBuf>) -> Result<(), IncludeError>
+	{
+		let io_error = |err| IncludeError::Io(path.to_path_buf(), err);
+		let content = fs::read_to_string(path).map_err(io_error)?;
+		let canonical = fs::canonicalize(path).map_err(io_error)?;
+
+		let file = self.paths.len();
+		self.paths.push(path.to_path_buf());
+		visited.insert(canonical.clone());
+
+		for (line_number, line, include) in include_lines(&content)
+		{
+			if let Some(include) = include
+			{
+				let include_path = path.parent().map_or_else(|| PathBuf::from(include), |dir| dir.join(include));
+
+				if fs::canonicalize(&include_path).is_ok_and(|canonical| visited.contains(&canonical))
+				{
+					return Err(IncludeError::Cycle(file, line_number, include_path));
+				}
+
+				self.splice(&include_path, visited)?;
+			}
+			else
+			{
+				self.text.push_str(line);
+				self.text.push('\n');
+				self.origins.push((file, line_number));
+			}
+		}
+
+		visited.remove(&canonical);
+		Ok(())
+	}
+
+	// The spliced program text (this is what the assembler parses):
+	pub fn text(&self) -> &str
+	{
+		&self.text
+	}
+
+	pub fn path(&self, file: FileId) -> &Path
+	{
+		&self.paths[file]
+	}
+
+	pub fn paths(&self) -> &[PathBuf]
+	{
+		&self.paths
+	}
+
+	// Map a line of the spliced text back to its file and line.
+	// Lines behind the end of the text (e. g. an unexpected end of input) are counted on from the last line.
+	pub fn origin(&self, line_number: usize) -> (FileId, usize)
+	{
+		match self.origins.get(line_number).or_else(|| self.origins.last())
+		{
+			Some(&(file, line)) if line_number >= self.origins.len() 	=> (file, line + 1 + line_number - self.origins.len()),
+			Some(&origin) 												=> origin,
+			None 														=> (0, line_number),
+		}
+	}
+}
+
+impl ObjectCode
+{
+	// Assemble a multi-file program.
+	// Like "assemble", but all line numbers in the errors, the diagnostics and the source map are located in the original files.
+	pub fn assemble_file(sources: &SourceSet) -> Result<(ObjectCode, Vec<Diagnostics<'_>>), AssemblerError<'_>>
+	{
+		match ObjectCode::assemble(sources.text())
+		{
+			Ok((mut object_code, mut diagnostics)) =>
+			{
+				object_code.source_map.iter_mut().for_each(|origin| *origin = sources.origin(origin.1));
+				diagnostics.iter_mut().for_each(|diag| diag.locate(sources));
+				Ok((object_code, diagnostics))
+			},
+
+			Err(mut err) =>
+			{
+				err.locate(sources);
+				Err(err)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	// Write the given files (relative path and content) into a fresh temporary directory and read the program from the first one:
+	fn read_files(name: &str, files: &[(&str, &str)]) -> Result<SourceSet, IncludeError>
+	{
+		let dir = std::env::temp_dir().join(format!("mimasim-include-{:}-{:}", name, std::process::id()));
+
+		for (path, content) in files.iter()
+		{
+			let path = dir.join(path);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, content).unwrap();
+		}
+
+		let sources = SourceSet::read(dir.join(files[0].0));
+		fs::remove_dir_all(&dir).unwrap();
+
+		sources
+	}
+
+	#[test]
+	fn nested_includes_are_spliced()
+	{
+		// Paths are relative to the including file:
+		let sources = read_files("nested", &[
+			("main.asm", "ldc 1\ninclude \"lib/a.asm\"\nhlt"),
+			("lib/a.asm", "include \"b.asm\"\nnot"),
+			("lib/b.asm", "nop\nnop"),
+		]).unwrap();
+
+		assert_eq!(sources.paths().len(), 3);
+		assert!(sources.path(2).ends_with("lib/b.asm"));
+		assert_eq!(sources.text(), "ldc 1\nnop\nnop\nnot\nhlt\n");
+		assert_eq!((0..5).map(|line_number| sources.origin(line_number)).collect::<Vec<_>>(), [(0, 0), (2, 0), (2, 1), (1, 1), (0, 2)]);
+
+		// The source map points into the original files:
+		let (object_code, _) = ObjectCode::assemble_file(&sources).unwrap();
+		assert_eq!(&*object_code.source_map, &[(0, 0), (2, 0), (2, 1), (1, 1), (0, 2)]);
+	}
+
+	#[test]
+	fn include_cycles_are_rejected()
+	{
+		let err = read_files("cycle", &[
+			("main.asm", "include \"a.asm\""),
+			("a.asm", "nop\ninclude \"main.asm\""),
+		]).err().unwrap();
+
+		// The directive that closes the cycle is reported:
+		match err
+		{
+			IncludeError::Cycle(file, line_number, ref path) 	=> assert_eq!((file, line_number, path.ends_with("main.asm")), (1, 1, true)),
+			_ 													=> panic!("Expected a cycle, got {:}.", err),
+		}
+
+		assert!(err.to_string().starts_with("[File 1] [Line 1]"));
+	}
+
+	#[test]
+	fn missing_includes_are_io_errors()
+	{
+		let err = read_files("missing", &[("main.asm", "include \"missing.asm\"")]).err().unwrap();
+
+		match err
+		{
+			IncludeError::Io(path, err) 	=> assert!(path.ends_with("missing.asm") && (err.kind() == io::ErrorKind::NotFound)),
+			_ 								=> panic!("Expected an I/O error, got {:}.", err),
+		}
+	}
+
+	#[test]
+	fn errors_in_included_files_are_located()
+	{
+		let sources = read_files("errors", &[
+			("main.asm", "ldc 1\ninclude \"lib.asm\"\nhlt"),
+			("lib.asm", "nop\nthis is not mima"),
+		]).unwrap();
+
+		match ObjectCode::assemble_file(&sources).err().unwrap()
+		{
+			AssemblerError::ParserError(err) 	=> assert_eq!((err.file(), err.line_number()), (Some(1), 1)),
+			err 								=> panic!("Expected a parser error, got {:}.", err),
+		}
+
+		let sources = read_files("label-errors", &[
+			("main.asm", "include \"lib.asm\"\nhlt"),
+			("lib.asm", "nop\njmp nowhere"),
+		]).unwrap();
+
+		match ObjectCode::assemble_file(&sources).err().unwrap()
+		{
+			AssemblerError::LabelError(err) 	=> assert_eq!((err.file(), err.line_number()), (Some(1), 1)),
+			err 								=> panic!("Expected a label error, got {:}.", err),
+		}
+	}
+
+	#[test]
+	fn block_comments_hide_includes()
+	{
+		let sources = read_files("comments", &[
+			("main.asm", "include \"lib.asm\"\n/*\ninclude \"missing.asm\"\n*/\nhlt"),
+			("lib.asm", "ldc 1"),
+		]).unwrap();
+
+		// The commented directive stays in the text (and is not read):
+		assert_eq!(sources.paths().len(), 2);
+		assert_eq!(sources.text(), "ldc 1\n/*\ninclude \"missing.asm\"\n*/\nhlt\n");
+		assert_eq!(sources.origin(2), (0, 2));
+
+		let (object_code, _) = ObjectCode::assemble_file(&sources).unwrap();
+		assert_eq!(object_code.raw_code.len(), 2);
+	}
+}
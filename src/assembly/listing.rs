@@ -0,0 +1,113 @@
+use std::fmt::Write;
+use crate::types::*;
+use crate::assembly::assembler::ObjectCode;
+use crate::assembly::parser::{ProgramToken, StatementContentToken};
+use crate::assembly::disassembler::render_word;
+
+// A listing row describes a single emitted word:
+// Its address, the machine word itself, the line of the originating statement and the rendered instruction (or data) text.
+pub struct ListingEntry
+{
+	pub address: Word,
+	pub word: Word,
+	pub line_number: usize,
+	pub text: String,
+}
+
+// A listing holds one row per emitted word (data with repetition emits one row per repetition):
+pub type Listing = Vec<ListingEntry>;
+
+// Walk the (resolved) program alongside its object code.
+// Statements are laid out consecutively, so their addresses are the running sums of the required words.
+pub(crate) fn build_listing(program: &ProgramToken, code: &ObjectCode) -> Listing
+{
+	let mut listing = Listing::with_capacity(code.raw_code.len());
+
+	for stmt in program.0.iter()
+	{
+		for _ in 0..stmt.required_words()
+		{
+			let address = listing.len();
+			let word = code.raw_code[address];
+
+			// Words that do not decode to an instruction keep the annotation of the disassembler:
+			let (statement, annotation) = render_word(word);
+
+			let text = match stmt.content
+			{
+				Some(StatementContentToken::Instruction(_)) 	=> format!("{:}{:}", statement, annotation),
+				_ 												=> format!("DAT {:}{:}", word, annotation),
+			};
+
+			listing.push(ListingEntry { address: Word(address as u32), word, line_number: stmt.line_number, text });
+		}
+	}
+
+	listing
+}
+
+// Format a single cell of object code (raw word, decoded instruction and source line).
+// Words that do not decode to an instruction are rendered as data (like the disassembler does).
+// Cells behind the end of the code are rendered as blanks.
+fn format_cell(code: &ObjectCode, address: usize) -> String
+{
+	match code.raw_code.get(address)
+	{
+		Some(&word) =>
+		{
+			let (instruction, _) = render_word(word);
+			let (_, line_number) = code.source_map[address];
+			format!("{:} {:<16} [Line {:03}]", word, instruction, line_number)
+		},
+		None => format!("{:<10} {:<16} {:<10}", "──────────", "", ""),
+	}
+}
+
+// Compare two versions of a program cell by cell.
+// Both sides are aligned by address, differing cells are marked with '!'.
+pub fn listing_diff(a: &ObjectCode, b: &ObjectCode) -> String
+{
+	let mut diff = String::new();
+	let number_of_words = a.raw_code.len().max(b.raw_code.len());
+
+	for address in 0..number_of_words
+	{
+		// Cells differ if one of them is missing or if the raw words (and therefore the decoded instructions) are not equal:
+		let marker = if a.raw_code.get(address) != b.raw_code.get(address) { '!' } else { ' ' };
+
+		writeln!(diff, "{:} {:} │ {:} │ {:}", marker, Word(address as u32), format_cell(a, address), format_cell(b, address))
+			.expect("Writing to a string must not fail.");
+	}
+
+	diff
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn diff_marks_changed_instruction()
+	{
+		let (a, _) = ObjectCode::assemble("ldc 1\nadd 0x10\nstv 0x10\nhlt").unwrap();
+		let (b, _) = ObjectCode::assemble("ldc 1\nadd 0x11\nstv 0x10\nhlt").unwrap();
+
+		let diff = listing_diff(&a, &b);
+		let marked: Vec<&str> = diff.lines().filter(|line| line.starts_with('!')).collect();
+
+		assert_eq!(diff.lines().count(), 4);
+		assert_eq!(marked.len(), 1);
+		assert!(marked[0].starts_with(&format!("! {:}", Word(1))));
+	}
+
+	#[test]
+	fn listing_renders_data_and_instructions()
+	{
+		let (_, _, listing) = ObjectCode::assemble_with_listing("add 0x10\ndat 0xE0000000\ndat 5\nhlt").unwrap();
+		let texts: Vec<&str> = listing.iter().map(|entry| entry.text.as_str()).collect();
+
+		assert_eq!(texts, ["ADD 0x00000010", "DAT 0xE0000000 (unknown opcode 0xE)", "DAT 0x00000005", "HLT"]);
+		assert_eq!(listing[1].line_number, 1);
+	}
+}
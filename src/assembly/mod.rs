@@ -1,6 +1,14 @@
 mod error;
 mod parser;
 mod assembler;
+mod listing;
+mod disassembler;
+mod object_file;
+mod include;
 
-pub use error::{Diagnostics, DiagnosticsType, ParserError, LabelErrorType, LabelError, AssemblerError};
-pub use assembler::{Label, Symbol, ObjectCode, ProgramRepr};
+pub use error::{Diagnostics, DiagnosticsType, ParserError, ParserErrorType, LabelErrorType, LabelError, OperandErrorType, OperandError, AssemblerError};
+pub use assembler::{Label, Symbol, SymbolStatus, SectionKind, Section, ObjectCode, ProgramRepr, AssembleOptions};
+pub use listing::{ListingEntry, Listing, listing_diff};
+pub use disassembler::disassemble;
+pub use parser::ProgramToken;
+pub use include::{FileId, SourceSet, IncludeError};
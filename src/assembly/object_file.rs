@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use crate::types::*;
+use crate::assembly::assembler::*;
+
+// The binary object file format (all integers are little-endian u32):
+//
+// - Magic bytes "MIMAOBJ" and the format version (u8)
+// - The entry point address
+// - Number of words, followed by the raw code
+// - The source map (one file and line number per word)
+// - Number of symbols, followed by the symbols (instruction address, prefix, name)
+// - Number of local symbols, followed by the local symbols (name, address)
+// - Number of relocations, followed by the relocated instruction addresses
+// - Number of sections, followed by the sections (kind, start address, end address)
+//
+// Strings are stored as byte length, followed by their UTF-8 bytes.
+// Section kinds are stored as 0 (code) or 1 (data).
+const MAGIC: &[u8; 7] = b"MIMAOBJ";
+const VERSION: u8 = 5;
+
+impl ObjectCode
+{
+	// Serialize the object code (including the symbol table) to allow linking without re-assembly:
+	pub fn write_object(&self, w: &mut impl Write) -> io::Result<()>
+	{
+		w.write_all(MAGIC)?;
+		w.write_all(&[VERSION])?;
+		write_u32(w, self.entry.0)?;
+
+		// Raw code and source map:
+		write_len(w, self.raw_code.len())?;
+
+		for word in self.raw_code.iter()
+		{
+			write_u32(w, word.0)?;
+		}
+
+		for &(file, line_number) in self.source_map.iter()
+		{
+			write_len(w, file)?;
+			write_len(w, line_number)?;
+		}
+
+		// Symbol table:
+		write_len(w, self.symbol_table.len())?;
+
+		for symbol in self.symbol_table.iter()
+		{
+			write_u32(w, symbol.instruction_address.0)?;
+			write_str(w, &symbol.label.prefix)?;
+			write_str(w, &symbol.label.name)?;
+		}
+
+		// Local symbols:
+		write_len(w, self.local_symbols.len())?;
+
+		for (name, addr) in self.local_symbols.iter()
+		{
+			write_str(w, name)?;
+			write_u32(w, addr.0)?;
+		}
+
+		// Relocations:
+		write_len(w, self.relocations.len())?;
+
+		for addr in self.relocations.iter()
+		{
+			write_u32(w, addr.0)?;
+		}
+
+		// Sections:
+		write_len(w, self.sections.len())?;
+
+		for section in self.sections.iter()
+		{
+			let kind = match section.kind
+			{
+				SectionKind::Code 	=> 0,
+				SectionKind::Data 	=> 1,
+			};
+
+			write_u32(w, kind)?;
+			write_u32(w, section.range.start.0)?;
+			write_u32(w, section.range.end.0)?;
+		}
+
+		Ok(())
+	}
+
+	// Deserialize object code that has been written by "write_object":
+	pub fn read_object(r: &mut impl Read) -> io::Result<ObjectCode>
+	{
+		// Check magic bytes and version:
+		let mut magic = [0u8; 7];
+		r.read_exact(&mut magic)?;
+
+		if &magic != MAGIC
+		{
+			return Err(invalid_data("Not a MiMA object file."));
+		}
+
+		let mut version = [0u8; 1];
+		r.read_exact(&mut version)?;
+
+		if version[0] != VERSION
+		{
+			return Err(invalid_data("Unsupported MiMA object file version."));
+		}
+
+		// Entry point:
+		let entry = Word(read_u32(r)?);
+
+		// Raw code and source map:
+		let number_of_words = read_len(r)?;
+
+		if number_of_words > LINEAR_ADDRESS_SPACE_WORDS
+		{
+			return Err(invalid_data("Raw code exceeds the size of the linear address space."));
+		}
+
+		let raw_code = (0..number_of_words).map(|_| read_u32(r).map(Word)).collect::<io::Result<Vec<_>>>()?;
+		let source_map = (0..number_of_words).map(|_| Ok((read_len(r)?, read_len(r)?))).collect::<io::Result<Vec<_>>>()?;
+
+		// Symbol table:
+		let number_of_symbols = read_len(r)?;
+		let mut symbol_table = vec![];
+
+		for _ in 0..number_of_symbols
+		{
+			let instruction_address = Word(read_u32(r)?);
+
+			if instruction_address.0 as usize >= number_of_words
+			{
+				return Err(invalid_data("Symbol references an address outside of the raw code."));
+			}
+
+			let prefix = read_str(r)?;
+			let name = read_str(r)?;
+
+			symbol_table.push(Symbol { instruction_address, label: Label { prefix, name } });
+		}
+
+		// Local symbols:
+		let number_of_local_symbols = read_len(r)?;
+		let mut local_symbols = vec![];
+
+		for _ in 0..number_of_local_symbols
+		{
+			let name = read_str(r)?;
+			let addr = Word(read_u32(r)?);
+
+			local_symbols.push((name, addr));
+		}
+
+		// Relocations:
+		let number_of_relocations = read_len(r)?;
+		let mut relocations = vec![];
+
+		for _ in 0..number_of_relocations
+		{
+			let addr = Word(read_u32(r)?);
+
+			if addr.0 as usize >= number_of_words
+			{
+				return Err(invalid_data("Relocation references an address outside of the raw code."));
+			}
+
+			relocations.push(addr);
+		}
+
+		// Sections:
+		let number_of_sections = read_len(r)?;
+		let mut sections = vec![];
+
+		for _ in 0..number_of_sections
+		{
+			let kind = match read_u32(r)?
+			{
+				0 	=> SectionKind::Code,
+				1 	=> SectionKind::Data,
+				_ 	=> return Err(invalid_data("Unknown section kind.")),
+			};
+
+			let start = Word(read_u32(r)?);
+			let end = Word(read_u32(r)?);
+
+			if start > end || end.0 as usize > number_of_words
+			{
+				return Err(invalid_data("Section exceeds the raw code."));
+			}
+
+			sections.push(Section { kind, range: start..end });
+		}
+
+		Ok(ObjectCode
+		{
+			raw_code: raw_code.into_boxed_slice(),
+			symbol_table,
+			source_map: source_map.into_boxed_slice(),
+			entry,
+			local_symbols,
+			relocations,
+			sections,
+		})
+	}
+}
+
+fn invalid_data(msg: &str) -> io::Error
+{
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> io::Result<()>
+{
+	w.write_all(&value.to_le_bytes())
+}
+
+fn write_len(w: &mut impl Write, len: usize) -> io::Result<()>
+{
+	let len = u32::try_from(len).map_err(|_| invalid_data("Length exceeds the object file format."))?;
+	write_u32(w, len)
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> io::Result<()>
+{
+	write_len(w, s.len())?;
+	w.write_all(s.as_bytes())
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32>
+{
+	let mut bytes = [0u8; 4];
+	r.read_exact(&mut bytes)?;
+
+	Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_len(r: &mut impl Read) -> io::Result<usize>
+{
+	read_u32(r).map(|len| len as usize)
+}
+
+fn read_str(r: &mut impl Read) -> io::Result<String>
+{
+	// The length has not been validated, so don't allocate it up front.
+	// Read at most that many bytes instead and check that they have been there:
+	let len = read_len(r)?;
+	let mut bytes = vec![];
+	r.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+
+	if bytes.len() != len
+	{
+		return Err(invalid_data("String is truncated."));
+	}
+
+	String::from_utf8(bytes).map_err(|_| invalid_data("String is not valid UTF-8."))
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	const SOURCE: &str = "START main\nvalue: dat 7\nmain: ldv value\nstv uart.data\nhlt";
+
+	#[test]
+	fn round_trip()
+	{
+		let (code, _) = ObjectCode::assemble(SOURCE).unwrap();
+		assert_eq!(code.symbol_table.len(), 1);
+		assert_eq!(code.relocations, vec![Word(1)]);
+		assert_eq!(code.sections, vec![Section { kind: SectionKind::Data, range: Word(0)..Word(1) }, Section { kind: SectionKind::Code, range: Word(1)..Word(4) }]);
+
+		let mut bytes = vec![];
+		code.write_object(&mut bytes).unwrap();
+
+		let read = ObjectCode::read_object(&mut bytes.as_slice()).unwrap();
+		assert!(read == code);
+	}
+
+	#[test]
+	fn unknown_section_kind_is_rejected()
+	{
+		let (code, _) = ObjectCode::assemble(SOURCE).unwrap();
+
+		let mut bytes = vec![];
+		code.write_object(&mut bytes).unwrap();
+
+		// The last section ends with its kind, start and end address:
+		let offset = bytes.len() - (3 * 4);
+		assert_eq!(&bytes[offset..(offset + 4)], &0u32.to_le_bytes());
+		bytes[offset..(offset + 4)].copy_from_slice(&2u32.to_le_bytes());
+
+		let err = ObjectCode::read_object(&mut bytes.as_slice()).err().unwrap();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[test]
+	fn oversized_string_length_is_rejected()
+	{
+		let (code, _) = ObjectCode::assemble(SOURCE).unwrap();
+
+		let mut bytes = vec![];
+		code.write_object(&mut bytes).unwrap();
+
+		// Header, entry point, 4 words with files and line numbers, the number of symbols and the instruction address precede the prefix length:
+		let offset = 8 + 4 + 4 + (4 * 4) + (4 * 8) + 4 + 4;
+		assert_eq!(&bytes[offset..(offset + 4)], &4u32.to_le_bytes());
+		bytes[offset..(offset + 4)].copy_from_slice(&u32::MAX.to_le_bytes());
+
+		let err = ObjectCode::read_object(&mut bytes.as_slice()).err().unwrap();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+}
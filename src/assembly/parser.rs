@@ -4,22 +4,63 @@ use nom::
 {
 	Err,
 	IResult,
+	error::{Error as NomError, ErrorKind},
 	branch::alt,
-	bytes::complete::{tag, tag_no_case, take_while, take_while_m_n},
-	character::complete::{char as single_char, not_line_ending, space0, space1},
-	combinator::{all_consuming, map, map_res, opt, recognize},
-	multi::many0,
+	bytes::complete::{tag, tag_no_case, take_until, take_while, take_while1, take_while_m_n},
+	character::complete::{char as single_char, none_of, not_line_ending, one_of, space0, space1},
+	combinator::{all_consuming, map, not, opt, recognize, value, verify},
+	multi::{many0, many0_count},
 	sequence::{delimited, pair, separated_pair, preceded, terminated, tuple},
 };
 use crate::assembly::error::*;
+use crate::assembly::assembler::ObjectCode;
 use crate::types::*;
 
 //Note: "'src" is the lifetime of the string slice we parse our assembler program from.
 // All references (parser result, labels, error messages, ...) annotated with this lifetime point into that original slice.
 
-// A word token wraps a single machine word:
+// The notation a word literal has been written in:
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
-pub struct WordToken(pub Word);
+pub enum Radix
+{
+	Binary,
+	Decimal,
+	Hexadecimal,
+	Character,
+}
+
+// A word token wraps a single machine word (and the notation of its literal).
+// The flag tells whether the literal has been written with a minus sign (e. g. "-1" in contrast to "4294967295").
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub struct WordToken(pub Word, pub Radix, pub bool);
+
+impl WordToken
+{
+	// Add an offset to the literal (e. g. for "10 - 1").
+	// The result must be representable like any other literal, i. e. in [i32.min, u32.max] (see "word_token_num"):
+	pub(crate) fn offset(self, offset: i32) -> Option<WordToken>
+	{
+		let WordToken(word, radix, negative) = self;
+		let value = if negative { word.as_i32() as i64 } else { word.0 as i64 } + (offset as i64);
+
+		if value < (i32::MIN as i64)
+		{
+			None
+		}
+		else if value < 0
+		{
+			Some(WordToken(Word(value as i32 as u32), radix, true))
+		}
+		else if value <= (u32::MAX as i64)
+		{
+			Some(WordToken(Word(value as u32), radix, false))
+		}
+		else
+		{
+			None
+		}
+	}
+}
 
 impl fmt::Display for WordToken
 {
@@ -41,17 +82,19 @@ impl<'src> fmt::Display for LabelIdentifierToken<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
-		write!(f, "{:}{:}{:}", self.0.unwrap_or(""), "::", self.1)
+		write!(f, "{:}::{:}", self.0.unwrap_or(""), self.1)
 	}
 }
 
 // Every instruction that takes an address payload can also take a label in our assembler dialect.
 // To handle those cases correctly, we use another algebraic datatype for addresses.
+// A label can be followed by an offset (e. g. "loop + 2"), which is added to its address.
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub enum AddressToken<'src>
 {
 	Address(WordToken),
 	Label(LabelIdentifierToken<'src>),
+	LabelOffset(LabelIdentifierToken<'src>, i32),
 }
 
 impl<'src> fmt::Display for AddressToken<'src>
@@ -60,8 +103,54 @@ impl<'src> fmt::Display for AddressToken<'src>
 	{
 		match self
 		{
-			AddressToken::Address(w) 	=> write!(f, "{:}({:})", "Address", w),
-			AddressToken::Label(l) 		=> write!(f, "{:}({:})", "Label", l),
+			AddressToken::Address(w) 			=> write!(f, "Address({:})", w),
+			AddressToken::Label(l) 				=> write!(f, "Label({:})", l),
+			AddressToken::LabelOffset(l, o) 	=> write!(f, "Label({:}{:+})", l, o),
+		}
+	}
+}
+
+// A value token is either a literal word or a reference to a named constant (see "ConstantDefinitionToken").
+// Like a label, a constant can be followed by an offset (e. g. "count - 1").
+// Constant references are replaced by their words before the program is assembled.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub enum ValueToken<'src>
+{
+	Word(WordToken),
+	Constant(&'src str),
+	ConstantOffset(&'src str, i32),
+}
+
+impl<'src> ValueToken<'src>
+{
+	pub fn word(&self) -> Word
+	{
+		match self
+		{
+			ValueToken::Word(w) 									=> w.0,
+			ValueToken::Constant(name) | ValueToken::ConstantOffset(name, _) 	=> panic!("The constant \"{:}\" has not been resolved.", name),
+		}
+	}
+
+	pub fn word_token(&self) -> WordToken
+	{
+		match self
+		{
+			ValueToken::Word(w) 									=> *w,
+			ValueToken::Constant(name) | ValueToken::ConstantOffset(name, _) 	=> panic!("The constant \"{:}\" has not been resolved.", name),
+		}
+	}
+}
+
+impl<'src> fmt::Display for ValueToken<'src>
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			ValueToken::Word(w) 					=> write!(f, "{:}", w),
+			ValueToken::Constant(name) 				=> write!(f, "Constant({:})", name),
+			ValueToken::ConstantOffset(name, o) 	=> write!(f, "Constant({:}{:+})", name, o),
 		}
 	}
 }
@@ -74,36 +163,97 @@ impl<'src> fmt::Display for LabelDefinitionToken<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
-		write!(f, "{:}({:})", "LabelDefinition", self.0)
+		write!(f, "LabelDefinition({:})", self.0)
+	}
+}
+
+// A constant definition token assigns an alphanumeric identifier (without prefix) to a word.
+// Constants share their namespace with the local labels.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub struct ConstantDefinitionToken<'src>(pub &'src str, pub WordToken);
+
+impl<'src> fmt::Display for ConstantDefinitionToken<'src>
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write!(f, "{:} = {:}", self.0, self.1)
 	}
 }
 
 // A data token represents a word definition with optional repitition count:
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
-pub struct DataToken(WordToken, Option<WordToken>);
+pub struct DataToken<'src>(pub ValueToken<'src>, pub Option<ValueToken<'src>>);
 
-impl DataToken
+impl<'src> DataToken<'src>
 {
 	pub fn word(&self) -> Word
 	{
-		(self.0).0
+		self.0.word()
 	}
 
+	// The repetition count defaults to 1.
+	// "times 0" is legal: The statement emits no words, so labels attached to it share the address of the next statement.
 	pub fn times(&self) -> usize
 	{
-		self.1.map_or(1, |w| (w.0).0 as usize)
+		self.1.map_or(1, |v| v.word().0 as usize)
 	}
 }
 
-impl fmt::Display for DataToken
+impl<'src> fmt::Display for DataToken<'src>
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
 		write!(f, "{:}", self.0)?;
 
-		if let Some(times) = self.1
+		match self.1
 		{
-			write!(f, " {:} {:}", "x", (times.0).0)?;
+			Some(ValueToken::Word(times)) 	=> write!(f, " x {:}", (times.0).0)?,
+			Some(times) 					=> write!(f, " x {:}", times)?,
+			None 							=> (),
+		}
+
+		Ok(())
+	}
+}
+
+// A string token represents a sequence of words (one per character) with optional NUL termination.
+// It holds the raw string content (without quotes, escape sequences are still present).
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub struct StringToken<'src>(&'src str, bool);
+
+impl<'src> StringToken<'src>
+{
+	// Iterate over the words of the (unescaped) characters, including the NUL terminator if requested:
+	pub fn words(&self) -> impl Iterator<Item = Word> + 'src
+	{
+		let mut remaining = self.0;
+		let chars = std::iter::from_fn(move ||
+		{
+			// The content has already been validated by the parser:
+			let (rest, c) = escaped_char('"')(remaining).ok()?;
+			remaining = rest;
+
+			Some(Word(c as u32))
+		});
+
+		chars.chain(if self.1 { Some(Word(0)) } else { None })
+	}
+
+	pub fn len(&self) -> usize
+	{
+		self.words().count()
+	}
+}
+
+impl<'src> fmt::Display for StringToken<'src>
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write!(f, "\"{:}\"", self.0)?;
+
+		if self.1
+		{
+			write!(f, " + NUL")?;
 		}
 
 		Ok(())
@@ -120,13 +270,19 @@ pub enum InstructionToken<'src>
 	Xor(AddressToken<'src>),
 	LoadValue(AddressToken<'src>),
 	StoreValue(AddressToken<'src>),
-	LoadConstant(WordToken),
+	LoadConstant(ValueToken<'src>),
 	Jump(AddressToken<'src>),
 	JumpIfNegative(AddressToken<'src>),
+	JumpIfZero(AddressToken<'src>),
+	JumpIfEqual(AddressToken<'src>),
 	Equals(AddressToken<'src>),
+	Compare(AddressToken<'src>),
+	Subtract(AddressToken<'src>),
 	Halt,
 	Not,
-	RotateRight(WordToken),
+	RotateRight(ValueToken<'src>),
+	RotateLeft(ValueToken<'src>),
+	SkipIfNegative,
 	NoOperation,
 }
 
@@ -136,31 +292,43 @@ impl<'src> fmt::Display for InstructionToken<'src>
 	{
 		match self
 		{
-			InstructionToken::Add(a) 				=> write!(f, "{:}({:})", "add", a),
-			InstructionToken::And(a) 				=> write!(f, "{:}({:})", "and", a),
-			InstructionToken::Or(a) 				=> write!(f, "{:}({:})",  "or", a),
-			InstructionToken::Xor(a) 				=> write!(f, "{:}({:})", "xor", a),
-			InstructionToken::LoadValue(a) 			=> write!(f, "{:}({:})", "ldv", a),
-			InstructionToken::StoreValue(a) 		=> write!(f, "{:}({:})", "stv", a),
-			InstructionToken::LoadConstant(w) 		=> write!(f, "{:}({:})", "ldc", w),
-			InstructionToken::Jump(a) 				=> write!(f, "{:}({:})", "jmp", a),
-			InstructionToken::JumpIfNegative(a) 	=> write!(f, "{:}({:})", "jmn", a),
-			InstructionToken::Equals(a) 			=> write!(f, "{:}({:})", "eql", a),
-			InstructionToken::Halt 					=> write!(f, "{:}", "hlt"),
-			InstructionToken::Not 					=> write!(f, "{:}", "not"),
-			InstructionToken::RotateRight(w) 		=> write!(f, "{:}({:})", "rar", w),
-			InstructionToken::NoOperation 			=> write!(f, "{:}", "nop"),
+			InstructionToken::Add(a) 				=> write!(f, "add({:})", a),
+			InstructionToken::And(a) 				=> write!(f, "and({:})", a),
+			InstructionToken::Or(a) 				=> write!(f, "or({:})", a),
+			InstructionToken::Xor(a) 				=> write!(f, "xor({:})", a),
+			InstructionToken::LoadValue(a) 			=> write!(f, "ldv({:})", a),
+			InstructionToken::StoreValue(a) 		=> write!(f, "stv({:})", a),
+			InstructionToken::LoadConstant(w) 		=> write!(f, "ldc({:})", w),
+			InstructionToken::Jump(a) 				=> write!(f, "jmp({:})", a),
+			InstructionToken::JumpIfNegative(a) 	=> write!(f, "jmn({:})", a),
+			InstructionToken::JumpIfZero(a) 		=> write!(f, "jmz({:})", a),
+			InstructionToken::JumpIfEqual(a) 		=> write!(f, "jme({:})", a),
+			InstructionToken::Equals(a) 			=> write!(f, "eql({:})", a),
+			InstructionToken::Compare(a) 			=> write!(f, "cmp({:})", a),
+			InstructionToken::Subtract(a) 			=> write!(f, "sub({:})", a),
+			InstructionToken::Halt 					=> write!(f, "hlt"),
+			InstructionToken::Not 					=> write!(f, "not"),
+			InstructionToken::RotateRight(w) 		=> write!(f, "rar({:})", w),
+			InstructionToken::RotateLeft(w) 		=> write!(f, "ral({:})", w),
+			InstructionToken::SkipIfNegative 		=> write!(f, "skn"),
+			InstructionToken::NoOperation 			=> write!(f, "nop"),
 		}
 	}
 }
 
 // A statement token wraps a list of 0...n label definition tokens.
 // Optionally, it is followed by either a data or an instruction token.
+// Constant definitions and the entry point directive are statements on their own (without label definitions).
+// When recovering from parser errors, bad statements are replaced by error placeholders (holding the failing token).
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub enum StatementContentToken<'src>
 {
-	Data(DataToken),
+	Data(DataToken<'src>),
+	String(StringToken<'src>),
 	Instruction(InstructionToken<'src>),
+	Constant(ConstantDefinitionToken<'src>),
+	Start(AddressToken<'src>),
+	Error(ParserError<'src>),
 }
 
 impl<'src> fmt::Display for StatementContentToken<'src>
@@ -169,8 +337,12 @@ impl<'src> fmt::Display for StatementContentToken<'src>
 	{
 		match self
 		{
-			StatementContentToken::Data(d) 			=> write!(f, "{:}({:})", "DataDefinition", d),
-			StatementContentToken::Instruction(i) 	=> write!(f, "{:}({:})", "Instruction", i),
+			StatementContentToken::Data(d) 			=> write!(f, "DataDefinition({:})", d),
+			StatementContentToken::String(s) 		=> write!(f, "StringDefinition({:})", s),
+			StatementContentToken::Instruction(i) 	=> write!(f, "Instruction({:})", i),
+			StatementContentToken::Constant(c) 		=> write!(f, "ConstantDefinition({:})", c),
+			StatementContentToken::Start(a) 		=> write!(f, "Start({:})", a),
+			StatementContentToken::Error(e) 		=> write!(f, "Error(\"{:}\")", e.token().unwrap_or("???")),
 		}
 	}
 }
@@ -206,6 +378,7 @@ impl<'src> StatementToken<'src>
 		match self.content
 		{
 			Some(StatementContentToken::Data(d)) 			=> d.times(),
+			Some(StatementContentToken::String(s)) 			=> s.len(),
 			Some(StatementContentToken::Instruction(_)) 	=> 1,
 			_ 												=> 0,
 		}
@@ -251,70 +424,152 @@ impl<'src> fmt::Display for ProgramToken<'src>
 	}
 }
 
+// The mnemonics and directives of the language (labels with these names are legal, but confusing):
+pub(crate) const KEYWORDS: &[&str] =
+&[
+	"add", "and", "or", "xor", "ldv", "stv", "ldc", "jmp", "jmn", "jmz", "eql", "sub", "hlt", "not", "rar", "ral", "nop", "cmp", "jme", "skn",
+	"dat", "times", "str", "strz", "equ", "start", "this", "include",
+];
+
 fn word_token(i: &str) -> IResult<&str, WordToken>
+{
+	// A character literal is mapped to its Unicode scalar value.
+	// The largest scalar value (0x10FFFF) always fits into the payload of both instruction formats.
+	let char_word_token = map(word_token_char, |c| WordToken(Word(c), Radix::Character, false));
+
+	alt((char_word_token, word_token_num))(i)
+}
+
+// A word literal, optionally followed by an offset (e. g. "10 - 1").
+// The expression is folded into a single literal right away:
+fn word_expression_token(i: &str) -> IResult<&str, WordToken>
+{
+	let (rest, (token, offset)) = pair(word_token, opt(offset_token))(i)?;
+
+	match offset
+	{
+		Some(offset) 	=> token.offset(offset).map(|token| (rest, token)).ok_or_else(|| Err::Failure(NomError::new(i, ErrorKind::TooLarge))),
+		None 			=> Ok((rest, token)),
+	}
+}
+
+// An offset is a '+' or '-' (optionally surrounded by spaces), followed by an unsigned literal that fits into an i32:
+fn offset_token(i: &str) -> IResult<&str, i32>
+{
+	let sign = alt((value(1, single_char('+')), value(-1, single_char('-'))));
+	let magnitude = verify(preceded(not(one_of("+-")), word_token), |&WordToken(word, _, _)| word.0 <= (i32::MAX as u32));
+
+	map(separated_pair(preceded(space0, sign), space0, magnitude), |(sign, WordToken(word, _, _))| sign * (word.0 as i32))(i)
+}
+
+fn word_token_num(i: &str) -> IResult<&str, WordToken>
 {
 	// Try to match the binary, hexadecimal, or decimal prefix.
 	// If all of them fail, the decimal version without prefix must succeed.
-	let opt_sign = opt(alt((single_char('+'), single_char('-'))));
+	let opt_sign = || opt(alt((single_char('+'), single_char('-'))));
+
+	// Remember the radix to allow style diagnostics.
+	let with_radix = |radix| move |(opt_sign, digits)| (opt_sign, digits, radix);
 
-	let prefixed_word_token_bin   = separated_pair(&opt_sign, tag("0b"), word_token_bin);
-	let prefixed_word_token_dec   = separated_pair(&opt_sign, tag("0d"), word_token_dec);
-	let prefixed_word_token_hex   = separated_pair(&opt_sign, tag("0x"), word_token_hex);
-	let unprefixed_word_token_dec = pair(&opt_sign, word_token_dec);
+	let prefixed_word_token_bin   = map(separated_pair(opt_sign(), tag("0b"), word_token_bin), with_radix(Radix::Binary));
+	let prefixed_word_token_dec   = map(separated_pair(opt_sign(), tag("0d"), word_token_dec), with_radix(Radix::Decimal));
+	let prefixed_word_token_hex   = map(separated_pair(opt_sign(), tag("0x"), word_token_hex), with_radix(Radix::Hexadecimal));
+	let unprefixed_word_token_dec = map(pair(opt_sign(), word_token_dec), with_radix(Radix::Decimal));
 
-	let result = map_res(alt((prefixed_word_token_bin, prefixed_word_token_dec, prefixed_word_token_hex, unprefixed_word_token_dec)), |(opt_sign, num)|
+	let (rest, (opt_sign, digits, radix)) = alt((prefixed_word_token_bin, prefixed_word_token_dec, prefixed_word_token_hex, unprefixed_word_token_dec))(i)?;
+
+	// The literal is syntactically fine now.
+	// If it does not fit into a machine word, we fail hard (instead of letting the alternatives try their luck).
+	let out_of_range = || Err::Failure(NomError::new(i, ErrorKind::TooLarge));
+
+	let base = match radix
 	{
-		// Determine if we have a positive or negative sign.
-		// No sign means positive.
-		// Also treat 0 always as positive. That allows us to perform the 2's complement without wrapping.
-		let is_negative = opt_sign.map_or(false, |s| if (s == '-') && (num > 0) { true } else { false });
+		Radix::Binary 		=> 2,
+		Radix::Hexadecimal 	=> 16,
+		_ 					=> 10,
+	};
+
+	let num = u32::from_str_radix(digits, base).map_err(|_| out_of_range())?;
+
+	// Determine if we have a positive or negative sign.
+	// No sign means positive.
+	// Also treat 0 always as positive. That allows us to perform the 2's complement without wrapping.
+	let is_negative = opt_sign.is_some_and(|s| (s == '-') && (num > 0));
 
-		// We want to allow literals from [i32.min, u32.max] which will then be encoded as machine word.
-		// Example: -1 will be mapped to 0xFF_FF_FF_FFu32.
-		// As a consequence, we have to perform a range check in the negative case.
-		if is_negative
+	// We want to allow literals from [i32.min, u32.max] which will then be encoded as machine word.
+	// Example: -1 will be mapped to 0xFF_FF_FF_FFu32.
+	// As a consequence, we have to perform a range check in the negative case.
+	if is_negative
+	{
+		if num <= 0x80_00_00_00u32
 		{
-			if num <= 0x80_00_00_00u32
-			{
-				// Apply 2's complement:
-				Ok(WordToken(Word(!num + 1)))
-			}
-			else
-			{
-				Err(())
-			}
+			// Apply 2's complement:
+			Ok((rest, WordToken(Word(!num + 1), radix, true)))
 		}
 		else
 		{
-			Ok(WordToken(Word(num)))
+			Err(out_of_range())
 		}
-	})(i)?;
+	}
+	else
+	{
+		Ok((rest, WordToken(Word(num), radix, false)))
+	}
+}
+
+fn word_token_bin(i: &str) -> IResult<&str, &str>
+{
+	take_while1(|c: char| c.is_digit(2))(i)
+}
 
-	// Separate return step needed to drop "opt_sign" after temporaries ...
-	Ok(result)
+fn word_token_dec(i: &str) -> IResult<&str, &str>
+{
+	take_while1(|c: char| c.is_ascii_digit())(i)
 }
 
-fn word_token_bin(i: &str) -> IResult<&str, u32>
+fn word_token_hex(i: &str) -> IResult<&str, &str>
 {
-	map_res(take_while_m_n(1, 32, |c: char| c.is_digit(2)), |s| u32::from_str_radix(s, 2))(i)
+	take_while1(|c: char| c.is_ascii_hexdigit())(i)
 }
 
-fn word_token_dec(i: &str) -> IResult<&str, u32>
+fn word_token_char(i: &str) -> IResult<&str, u32>
 {
-	map_res(take_while_m_n(1, 10, |c: char| c.is_digit(10)), |s| u32::from_str_radix(s, 10))(i)
+	// A single (potentially escaped) character, enclosed in single quotes:
+	map(delimited(single_char('\''), escaped_char('\''), single_char('\'')), |c| c as u32)(i)
 }
 
-fn word_token_hex(i: &str) -> IResult<&str, u32>
+fn escaped_char<'src>(delimiter: char) -> impl FnMut(&'src str) -> IResult<&'src str, char>
 {
-	map_res(take_while_m_n(1, 8, |c: char| c.is_digit(16)), |s| u32::from_str_radix(s, 16))(i)
+	// Supported escape sequences are "\n", "\t", "\0", "\\", "\'" and "\"".
+	// Everything else is taken literally, except for the backslash and the given delimiter.
+	let forbidden = ['\\', delimiter];
+
+	move |i| alt
+	((
+		preceded(single_char('\\'), alt
+		((
+			value('\n', single_char('n')),
+			value('\t', single_char('t')),
+			value('\0', single_char('0')),
+			value('\\', single_char('\\')),
+			value('\'', single_char('\'')),
+			value('"', single_char('"')),
+		))),
+		none_of(&forbidden[..]),
+	))(i)
 }
 
-fn label_identifier_token(i: &str) -> IResult<&str, LabelIdentifierToken>
+fn label_identifier_token(i: &str) -> IResult<&str, LabelIdentifierToken<'_>>
 {
-	// Match prefix and actual identifier as pair.
+	// A scoped label has a leading '.' (which stays part of the name) and no prefix:
+	let scoped = map(recognize(pair(single_char('.'), label_identifier_token_part)), |n| LabelIdentifierToken(None, n));
+
+	// Otherwise, match prefix and actual identifier as pair.
 	// The first part is optional.
 	let prefix = opt(terminated(label_identifier_token_part, single_char('.')));
-	map(pair(prefix, label_identifier_token_part), |(p, n)| LabelIdentifierToken(p, n))(i)
+	let prefixed = map(pair(prefix, label_identifier_token_part), |(p, n)| LabelIdentifierToken(p, n));
+
+	alt((scoped, prefixed))(i)
 }
 
 fn label_identifier_token_part(i: &str) -> IResult<&str, &str>
@@ -327,63 +582,124 @@ fn label_identifier_token_part(i: &str) -> IResult<&str, &str>
 	recognize(pair(take_while_m_n(1, 1, cond_alpha), take_while(cond_alphanum)))(i)
 }
 
-fn address_token(i: &str) -> IResult<&str, AddressToken>
+fn address_token(i: &str) -> IResult<&str, AddressToken<'_>>
 {
-	// Match either a word or a label identifier and map both to our algebraic data type:
-	let word_match = map(word_token, |t| AddressToken::Address(t));
-	let label_identifier_match = map(label_identifier_token, |t| AddressToken::Label(t));
+	// Match either a word or a label identifier (with an optional offset) and map both to our algebraic data type:
+	let word_match = map(word_expression_token, AddressToken::Address);
+	let label_identifier_match = map(pair(label_identifier_token, opt(offset_token)), |(t, o)| match o
+	{
+		Some(o) 	=> AddressToken::LabelOffset(t, o),
+		None 		=> AddressToken::Label(t),
+	});
 
 	alt((word_match, label_identifier_match))(i)
 }
 
-fn label_definition_token(i: &str) -> IResult<&str, LabelDefinitionToken>
+fn value_token(i: &str) -> IResult<&str, ValueToken<'_>>
+{
+	// Match either a word or the name of a constant (no prefix allowed, but an optional offset):
+	let word_match = map(word_expression_token, ValueToken::Word);
+	let constant_match = map(pair(label_identifier_token_part, opt(offset_token)), |(n, o)| match o
+	{
+		Some(o) 	=> ValueToken::ConstantOffset(n, o),
+		None 		=> ValueToken::Constant(n),
+	});
+
+	alt((word_match, constant_match))(i)
+}
+
+fn label_definition_token(i: &str) -> IResult<&str, LabelDefinitionToken<'_>>
 {
 	// Match identifier (terminated by ':') and wrap it:
-	map(terminated(label_identifier_token, single_char(':')), |i| LabelDefinitionToken(i))(i)
+	map(terminated(label_identifier_token, single_char(':')), LabelDefinitionToken)(i)
+}
+
+fn constant_definition_token(i: &str) -> IResult<&str, ConstantDefinitionToken<'_>>
+{
+	// The name is terminated by ':' (like a label definition), followed by "equ" and the word:
+	let name = terminated(label_identifier_token_part, single_char(':'));
+	let definition = preceded(tuple((space0, tag_no_case("equ"), space1)), word_expression_token);
+
+	map(pair(name, definition), |(n, w)| ConstantDefinitionToken(n, w))(i)
+}
+
+fn start_token(i: &str) -> IResult<&str, AddressToken<'_>>
+{
+	// The entry point is an address or label, preceded by "start" and at least one space:
+	preceded(pair(tag_no_case("start"), space1), address_token)(i)
 }
 
-fn data_token(i: &str) -> IResult<&str, DataToken>
+fn data_token(i: &str) -> IResult<&str, DataToken<'_>>
 {
-	// First, we have the actual definition of a word, preceded by "dat" and at least one space:
-	let definition = preceded(pair(tag_no_case("dat"), space1), word_token);
+	// First, we have the actual definition of a word (or constant), preceded by "dat" and at least one space:
+	let definition = preceded(pair(tag_no_case("dat"), space1), value_token);
 
 	// Then there might be a repitition count.
-	// It is a word, preceded by [space1, "times", space1].
-	let repitition = preceded(tuple((space1, tag_no_case("times"), space1)), word_token);
+	// It is a word (or constant), preceded by [space1, "times", space1].
+	let repitition = preceded(tuple((space1, tag_no_case("times"), space1)), value_token);
 
 	// Assemble everything:
 	map(pair(definition, opt(repitition)), |(d, t)| DataToken(d, t))(i)
 }
 
-fn instruction_token(i: &str) -> IResult<&str, InstructionToken>
+fn string_token(i: &str) -> IResult<&str, StringToken<'_>>
+{
+	// "strz" appends a NUL terminator, "str" does not:
+	let directive = alt((value(true, tag_no_case("strz")), value(false, tag_no_case("str"))));
+
+	// The content is a sequence of (potentially escaped) characters, enclosed in double quotes:
+	let content = delimited(single_char('"'), recognize(many0_count(escaped_char('"'))), single_char('"'));
+
+	map(separated_pair(directive, space1, content), |(t, s)| StringToken(s, t))(i)
+}
+
+fn instruction_token(i: &str) -> IResult<&str, InstructionToken<'_>>
 {
 	// Match on one big alternative of all the instructions.
 	// Some instructions are simple case-insensitive tags.
-	// All others are words (ldc, rar) or addresses, preceded by a case-insensitive tag and at least one space.
+	// All others are values (ldc, rar) or addresses, preceded by a case-insensitive tag and at least one space.
+	// Simple tags must span a whole identifier (otherwise, "hltx" would be "hlt" followed by garbage).
 	let instr_address_arg 	= |opcode| preceded(pair(tag_no_case(opcode), space1), address_token);
-	let instr_word_arg		= |opcode| preceded(pair(tag_no_case(opcode), space1), word_token);
-	let instr_no_arg 		= |opcode| tag_no_case(opcode);
+	let instr_word_arg		= |opcode| preceded(pair(tag_no_case(opcode), space1), value_token);
+	let instr_no_arg 		= |opcode: &'static str| verify(label_identifier_token_part, move |s: &str| s.eq_ignore_ascii_case(opcode));
 
 	// "Return" construct needed for the borrow checker ...
 	return alt
 	((
-		|s| map(instr_address_arg("add"), 	|a| InstructionToken::Add(a))(s),
-		|s| map(instr_address_arg("and"), 	|a| InstructionToken::And(a))(s),
-		|s| map(instr_address_arg("or"), 	|a| InstructionToken::Or(a))(s),
-		|s| map(instr_address_arg("xor"), 	|a| InstructionToken::Xor(a))(s),
-		|s| map(instr_address_arg("ldv"), 	|a| InstructionToken::LoadValue(a))(s),
-		|s| map(instr_address_arg("stv"), 	|a| InstructionToken::StoreValue(a))(s),
-		|s| map(instr_word_arg("ldc"), 		|w| InstructionToken::LoadConstant(w))(s),
-		|s| map(instr_address_arg("jmp"), 	|a| InstructionToken::Jump(a))(s),
-		|s| map(instr_address_arg("jmn"), 	|a| InstructionToken::JumpIfNegative(a))(s),
-		|s| map(instr_address_arg("eql"), 	|a| InstructionToken::Equals(a))(s),
+		|s| map(instr_address_arg("add"), 	InstructionToken::Add)(s),
+		|s| map(instr_address_arg("and"), 	InstructionToken::And)(s),
+		|s| map(instr_address_arg("or"), 	InstructionToken::Or)(s),
+		|s| map(instr_address_arg("xor"), 	InstructionToken::Xor)(s),
+		|s| map(instr_address_arg("ldv"), 	InstructionToken::LoadValue)(s),
+		|s| map(instr_address_arg("stv"), 	InstructionToken::StoreValue)(s),
+		|s| map(instr_word_arg("ldc"), 		InstructionToken::LoadConstant)(s),
+		|s| map(instr_address_arg("jmp"), 	InstructionToken::Jump)(s),
+		|s| map(instr_address_arg("jmn"), 	InstructionToken::JumpIfNegative)(s),
+		|s| map(instr_address_arg("jmz"), 	InstructionToken::JumpIfZero)(s),
+		|s| map(instr_address_arg("jme"), 	InstructionToken::JumpIfEqual)(s),
+		|s| map(instr_address_arg("eql"), 	InstructionToken::Equals)(s),
+		|s| map(instr_address_arg("cmp"), 	InstructionToken::Compare)(s),
+		|s| map(instr_address_arg("sub"), 	InstructionToken::Subtract)(s),
 		|s| map(instr_no_arg("hlt"), 		|_| InstructionToken::Halt)(s),
 		|s| map(instr_no_arg("not"), 		|_| InstructionToken::Not)(s),
-		|s| map(instr_word_arg("rar"), 		|w| InstructionToken::RotateRight(w))(s),
-		|s| map(tag_no_case("nop"), 		|_| InstructionToken::NoOperation)(s),
+		|s| map(instr_word_arg("rar"), 		InstructionToken::RotateRight)(s),
+		|s| map(instr_word_arg("ral"), 		InstructionToken::RotateLeft)(s),
+		|s| map(instr_no_arg("skn"), 		|_| InstructionToken::SkipIfNegative)(s),
+		|s| map(instr_no_arg("nop"), 		|_| InstructionToken::NoOperation)(s),
 	))(i);
 }
 
+// An include directive stands on its own line: "include", at least one space and the path in double quotes (optionally followed by a comment).
+// It is not a statement, but is spliced away before parsing (see "SourceSet").
+fn include_directive(line: &str) -> Option<&str>
+{
+	let directive = tuple((space0, tag_no_case("include"), space1));
+	let path = delimited(single_char('"'), take_while1(|c| c != '"'), single_char('"'));
+	let trailer = pair(space0, opt(comment_token));
+
+	all_consuming(delimited(directive, path, trailer))(line).ok().map(|(_, path)| path)
+}
+
 fn comment_token(i: &str) -> IResult<&str, ()>
 {
 	// First '#', then anything except line ending.
@@ -391,8 +707,73 @@ fn comment_token(i: &str) -> IResult<&str, ()>
 	map(pair(single_char('#'), not_line_ending), |_| ())(i)
 }
 
-// The input string must not contain a line ending!
-fn statement_token(line_number: usize, i: &str) -> Result<Option<StatementToken>, ParserError>
+fn block_comment_token(i: &str) -> IResult<&str, ()>
+{
+	// Either the comment is closed in the same line or it continues in the next one (=> drop the rest of the line):
+	let closed = delimited(tag("/*"), take_until("*/"), tag("*/"));
+	let open = preceded(tag("/*"), not_line_ending);
+
+	map(alt((closed, open)), |_| ())(i)
+}
+
+fn gap_token(i: &str) -> IResult<&str, ()>
+{
+	// Whitespace and block comments in arbitrary order:
+	map(many0_count(alt((space1, recognize(block_comment_token)))), |_| ())(i)
+}
+
+// Does the given code end inside of a block comment?
+// Comment delimiters in string / character literals and behind '#' do not count.
+fn ends_in_block_comment(code: &str) -> bool
+{
+	let mut chars = code.chars().peekable();
+	let mut in_comment = false;
+	let mut literal_delimiter = None;
+
+	while let Some(c) = chars.next()
+	{
+		match (in_comment, literal_delimiter, c)
+		{
+			(true, _, '*') if chars.peek() == Some(&'/') 		=> { chars.next(); in_comment = false; },
+			(true, _, _) 										=> (),
+			(false, Some(_), '\\') 								=> { chars.next(); },
+			(false, Some(d), c) if c == d 						=> literal_delimiter = None,
+			(false, Some(_), _) 								=> (),
+			(false, None, '"') | (false, None, '\'') 			=> literal_delimiter = Some(c),
+			(false, None, '#') 									=> return false,
+			(false, None, '/') if chars.peek() == Some(&'*') 	=> { chars.next(); in_comment = true; },
+			_ 													=> (),
+		}
+	}
+
+	in_comment
+}
+
+// Split the input into numbered lines.
+// Block comments may span multiple lines. If a line continues a block comment, its code starts behind the closing "*/".
+// Lines that are completely commented out have no code at all.
+fn code_lines(input: &str) -> impl Iterator<Item = (usize, &str, &str)>
+{
+	input.lines().enumerate().scan(false, |in_comment, (line_number, line)|
+	{
+		let code_start = if *in_comment { line.find("*/").map(|pos| pos + 2) } else { Some(0) };
+		let code = code_start.map_or("", |start| &line[start..]);
+
+		*in_comment = code_start.is_none() || ends_in_block_comment(code);
+		Some((line_number, line, code))
+	})
+}
+
+// Split the input into numbered lines and recognize the include directives among them (see "code_lines").
+// A line that continues a block comment from previous lines is never a directive.
+pub(crate) fn include_lines(input: &str) -> impl Iterator<Item = (usize, &str, Option<&str>)>
+{
+	code_lines(input).map(|(line_number, line, code)| (line_number, line, Some(code).filter(|code| code.len() == line.len()).and_then(include_directive)))
+}
+
+// The code is a suffix of the line (behind the end of a block comment from previous lines).
+// The line must not contain a line ending!
+fn statement_token<'src>(line_number: usize, line: &'src str, i: &'src str) -> Result<Option<StatementToken<'src>>, ParserError<'src>>
 {
 	// The labels are a whitespace-separated list.
 	// We cannot use "separated_list" or "many0" in direct combination with "space0" because of nom's endless-loop-detection (see https://github.com/Geal/nom/issues/834).
@@ -400,19 +781,23 @@ fn statement_token(line_number: usize, i: &str) -> Result<Option<StatementToken>
 	let label_defs = many0(terminated(label_definition_token, space0));
 
 	// The data / instruction token (both mapped to a statement content token for type soundness) is an alternative:
-	let stmt_content_data = map(data_token, |t| StatementContentToken::Data(t));
-	let stmt_content_instruction = map(instruction_token, |t| StatementContentToken::Instruction(t));
-	let stmt_content = alt((stmt_content_data, stmt_content_instruction));
+	let stmt_content_data = map(data_token, StatementContentToken::Data);
+	let stmt_content_string = map(string_token, StatementContentToken::String);
+	let stmt_content_instruction = map(instruction_token, StatementContentToken::Instruction);
+	let stmt_content = alt((stmt_content_data, stmt_content_string, stmt_content_instruction));
 
 	// Combine both parts.
 	// The statement content is optional.
-	let center = pair(label_defs, opt(stmt_content));
+	// Constant definitions and the entry point directive are alternatives to the whole combination.
+	let stmt_constant = map(constant_definition_token, |t| (vec![], Some(StatementContentToken::Constant(t))));
+	let stmt_start = map(start_token, |t| (vec![], Some(StatementContentToken::Start(t))));
+	let center = alt((stmt_constant, stmt_start, pair(label_defs, opt(stmt_content))));
 
-	// The statement start is optional whitespace:
-	let start = space0;
+	// The statement start is optional whitespace (or block comments):
+	let start = gap_token;
 
-	// The statement end is a sequence of 0...n whitespaces and an optional comment:
-	let end = pair(space0, opt(comment_token));
+	// The statement end is a sequence of 0...n whitespaces (or block comments) and an optional comment:
+	let end = pair(gap_token, opt(comment_token));
 
 	// Now combine everything and capture the center.
 	// The input string must be completely consumed.
@@ -421,34 +806,247 @@ fn statement_token(line_number: usize, i: &str) -> Result<Option<StatementToken>
 	// For empty statements, we return Ok(None).
 	map(all_consuming(delimited(start, center, end)), |(l, s)| StatementToken::new(line_number, l, s))(i)
 		.map(|(_, stmt)| if stmt.is_empty() { None } else { Some(stmt) })
-		.map_err(|err|
-		{
-			let token = match err
-			{
-				Err::Error(tuple) | Err::Failure(tuple) 	=> Some(tuple.0),
-				_ 											=> None,
-			};
+		.map_err(|err| parser_error(line_number, line, err))
+}
 
-			ParserError::new(line_number, token)
-		})
+// Map nom's error for the given line to our parser error:
+fn parser_error<'src>(line_number: usize, line: &'src str, err: Err<NomError<&'src str>>) -> ParserError<'src>
+{
+	let (token, err_type) = match err
+	{
+		Err::Error(err) | Err::Failure(err) if err.code == ErrorKind::TooLarge 	=> (Some(err.input), ParserErrorType::LiteralOutOfRange),
+		Err::Error(err) | Err::Failure(err) 									=> (Some(err.input), ParserErrorType::BadToken),
+		_ 																		=> (None, ParserErrorType::BadToken),
+	};
+
+	// The failing token is a suffix of the line.
+	// Its column is the number of characters in front of it:
+	let column = token.map_or(0, |t| line[..(line.len() - t.len())].chars().count());
+
+	ParserError::new(line_number, column, token, err_type)
 }
 
 // Expose a public interface for parsing a program token from a string slice:
 impl<'src> ProgramToken<'src>
 {
 	// The input string contains the statements, separated by line endings.
-	pub fn parse(input: &str) -> Result<ProgramToken, ParserError>
+	pub fn parse(input: &str) -> Result<ProgramToken<'_>, ParserError<'_>>
 	{
 		// Iterate through the lines.
 		// Generate line numbers.
 		// Construct a statement token from each line number and line.
 		// Transpose Result<Option<StatementToken>> to Option<Result<StatementToken>> and filter => iterator over Result<StatementToken, _>.
 		// Then collect into a vector until we have them all or an error occurs.
-		let statements = input.lines()
-			.enumerate()
-			.filter_map(|(line_number, line)| statement_token(line_number, line).transpose())
+		let statements = code_lines(input)
+			.filter_map(|(line_number, line, code)| statement_token(line_number, line, code).transpose())
 			.collect::<Result<_, _>>()?;
 
 		Ok(ProgramToken(statements))
 	}
+
+	// Like "parse", but don't stop at the first bad statement.
+	// All good statements are collected into the program token, all bad ones into the error vector.
+	pub fn parse_collecting_errors(input: &str) -> (ProgramToken<'_>, Vec<ParserError<'_>>)
+	{
+		let mut statements = vec![];
+		let mut errors = vec![];
+
+		for (line_number, line, code) in code_lines(input)
+		{
+			match statement_token(line_number, line, code)
+			{
+				Ok(Some(stmt)) 	=> statements.push(stmt),
+				Ok(None) 		=> (),
+				Err(err) 		=> errors.push(err),
+			}
+		}
+
+		(ProgramToken(statements), errors)
+	}
+
+	// Like "parse", but replace every bad statement by an error placeholder and continue.
+	// The resulting program token covers the whole input.
+	pub fn parse_recover(input: &str) -> ProgramToken<'_>
+	{
+		let statements = code_lines(input)
+			.filter_map(|(line_number, line, code)| match statement_token(line_number, line, code)
+			{
+				Ok(stmt) 	=> stmt,
+				Err(err) 	=> Some(StatementToken::new(line_number, vec![], Some(StatementContentToken::Error(err)))),
+			})
+			.collect();
+
+		ProgramToken(statements)
+	}
+
+	// Collect the errors of all placeholder statements:
+	pub fn errors(&self) -> Vec<ParserError<'src>>
+	{
+		self.0.iter().filter_map(|stmt| match stmt.content
+		{
+			Some(StatementContentToken::Error(err)) 	=> Some(err),
+			_ 											=> None,
+		}).collect()
+	}
+}
+
+// Expose a public interface for parsing a single instruction (without labels, comments or statement framing) from a string slice:
+impl Instruction
+{
+	// Only literal operands can be resolved here.
+	// Labels, constants and literals that exceed the payload are reported as a parser error at the operand.
+	pub fn parse<'src>(input: &'src str) -> Result<Instruction, ParserError<'src>>
+	{
+		let (_, instruction) = all_consuming(delimited(space0, instruction_token, space0))(input)
+			.map_err(|err| parser_error(0, input, err))?;
+
+		// The operand is what remains after the mnemonic and the spaces behind it:
+		let (operand, _) = tuple((space0, label_identifier_token_part, space0))(input)
+			.map_err(|err| parser_error(0, input, err))?;
+		let operand = operand.trim_end();
+		let operand_error = || ParserError::new(0, input[..(input.len() - operand.len())].chars().count(), Some(operand), ParserErrorType::BadToken);
+
+		let address = |a| match a
+		{
+			AddressToken::Address(w) 	=> ObjectCode::check_address(w, 0).map_err(|_| operand_error()),
+			_ 							=> Err(operand_error()),
+		};
+
+		let value = |v, bits| match v
+		{
+			ValueToken::Word(w) 		=> ObjectCode::check_operand(w, bits, 0).map_err(|_| operand_error()),
+			_ 							=> Err(operand_error()),
+		};
+
+		Ok(match instruction
+		{
+			InstructionToken::Add(a) 				=> Instruction::Add(address(a)?),
+			InstructionToken::And(a) 				=> Instruction::And(address(a)?),
+			InstructionToken::Or(a) 				=> Instruction::Or(address(a)?),
+			InstructionToken::Xor(a) 				=> Instruction::Xor(address(a)?),
+			InstructionToken::LoadValue(a) 			=> Instruction::LoadValue(address(a)?),
+			InstructionToken::StoreValue(a) 		=> Instruction::StoreValue(address(a)?),
+			InstructionToken::LoadConstant(v) 		=> Instruction::LoadConstant(value(v, ObjectCode::BASIC_PAYLOAD_BITS)?),
+			InstructionToken::Jump(a) 				=> Instruction::Jump(address(a)?),
+			InstructionToken::JumpIfNegative(a) 	=> Instruction::JumpIfNegative(address(a)?),
+			InstructionToken::JumpIfZero(a) 		=> Instruction::JumpIfZero(address(a)?),
+			InstructionToken::JumpIfEqual(a) 		=> Instruction::JumpIfEqual(address(a)?),
+			InstructionToken::Equals(a) 			=> Instruction::Equals(address(a)?),
+			InstructionToken::Compare(a) 			=> Instruction::Compare(address(a)?),
+			InstructionToken::Subtract(a) 			=> Instruction::Subtract(address(a)?),
+			InstructionToken::Halt 					=> Instruction::Halt,
+			InstructionToken::Not 					=> Instruction::Not,
+			InstructionToken::RotateRight(v) 		=> Instruction::RotateRight(value(v, ObjectCode::EXTENDED_PAYLOAD_BITS)?),
+			InstructionToken::RotateLeft(v) 		=> Instruction::RotateLeft(value(v, ObjectCode::EXTENDED_PAYLOAD_BITS)?),
+			InstructionToken::SkipIfNegative 		=> Instruction::SkipIfNegative,
+			InstructionToken::NoOperation 			=> Instruction::NoOperation,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn recover_keeps_statements_around_bad_line()
+	{
+		let program = ProgramToken::parse_recover("start: ldc 1\nthis is not mima\nhlt");
+		let statements = &program.0;
+
+		assert_eq!(statements.len(), 3);
+		assert_eq!(statements[0].label_defs.len(), 1);
+		assert!(matches!(statements[0].content, Some(StatementContentToken::Instruction(InstructionToken::LoadConstant(_)))));
+		assert!(matches!(statements[1].content, Some(StatementContentToken::Error(_))));
+		assert!(matches!(statements[2].content, Some(StatementContentToken::Instruction(InstructionToken::Halt))));
+
+		let errors = program.errors();
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].line_number(), 1);
+	}
+
+	#[test]
+	fn parse_single_instruction()
+	{
+		assert!(matches!(Instruction::parse("LDV 0x10"), Ok(Instruction::LoadValue(Word(0x10)))));
+		assert!(matches!(Instruction::parse("HLT"), Ok(Instruction::Halt)));
+		assert!(matches!(Instruction::parse(" RAR 3 "), Ok(Instruction::RotateRight(Word(3)))));
+	}
+
+	#[test]
+	fn parse_single_instruction_rejects_labels()
+	{
+		let err = Instruction::parse("LDV loop").err().unwrap();
+		assert_eq!(err.column(), 4);
+		assert_eq!(err.token(), Some("loop"));
+
+		// The operand is located behind mnemonics of any length:
+		let err = Instruction::parse("  or   mask").err().unwrap();
+		assert_eq!(err.column(), 7);
+		assert_eq!(err.token(), Some("mask"));
+	}
+
+	#[test]
+	fn parse_single_instruction_rejects_malformed_input()
+	{
+		assert!(Instruction::parse("").is_err());
+		assert!(Instruction::parse("FOO 1").is_err());
+		assert!(Instruction::parse("HLT 1").is_err());
+		assert!(Instruction::parse("loop: HLT").is_err());
+		assert!(Instruction::parse("LDV 1 # comment").is_err());
+	}
+
+	#[test]
+	fn literals_out_of_word_range()
+	{
+		for literal in ["0x100000000", "-2147483649", "4294967296"]
+		{
+			let source = format!("dat {:}", literal);
+			let err = ProgramToken::parse(&source).err().unwrap();
+
+			assert_eq!(err.err_type(), ParserErrorType::LiteralOutOfRange);
+			assert_eq!(err.column(), 4);
+		}
+	}
+
+	#[test]
+	fn literals_at_word_range_bounds()
+	{
+		for literal in ["0xFFFFFFFF", "-2147483648", "4294967295"]
+		{
+			assert!(ProgramToken::parse(&format!("dat {:}", literal)).is_ok());
+		}
+	}
+
+	#[test]
+	fn mnemonics_need_word_boundary()
+	{
+		assert_eq!(ProgramToken::parse("hltx").err().unwrap().column(), 0);
+		assert_eq!(ProgramToken::parse("notacc").err().unwrap().column(), 0);
+
+		// A comment is a boundary:
+		assert!(ProgramToken::parse("hlt# done").is_ok());
+	}
+
+	#[test]
+	fn labels_may_start_with_mnemonics()
+	{
+		let program = ProgramToken::parse("nop_loop: nop\njmp nop_loop").unwrap();
+		let statements = &program.0;
+
+		assert_eq!(statements[0].label_defs.len(), 1);
+		assert_eq!(statements[0].label_defs[0].0.1, "nop_loop");
+		assert!(matches!(statements[0].content, Some(StatementContentToken::Instruction(InstructionToken::NoOperation))));
+		assert!(matches!(statements[1].content, Some(StatementContentToken::Instruction(InstructionToken::Jump(AddressToken::Label(_))))));
+	}
+
+	#[test]
+	fn includes_in_block_comments_are_ignored()
+	{
+		let source = "include \"a.asm\"\n/* disabled:\ninclude \"b.asm\"\n*/\n  include \"c.asm\" # enabled";
+		let includes: Vec<_> = include_lines(source).filter_map(|(line_number, _, include)| include.map(|path| (line_number, path))).collect();
+
+		assert_eq!(includes, [(0, "a.asm"), (4, "c.asm")]);
+	}
 }
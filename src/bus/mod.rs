@@ -1,3 +1,4 @@
+use std::fmt;
 use crate::types::{*, Registers as Regs};
 
 // Sources and destinations for bus transfers.
@@ -6,7 +7,50 @@ use crate::types::{*, Registers as Regs};
 // The Transfer type checks those constraints.
 pub use Xfer as BusXfer;
 
-// A bus transfer holds a source and 1...n destinations:
+// What happened on the bus during a microcycle with a transfer?
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BusValue
+{
+	// The (masked) source word has been written to the destinations:
+	Propagated(Word),
+
+	// The transfer is accumulator-dependent and its condition did not hold:
+	Suppressed,
+}
+
+// The conditions an accumulator-dependent bus transfer can depend on.
+// The EQUAL flag of the ALU is evaluated along with the accumulator:
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum AccCondition
+{
+	// ACC < 0 (JMN)
+	Negative,
+
+	// ACC == 0 (JMZ)
+	Zero,
+
+	// EQUAL flag set (JME)
+	Equal,
+}
+
+impl AccCondition
+{
+	pub fn is_satisfied(self, acc: Word, equal: Flag) -> bool
+	{
+		match self
+		{
+			AccCondition::Negative 	=> (acc.0 & Word::SIGN_BIT.0) != 0,
+			AccCondition::Zero 		=> acc.0 == 0,
+			AccCondition::Equal 	=> equal.0,
+		}
+	}
+}
+
+// A bus transfer holds a source and 1...n destinations.
+// It can be serialized (e. g. as part of a microcycle summary), but not deserialized (that would bypass the validation in "new"):
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Xfer
 {
 	source: Regs,
@@ -19,11 +63,15 @@ pub struct Xfer
 	source_bitmask: Word,
 
 	// A bus transfer can be executed "accumulator-dependent".
-	// This is specifically important for the JMN instruction and translates to:
+	// This is specifically important for the conditional jumps and translates to (e. g. for JMN):
 	// "Execute the transfer if and only if ACC < 0."
 	// By default, bus transfers are not accumulator-dependent.
 	// Call "make_acc_dependent()" on them to change that.
-	is_acc_dependent: bool
+	acc_condition: Option<AccCondition>,
+
+	// The masked source can be a signed immediate (e. g. the constant of LDC).
+	// If the MiMA is configured to sign-extend constants, the uppermost bit of the mask is copied into the bits above it.
+	sign_extending: bool,
 }
 
 impl Xfer
@@ -36,7 +84,7 @@ impl Xfer
 	pub(crate) fn new(source: Regs, destinations: Regs, source_bitmask: Word) -> Xfer
 	{
 		// Validate counts:
-		let source_count = Regs::ALL_REGISTERS.iter().filter(|&&curr_source| source.contains(curr_source)).count();
+		let source_count = source.iter().count();
 
 		assert!(source_count == 1, "Bus source registers must contain exactly one register.");
 		assert!(!destinations.is_empty(), "Bus destination registers must not be empty.");
@@ -56,7 +104,8 @@ impl Xfer
 			source,
 			destinations,
 			source_bitmask,
-			is_acc_dependent: false,
+			acc_condition: None,
+			sign_extending: false,
 		}
 	}
 
@@ -77,12 +126,34 @@ impl Xfer
 
 	pub fn is_acc_dependent(&self) -> bool
 	{
-		self.is_acc_dependent
+		self.acc_condition.is_some()
 	}
 
-	pub(crate) fn make_acc_dependent(&mut self)
+	pub fn acc_condition(&self) -> Option<AccCondition>
 	{
-		self.is_acc_dependent = true;
+		self.acc_condition
+	}
+
+	// Is the transfer executed for the given accumulator (and EQUAL flag)?
+	// Transfers that are not accumulator-dependent are always executed.
+	pub fn is_satisfied(&self, acc: Word, equal: Flag) -> bool
+	{
+		self.acc_condition.is_none_or(|condition| condition.is_satisfied(acc, equal))
+	}
+
+	pub(crate) fn make_acc_dependent(&mut self, condition: AccCondition)
+	{
+		self.acc_condition = Some(condition);
+	}
+
+	pub fn is_sign_extending(&self) -> bool
+	{
+		self.sign_extending
+	}
+
+	pub(crate) fn make_sign_extending(&mut self)
+	{
+		self.sign_extending = true;
 	}
 
 	fn validate_source_bitmask(source: Regs, source_bitmask: Word) -> bool
@@ -90,7 +161,22 @@ impl Xfer
 		match source
 		{
 			Regs::IR 	=> (source_bitmask == Xfer::SOURCE_BITMASK_BASIC_PAYLOAD) || (source_bitmask == Xfer::SOURCE_BITMASK_EXTENDED_PAYLOAD),
-			_ 			=> (source_bitmask == Xfer::SOURCE_BITMASK_FULL),
+			_ 			=> source_bitmask == Xfer::SOURCE_BITMASK_FULL,
 		}
 	}
 }
+
+// The registers are shown by their names (e. g. "[X, SAR]") instead of the raw bitflags:
+impl fmt::Debug for Xfer
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		f.debug_struct("Xfer")
+			.field("source", &format_args!("{:}", self.source))
+			.field("destinations", &format_args!("{:}", self.destinations))
+			.field("source_bitmask", &format_args!("{:}", self.source_bitmask))
+			.field("acc_condition", &self.acc_condition)
+			.field("sign_extending", &self.sign_extending)
+			.finish()
+	}
+}
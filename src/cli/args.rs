@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+// What is drawn while the MiMA runs:
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum View
+{
+	// The microcycle diagram with the watch and memory panels (and the cycle diagram after every instruction):
+	Full,
+
+	// Only the cycle diagram after every instruction:
+	Cycle,
+
+	// One line per microcycle (see "record::render_oneline"):
+	Oneline,
+}
+
+// The parsed command line:
+// mimasim [--auto] [--speed <ms>] [--view full|cycle|oneline] [<source file>]
+pub struct Args
+{
+	// The assembly source to run (the built-in demo if there is none):
+	pub path: Option<String>,
+
+	// Animate instead of waiting for keys (see "Stepper"):
+	pub auto: bool,
+
+	// The delay between two microcycles of the animation (implies "auto"):
+	pub speed: Option<Duration>,
+
+	pub view: View,
+}
+
+// This error type occurs when the command line cannot be parsed:
+#[derive(Debug)]
+pub enum ArgsError
+{
+	UnknownOption(String),
+	MissingValue(&'static str),
+	BadSpeed(String),
+	BadView(String),
+	SurplusArgument(String),
+}
+
+impl fmt::Display for ArgsError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			ArgsError::UnknownOption(o) 	=> write!(f, "Unknown option \"{:}\".", o),
+			ArgsError::MissingValue(o) 		=> write!(f, "The option \"{:}\" needs a value.", o),
+			ArgsError::BadSpeed(s) 			=> write!(f, "The speed \"{:}\" is not a delay in milliseconds.", s),
+			ArgsError::BadView(s) 			=> write!(f, "The view \"{:}\" is unknown (expected \"full\", \"cycle\" or \"oneline\").", s),
+			ArgsError::SurplusArgument(s) 	=> write!(f, "Only one source file can be given (surplus argument \"{:}\").", s),
+		}
+	}
+}
+
+impl Error for ArgsError { }
+
+impl Args
+{
+	pub const USAGE: &'static str = "Usage: mimasim [--auto] [--speed <ms>] [--view full|cycle|oneline] [<source file>]";
+
+	// Parse the arguments (without the name of the executable):
+	pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, ArgsError>
+	{
+		let mut parsed = Args
+		{
+			path: None,
+			auto: false,
+			speed: None,
+			view: View::Full,
+		};
+
+		let mut args = args.into_iter();
+
+		while let Some(arg) = args.next()
+		{
+			match arg.as_str()
+			{
+				"--auto" 					=> parsed.auto = true,
+				"--speed" 					=>
+				{
+					let value = args.next().ok_or(ArgsError::MissingValue("--speed"))?;
+					let delay_ms = value.parse().map_err(|_| ArgsError::BadSpeed(value))?;
+
+					parsed.speed = Some(Duration::from_millis(delay_ms));
+					parsed.auto = true;
+				},
+				"--view" 					=>
+				{
+					let value = args.next().ok_or(ArgsError::MissingValue("--view"))?;
+
+					parsed.view = match value.as_str()
+					{
+						"full" 		=> View::Full,
+						"cycle" 	=> View::Cycle,
+						"oneline" 	=> View::Oneline,
+						_ 			=> return Err(ArgsError::BadView(value)),
+					};
+				},
+				_ if arg.starts_with("--") 	=> return Err(ArgsError::UnknownOption(arg)),
+				_ if parsed.path.is_some() 	=> return Err(ArgsError::SurplusArgument(arg)),
+				_ 							=> parsed.path = Some(arg),
+			}
+		}
+
+		Ok(parsed)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn parse(args: &[&str]) -> Result<Args, ArgsError>
+	{
+		Args::parse(args.iter().map(|arg| arg.to_string()))
+	}
+
+	#[test]
+	fn speed_implies_auto()
+	{
+		let args = parse(&["--speed", "100", "--view", "oneline", "prog.asm"]).unwrap();
+		assert!(args.auto);
+		assert_eq!(args.speed, Some(Duration::from_millis(100)));
+		assert!(args.view == View::Oneline);
+		assert_eq!(args.path.as_deref(), Some("prog.asm"));
+
+		let args = parse(&[]).unwrap();
+		assert!(!args.auto);
+		assert!(args.view == View::Full);
+		assert!(args.path.is_none());
+	}
+
+	#[test]
+	fn missing_value()
+	{
+		assert!(matches!(parse(&["--speed"]), Err(ArgsError::MissingValue("--speed"))));
+		assert!(matches!(parse(&["--view"]), Err(ArgsError::MissingValue("--view"))));
+	}
+
+	#[test]
+	fn unknown_view_and_option()
+	{
+		assert!(matches!(parse(&["--view", "wide"]), Err(ArgsError::BadView(view)) if view == "wide"));
+		assert!(matches!(parse(&["--fast"]), Err(ArgsError::UnknownOption(option)) if option == "--fast"));
+	}
+
+	#[test]
+	fn surplus_path()
+	{
+		assert!(matches!(parse(&["a.asm", "b.asm"]), Err(ArgsError::SurplusArgument(path)) if path == "b.asm"));
+	}
+}
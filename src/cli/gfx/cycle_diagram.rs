@@ -1,7 +1,7 @@
+use std::fmt;
 use std::io::{stdout, Write};
-use mimasim::types::{*, Registers as Regs};
 use crate::cli::term::{color, cursor, ui};
-use crate::cli::record::{CycleSummary, RegisterValue as RegValue, FlagValue};
+use mimasim::record::{CycleSummary, RegisterValue as RegValue, FlagValue};
 
 pub enum Model { }
 
@@ -26,54 +26,90 @@ const RUN_Y: u16 = IAR_Y;
 const TRA_X: u16 = RUN_X + FLAG_WIDTH;
 const TRA_Y: u16 = RUN_Y;
 
+const CRY_X: u16 = TRA_X + FLAG_WIDTH;
+const CRY_Y: u16 = TRA_Y;
+
+const OVF_X: u16 = CRY_X + FLAG_WIDTH;
+const OVF_Y: u16 = CRY_Y;
+
+const EQU_X: u16 = OVF_X + FLAG_WIDTH;
+const EQU_Y: u16 = OVF_Y;
+
 impl Model
 {
+	// The diagram is a single row of register and flag boxes:
+	pub const HEIGHT: u16 = REG_HEIGHT;
+
+	// Draw the diagram to the terminal:
 	pub fn draw_from_summary(summary: &CycleSummary, x: u16, y: u16)
 	{
-		// Draw accumulator and flags as named boxes:
-		Model::draw_register(x + ACC_X, y + ACC_Y, "ACC", summary.acc);
-		Model::draw_flag(x + RUN_X, y + RUN_Y, "RUN", summary.run);
-		Model::draw_flag(x + TRA_X, y + TRA_Y, "TRA", summary.tra);
-		Model::draw_register(x + IAR_X, y + IAR_Y, "IAR", summary.iar);
+		print!("{:}", Model::draw_to_string(summary, x, y));
 
 		// Flush the output:
 		stdout().flush().expect("Failed to flush terminal.");
 	}
 
-	fn draw_register(reg_x: u16, reg_y: u16, name: &str, value: RegValue)
+	// Draw the diagram into a string (e. g. to embed it or to compare it against a snapshot):
+	pub fn draw_to_string(summary: &CycleSummary, x: u16, y: u16) -> String
+	{
+		let mut out = String::new();
+		Model::draw(&mut out, summary, x, y).expect("Failed to draw into a string.");
+
+		out
+	}
+
+	// Draw the diagram into any fmt::Write target (cursor positioning, colors and styles are escape sequences in the output):
+	pub fn draw(out: &mut dyn fmt::Write, summary: &CycleSummary, x: u16, y: u16) -> fmt::Result
+	{
+		// Draw accumulator and flags as named boxes:
+		Model::draw_register(out, x + ACC_X, y + ACC_Y, "ACC", summary.acc)?;
+		Model::draw_flag(out, x + RUN_X, y + RUN_Y, "RUN", summary.run)?;
+		Model::draw_flag(out, x + TRA_X, y + TRA_Y, "TRA", summary.tra)?;
+		Model::draw_flag(out, x + CRY_X, y + CRY_Y, "CRY", summary.carry)?;
+		Model::draw_flag(out, x + OVF_X, y + OVF_Y, "OVF", summary.overflow)?;
+		Model::draw_flag(out, x + EQU_X, y + EQU_Y, "EQU", summary.equal)?;
+		Model::draw_register(out, x + IAR_X, y + IAR_Y, "IAR", summary.iar)?;
+
+		Ok(())
+	}
+
+	fn draw_register(out: &mut dyn fmt::Write, reg_x: u16, reg_y: u16, name: &str, value: RegValue) -> fmt::Result
 	{
 		// Draw a box around the register:
-		ui::draw_named_box(reg_x, reg_y, REG_WIDTH, REG_HEIGHT, color::LightBlack, name, color::White, false);
+		ui::draw_named_box(out, reg_x, reg_y, REG_WIDTH, REG_HEIGHT, name, ui::BoxStyle::PLAIN)?;
 
 		// Write the content:
 		match value
 		{
 			RegValue::Stasis(v) =>
 			{
-				print!("{goto0}{fg_color0}0x{value:08X}{goto1}{fg_color1} ────────── ",
+				write!(out, "{goto0}{fg_color0}0x{value:08X}{goto1}{fg_color1}{line}",
 					goto0 = cursor::Goto(reg_x + 2, reg_y + 1),
 					fg_color0 = color::Fg(color::White),
 					value = v.0,
 					goto1 = cursor::Goto(reg_x + 1, reg_y + 2),
-					fg_color1 = color::Fg(color::LightBlack));
+					fg_color1 = color::Fg(color::LightBlack),
+					line = ui::glyphs(" ────────── "))?;
 			},
 			RegValue::Change(old_v, new_v) =>
 			{
-				print!("{goto0}{fg_color0}0x{new_value:08X}{goto1}{fg_color1}0x{old_value:08X}",
+				write!(out, "{goto0}{fg_color0}0x{new_value:08X}{goto1}{fg_color1}0x{old_value:08X}",
 					goto0 = cursor::Goto(reg_x + 2, reg_y + 1),
 					fg_color0 = color::Fg(color::Green),
 					new_value = new_v.0,
 					goto1 = cursor::Goto(reg_x + 2, reg_y + 2),
 					fg_color1 = color::Fg(color::LightBlack),
-					old_value = old_v.0);
+					old_value = old_v.0)?;
 			},
 		}
+
+		Ok(())
 	}
 
-	fn draw_flag(x: u16, y: u16, name: &str, value: FlagValue)
+	fn draw_flag(out: &mut dyn fmt::Write, x: u16, y: u16, name: &str, value: FlagValue) -> fmt::Result
 	{
 		// Draw a box around the flag:
-		ui::draw_named_box(x, y, FLAG_WIDTH, FLAG_HEIGHT, color::LightBlack, name, color::White, false);
+		ui::draw_named_box(out, x, y, FLAG_WIDTH, FLAG_HEIGHT, name, ui::BoxStyle::PLAIN)?;
 
 		// Write the content:
 		let (color, text) = match value
@@ -82,9 +118,11 @@ impl Model
 			FlagValue::Change(_, new_v) => if new_v.0 { (color::Green, '1') } else{ (color::Red, '0') },
 		};
 
-		print!("{goto}{fg_color}{value}",
+		write!(out, "{goto}{fg_color}{value}",
 			goto = cursor::Goto(x + 2, y + 1),
 			fg_color = color::Fg(color),
-			value = text);
+			value = text)?;
+
+		Ok(())
 	}
 }
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::io::{stdout, Write};
+use crate::cli::term::{color, cursor, style, ui};
+use mimasim::mima::Mima;
+use mimasim::record::{RegisterValue, Value};
+use mimasim::types::{Instruction, Registers, Word, LINEAR_ADDRESS_SPACE_RANGE};
+
+pub enum Model { }
+
+// Measures:
+const HEX_WIDTH: u16 = 2 + 8;
+const INSTR_WIDTH: u16 = 2 * HEX_WIDTH + 2;
+const PANEL_WIDTH: u16 = 1 + 1 + 2 + HEX_WIDTH + 1 + INSTR_WIDTH + 1 + 1;
+
+impl Model
+{
+	// The number of rows the panel occupies for a window of the given size:
+	pub fn height(before: u32, after: u32) -> u16
+	{
+		(before + 1 + after) as u16 + 2
+	}
+
+	// Draw the panel to the terminal.
+	// The memory cell that has been written during the last microcycle (see "MicrocycleSummary::mem_write") is highlighted if it has changed.
+	pub fn draw_from_mima(mima: &Mima, mem_write: Option<(Word, RegisterValue)>, before: u32, after: u32, x: u16, y: u16)
+	{
+		// The panel is optional, so it is simply left out if the terminal is too small:
+		if !ui::fits_terminal(x, y, PANEL_WIDTH, Model::height(before, after))
+		{
+			return;
+		}
+
+		print!("{:}", Model::draw_to_string(mima, mem_write, before, after, x, y));
+
+		// Flush the output:
+		stdout().flush().expect("Failed to flush terminal.");
+	}
+
+	// Draw the panel into a string (e. g. to embed it or to compare it against a snapshot):
+	pub fn draw_to_string(mima: &Mima, mem_write: Option<(Word, RegisterValue)>, before: u32, after: u32, x: u16, y: u16) -> String
+	{
+		let mut out = String::new();
+		Model::draw(&mut out, mima, mem_write, before, after, x, y).expect("Failed to draw into a string.");
+
+		out
+	}
+
+	// Draw a panel that lists the disassembled words from IAR - before to IAR + after.
+	// The window is clipped to the linear address space, the word at IAR (the instruction that is fetched next) is highlighted.
+	// A changed cell is highlighted like a changed register: Its old and new word are shown in green.
+	pub fn draw(out: &mut dyn fmt::Write, mima: &Mima, mem_write: Option<(Word, RegisterValue)>, before: u32, after: u32, x: u16, y: u16) -> fmt::Result
+	{
+		ui::draw_named_box(out, x, y, PANEL_WIDTH, Model::height(before, after), "Memory", ui::BoxStyle::PLAIN)?;
+
+		let iar = mima.register(Registers::IAR);
+		let memory = mima.memory_unit.linear_memory();
+
+		let start = iar.0.saturating_sub(before).min(LINEAR_ADDRESS_SPACE_RANGE.end.0);
+		let end = iar.0.saturating_add(after).saturating_add(1).min(LINEAR_ADDRESS_SPACE_RANGE.end.0);
+
+		for (row, address) in (start..end).enumerate()
+		{
+			let word = memory[address as usize];
+			let is_current = address == iar.0;
+			let change = match mem_write
+			{
+				Some((addr, Value::Change(old, new))) if addr.0 == address 	=> Some((old, new)),
+				_ 															=> None,
+			};
+
+			// Changed cells show their old and new word, words that do not decode to an instruction are shown as data:
+			let statement = match (change, Instruction::decode(word))
+			{
+				(Some((old, new)), _) 		=> format!("{:}->{:}", old, new),
+				(None, Some(instruction)) 	=> format!("{:}", instruction),
+				(None, None) 				=> format!("DAT {:}", word),
+			};
+
+			let is_highlighted = is_current || change.is_some();
+
+			write!(out, "{goto}{addr_color}{marker}{address} {instr_color}{bold}{statement:<instr_width$.instr_width$}{reset}",
+				goto = cursor::Goto(x + 2, y + 1 + (row as u16)),
+				addr_color = color::Fg(if is_highlighted { color::Green } else { color::LightBlack }),
+				marker = if is_current { "> " } else if change.is_some() { "* " } else { "  " },
+				address = Word(address),
+				instr_color = color::Fg(if is_highlighted { color::Green } else { color::White }),
+				bold = if is_highlighted { format!("{:}", style::Bold) } else { String::new() },
+				statement = statement,
+				instr_width = INSTR_WIDTH as usize,
+				reset = style::Reset)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use mimasim::assembly::ObjectCode;
+	use mimasim::record::MicrocycleSummary;
+	use crate::cli::term::color::{self, ColorMode};
+
+	#[test]
+	fn store_highlights_changed_cell()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 7\nstv cell\nhlt\ncell: dat 3").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		// Run until the store commits:
+		let mem_write = loop
+		{
+			let summary = MicrocycleSummary::record_microcycle(&mut mima).expect("The store must commit before the MiMA halts.");
+
+			if let Some((_, Value::Change(_, _))) = summary.mem_write
+			{
+				break summary.mem_write;
+			}
+		};
+
+		color::set_mode(ColorMode::None);
+		let panel = Model::draw_to_string(&mima, mem_write, 2, 5, 1, 1);
+
+		// The cell is marked and shows its old and new word, the other cells are drawn as usual:
+		assert!(panel.contains("* 0x00000003 0x00000003->0x00000007"), "{:}", panel);
+		assert!(panel.contains("  0x00000001 STV 0x00000003"), "{:}", panel);
+
+		// Without the write, the cell shows its current word (which happens to decode to an instruction):
+		let panel = Model::draw_to_string(&mima, None, 2, 5, 1, 1);
+		assert!(panel.contains("  0x00000003 ADD 0x00000007"), "{:}", panel);
+	}
+}
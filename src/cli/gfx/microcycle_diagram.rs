@@ -1,8 +1,10 @@
+use std::fmt;
 use std::io::{stdout, Write};
 use mimasim::types::{*, Registers as Regs};
 use mimasim::unit::{ALUOperation, MemoryAccess, MemoryType};
+use mimasim::bus::BusValue;
 use crate::cli::term::{color, cursor, style, ui};
-use crate::cli::record::{MicrocycleSummary, RegisterValue as RegValue, FlagValue};
+use mimasim::record::{MicrocycleSummary, RegisterValue as RegValue, FlagValue};
 
 // Okay, I am pretty sure this is the messiest part of the whole MiMA simulator ...
 // We use Termion to draw an ASCII-art circuit diagram of the MiMA to an ANSI-aware terminal.
@@ -61,6 +63,7 @@ const IO_BUS_WIDTH: u16 = MIMA_WIDTH - 20;
 const IO_BUS_HEIGHT: u16 = 3;
 
 // Which role does a register play in the microcycle's bus transfer?
+#[derive(Copy, Clone)]
 enum RegisterBusXFerRole
 {
 	Source,
@@ -93,10 +96,33 @@ impl RegisterBusXFerRole
 	}
 }
 
+// How is a register connected to the bus (the shape of the line, its role in the transfer and whether the bus carries a value at all)?
+#[derive(Copy, Clone)]
+struct BusConnection
+{
+	attachment: RegisterAttachment,
+	xfer_role: Option<RegisterBusXFerRole>,
+	is_bus_active: bool,
+}
+
+impl BusConnection
+{
+	fn from_summary(summary: &MicrocycleSummary, reg: Regs, attachment: RegisterAttachment) -> BusConnection
+	{
+		BusConnection
+		{
+			attachment,
+			xfer_role: RegisterBusXFerRole::from_summary(summary, reg),
+			is_bus_active: summary.is_bus_active(),
+		}
+	}
+}
+
 // How do we attach a given register to the bus?
 // Default is a simple horizontal line.
 // In some cases, we need to go vertical first.
 // To calculate this stuff at least a little bit automated, these hints are used.
+#[derive(Copy, Clone)]
 enum RegisterAttachment
 {
 	Horizontal,
@@ -106,70 +132,106 @@ enum RegisterAttachment
 
 impl Model
 {
+	// The number of rows and columns the diagram occupies:
+	pub const HEIGHT: u16 = MIMA_Y + MIMA_HEIGHT + IO_BUS_HEIGHT;
+	pub const WIDTH: u16 = MIMA_X + MIMA_WIDTH;
+
+	// Draw the diagram to the terminal:
 	pub fn draw_from_summary(summary: &MicrocycleSummary, x: u16, y: u16)
+	{
+		// Print a message instead if the terminal is too small for the model:
+		if ui::fits_terminal(x, y, Model::WIDTH, Model::HEIGHT)
+		{
+			print!("{:}", Model::draw_to_string(summary, x, y));
+		}
+		else
+		{
+			let mut out = String::new();
+			ui::draw_too_small(&mut out, x, y, Model::WIDTH, Model::HEIGHT).expect("Failed to draw into a string.");
+
+			print!("{:}", out);
+		}
+
+		// Flush the output:
+		stdout().flush().expect("Failed to flush terminal.");
+	}
+
+	// Draw the diagram into a string (e. g. to embed it or to compare it against a snapshot):
+	pub fn draw_to_string(summary: &MicrocycleSummary, x: u16, y: u16) -> String
+	{
+		let mut out = String::new();
+		Model::draw(&mut out, summary, x, y).expect("Failed to draw into a string.");
+
+		out
+	}
+
+	// Draw the diagram into any fmt::Write target (cursor positioning, colors and styles are escape sequences in the output):
+	pub fn draw(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		// Draw the outer MiMA box:
-		ui::draw_named_box(x + MIMA_X, y + MIMA_Y, MIMA_WIDTH, MIMA_HEIGHT, color::LightBlack, "MiMA", color::White, true);
+		ui::draw_named_box(out, x + MIMA_X, y + MIMA_Y, MIMA_WIDTH, MIMA_HEIGHT, "MiMA", ui::BoxStyle::new(color::LightBlack, color::White, true))?;
 
 		// Draw the bus:
-		Model::draw_bus(summary.is_bus_active(), x, y);
+		Model::draw_bus(out, summary, x, y)?;
 
 		// Draw the units:
-		Model::draw_arithmetic_unit(summary, x, y);
-		Model::draw_control_unit(summary, x, y);
-		Model::draw_memory_unit(summary, x, y);
+		Model::draw_arithmetic_unit(out, summary, x, y)?;
+		Model::draw_control_unit(out, summary, x, y)?;
+		Model::draw_memory_unit(out, summary, x, y)?;
 
 		// Reset colors and style.
 		// Then move the cursor below the model.
-		print!("{color_reset}{style_reset}{goto}",
+		write!(out, "{color_reset}{style_reset}{goto}",
 			color_reset = color::Fg(color::Reset),
 			style_reset = style::Reset,
-			goto = cursor::Goto(1, y + MIMA_HEIGHT + IO_BUS_HEIGHT + 1));
+			goto = cursor::Goto(1, y + Model::HEIGHT))?;
 
-		// Flush the output:
-		stdout().flush().expect("Failed to flush terminal.");
+		Ok(())
 	}
 
-	fn draw_register(reg_x: u16, reg_y: u16, x: u16, name: &str, attachment: RegisterAttachment, value: RegValue, xfer_role: Option<RegisterBusXFerRole>, is_bus_active: bool)
+	fn draw_register(out: &mut dyn fmt::Write, reg_x: u16, reg_y: u16, x: u16, name: &str, value: RegValue, connection: BusConnection) -> fmt::Result
 	{
 		// Draw a box around the register:
-		ui::draw_named_box(reg_x, reg_y, REG_WIDTH, REG_HEIGHT, color::LightBlack, name, color::White, false);
+		ui::draw_named_box(out, reg_x, reg_y, REG_WIDTH, REG_HEIGHT, name, ui::BoxStyle::PLAIN)?;
 
 		// Write the content:
 		match value
 		{
 			RegValue::Stasis(v) =>
 			{
-				print!("{goto0}{fg_color0}0x{value:08X}{goto1}{fg_color1} ────────── ",
+				write!(out, "{goto0}{fg_color0}0x{value:08X}{goto1}{fg_color1}{line}",
 					goto0 = cursor::Goto(reg_x + 2, reg_y + 1),
 					fg_color0 = color::Fg(color::White),
 					value = v.0,
 					goto1 = cursor::Goto(reg_x + 1, reg_y + 2),
-					fg_color1 = color::Fg(color::LightBlack));
+					fg_color1 = color::Fg(color::LightBlack),
+					line = ui::glyphs(" ────────── "))?;
 			},
 			RegValue::Change(old_v, new_v) =>
 			{
-				print!("{goto0}{fg_color0}0x{new_value:08X}{goto1}{fg_color1}0x{old_value:08X}",
+				write!(out, "{goto0}{fg_color0}0x{new_value:08X}{goto1}{fg_color1}0x{old_value:08X}",
 					goto0 = cursor::Goto(reg_x + 2, reg_y + 1),
 					fg_color0 = color::Fg(color::Green),
 					new_value = new_v.0,
 					goto1 = cursor::Goto(reg_x + 2, reg_y + 2),
 					fg_color1 = color::Fg(color::LightBlack),
-					old_value = old_v.0);
+					old_value = old_v.0)?;
 			},
 		}
 
 		// Attach the register to the bus:
-		match attachment
+		match connection.attachment
 		{
-			RegisterAttachment::Horizontal 				=> Model::draw_register_attachment_horizontal(reg_x, reg_y, x, xfer_role, is_bus_active),
-			RegisterAttachment::VerticalUp(offset) 		=> Model::draw_register_attachment_vertical(reg_x, reg_y, x, xfer_role, true, offset, is_bus_active),
-			RegisterAttachment::VerticalDown(offset) 	=> Model::draw_register_attachment_vertical(reg_x, reg_y, x, xfer_role, false, offset, is_bus_active),
+			RegisterAttachment::Horizontal 				=> Model::draw_register_attachment_horizontal(out, reg_x, reg_y, x, connection),
+			RegisterAttachment::VerticalUp(offset) 		=> Model::draw_register_attachment_vertical(out, reg_x, reg_y, x, connection, true, offset),
+			RegisterAttachment::VerticalDown(offset) 	=> Model::draw_register_attachment_vertical(out, reg_x, reg_y, x, connection, false, offset),
 		}
 	}
 
-	fn draw_register_attachment_horizontal(reg_x: u16, reg_y: u16, x: u16, xfer_role: Option<RegisterBusXFerRole>, is_bus_active: bool)
+	fn draw_register_attachment_horizontal(out: &mut dyn fmt::Write, reg_x: u16, reg_y: u16, x: u16, connection: BusConnection) -> fmt::Result
 	{
+		let BusConnection { xfer_role, is_bus_active, .. } = connection;
+
 		// Draw a simple horizontal connector line at the vertical center of the register.
 		// Attach it to the bus-facing edge.
 		let bus_x = x + BUS_X;
@@ -200,13 +262,16 @@ impl Model
 		let start_y = reg_y + (REG_HEIGHT / 2) - 1;
 
 		// Draw the line and the connectors:
-		ui::draw_perpendicular_line(start_x, start_y, end_x, ui::LineDirection::Horizontal, start_char, '─', end_char, if xfer_role.is_some() { color::Green } else { color::LightBlack });
-		ui::draw_char(reg_connector, reg_connector_x, start_y, color::LightBlack);
-		ui::draw_char(bus_connector, bus_connector_x, start_y, if is_bus_active { color::Green } else { color::LightBlack });
+		ui::draw_perpendicular_line(out, start_x, start_y, end_x, ui::LineDirection::Horizontal, [start_char, '─', end_char], if xfer_role.is_some() { color::Green } else { color::LightBlack })?;
+		ui::draw_char(out, reg_connector, reg_connector_x, start_y, color::LightBlack)?;
+		ui::draw_char(out, bus_connector, bus_connector_x, start_y, if is_bus_active { color::Green } else { color::LightBlack })?;
+
+		Ok(())
 	}
 
-	fn draw_register_attachment_vertical(reg_x: u16, reg_y: u16, x: u16, xfer_role: Option<RegisterBusXFerRole>, up: bool, offset: u16, is_bus_active: bool)
+	fn draw_register_attachment_vertical(out: &mut dyn fmt::Write, reg_x: u16, reg_y: u16, x: u16, connection: BusConnection, up: bool, offset: u16) -> fmt::Result
 	{
+		let BusConnection { xfer_role, is_bus_active, .. } = connection;
 		assert!(offset >= 2, "The offset (= length of the vertical attachment) must be at least 2 to include connector and turn characters.");
 
 		// Determine all the parameters -.-
@@ -233,17 +298,19 @@ impl Model
 		// Draw the lines and the connectors:
 		let line_color = if xfer_role.is_some() { color::Green } else { color::LightBlack };
 
-		ui::draw_perpendicular_line(vert_x, vert_start_y, vert_end_y, ui::LineDirection::Vertical, vert_start, '│', vert_end, line_color);
-		ui::draw_perpendicular_line(horz_start_x, horz_y, horz_end_x, ui::LineDirection::Horizontal, horz_start, '─', horz_end, line_color);
+		ui::draw_perpendicular_line(out, vert_x, vert_start_y, vert_end_y, ui::LineDirection::Vertical, [vert_start, '│', vert_end], line_color)?;
+		ui::draw_perpendicular_line(out, horz_start_x, horz_y, horz_end_x, ui::LineDirection::Horizontal, [horz_start, '─', horz_end], line_color)?;
 
-		ui::draw_char(reg_connector, vert_x, reg_connector_y, color::LightBlack);
-		ui::draw_char(bus_connector, bus_connector_x, horz_y, if is_bus_active { color::Green } else { color::LightBlack });
+		ui::draw_char(out, reg_connector, vert_x, reg_connector_y, color::LightBlack)?;
+		ui::draw_char(out, bus_connector, bus_connector_x, horz_y, if is_bus_active { color::Green } else { color::LightBlack })?;
+
+		Ok(())
 	}
 
-	fn draw_flag(flag_x: u16, flag_y: u16, name: &str, value: FlagValue)
+	fn draw_flag(out: &mut dyn fmt::Write, flag_x: u16, flag_y: u16, name: &str, value: FlagValue) -> fmt::Result
 	{
 		// Draw a box around the flag:
-		ui::draw_named_box(flag_x, flag_y, FLAG_WIDTH, FLAG_HEIGHT, color::LightBlack, name, color::White, false);
+		ui::draw_named_box(out, flag_x, flag_y, FLAG_WIDTH, FLAG_HEIGHT, name, ui::BoxStyle::PLAIN)?;
 
 		// Write the content:
 		let (color, text) = match value
@@ -252,44 +319,72 @@ impl Model
 			FlagValue::Change(_, new_v) => if new_v.0 { (color::Green, '1') } else{ (color::Red, '0') },
 		};
 
-		print!("{goto}{fg_color}{value}",
+		write!(out, "{goto}{fg_color}{value}",
 			goto = cursor::Goto(flag_x + 2, flag_y + 1),
 			fg_color = color::Fg(color),
-			value = text);
+			value = text)?;
+
+		Ok(())
 	}
 
-	fn draw_bus(is_active: bool, x: u16, y: u16)
+	fn draw_bus(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		let bus_x = x + BUS_X;
 		let bus_y = y + BUS_Y;
 
 		// Draw the box:
-		let box_color = if is_active { color::Green } else { color::LightBlack };
-		ui::draw_box(bus_x, bus_y, BUS_WIDTH, BUS_HEIGHT, box_color, true);
+		let box_color = if summary.is_bus_active() { color::Green } else { color::LightBlack };
+		ui::draw_box(out, bus_x, bus_y, BUS_WIDTH, BUS_HEIGHT, box_color, true)?;
 
 		// Label it:
-		ui::draw_char('B', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2) - 1, box_color);
-		ui::draw_char('U', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2), box_color);
-		ui::draw_char('S', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2) + 1, box_color);
+		ui::draw_char(out, 'B', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2) - 1, box_color)?;
+		ui::draw_char(out, 'U', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2), box_color)?;
+		ui::draw_char(out, 'S', bus_x + (BUS_WIDTH / 2), bus_y + (BUS_HEIGHT / 2) + 1, box_color)?;
+
+		// Below, show the word that traveled over the bus (split into halves to fit in).
+		// Suppressed accumulator-dependent transfers are marked instead.
+		match summary.bus_value
+		{
+			Some(BusValue::Propagated(value)) =>
+			{
+				write!(out, "{goto0}{fg_color}{high:04X}{goto1}{low:04X}",
+					goto0 = cursor::Goto(bus_x + (BUS_WIDTH / 2) - 1, bus_y + (BUS_HEIGHT / 2) + 3),
+					fg_color = color::Fg(color::White),
+					high = value.0 >> 16,
+					goto1 = cursor::Goto(bus_x + (BUS_WIDTH / 2) - 1, bus_y + (BUS_HEIGHT / 2) + 4),
+					low = value.0 & 0xFFFF)?;
+			},
+			Some(BusValue::Suppressed) =>
+			{
+				write!(out, "{goto}{fg_color}SKIP",
+					goto = cursor::Goto(bus_x + (BUS_WIDTH / 2) - 1, bus_y + (BUS_HEIGHT / 2) + 3),
+					fg_color = color::Fg(color::Yellow))?;
+			},
+			None => (),
+		}
+
+		Ok(())
 	}
 
-	fn draw_arithmetic_unit(summary: &MicrocycleSummary, x: u16, y: u16)
+	fn draw_arithmetic_unit(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		// Draw the outer box:
-		ui::draw_named_box(x + ARITH_X, y + ARITH_Y, ARITH_WIDTH, ARITH_HEIGHT, color::LightYellow, "Arithmetic Unit", color::LightYellow, true);
+		ui::draw_named_box(out, x + ARITH_X, y + ARITH_Y, ARITH_WIDTH, ARITH_HEIGHT, "Arithmetic Unit", ui::BoxStyle::new(color::LightYellow, color::LightYellow, true))?;
 
 		// Draw the non-ALU registers:
-		Model::draw_register(x + ARITH_X + 2 + 2, y + ARITH_Y + 1, x, "ONE", RegisterAttachment::VerticalDown(2), RegValue::Stasis(Word(1)), RegisterBusXFerRole::from_summary(summary, Regs::ONE), summary.is_bus_active());
-		Model::draw_register(x + ARITH_X + 2 + 2 + REG_WIDTH + 1, y + ARITH_Y + 1, x, "ACC", RegisterAttachment::Horizontal, summary.acc, RegisterBusXFerRole::from_summary(summary, Regs::ACC), summary.is_bus_active());
+		Model::draw_register(out, x + ARITH_X + 2 + 2, y + ARITH_Y + 1, x, "ONE", RegValue::Stasis(Word(1)), BusConnection::from_summary(summary, Regs::ONE, RegisterAttachment::VerticalDown(2)))?;
+		Model::draw_register(out, x + ARITH_X + 2 + 2 + REG_WIDTH + 1, y + ARITH_Y + 1, x, "ACC", summary.acc, BusConnection::from_summary(summary, Regs::ACC, RegisterAttachment::Horizontal))?;
 
 		// Draw the ALU:
-		Model::draw_alu(summary, x, y);
+		Model::draw_alu(out, summary, x, y)?;
+
+		Ok(())
 	}
 
-	fn draw_alu(summary: &MicrocycleSummary, x: u16, y: u16)
+	fn draw_alu(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		// Draw the outer box around the ALU:
-		ui::draw_named_box(x + ALU_X, y + ALU_Y, ALU_WIDTH, ALU_HEIGHT, color::LightYellow, "ALU", color::LightYellow, false);
+		ui::draw_named_box(out, x + ALU_X, y + ALU_Y, ALU_WIDTH, ALU_HEIGHT, "ALU", ui::BoxStyle::new(color::LightYellow, color::LightYellow, false))?;
 
 		// Draw the registers X, Y and Z:
 		let reg_x_x = x + ALU_X + 2;
@@ -298,9 +393,9 @@ impl Model
 		let reg_z_x = x + ALU_X + ((ALU_WIDTH - REG_WIDTH) / 2);
 		let reg_z_y = y + ALU_Y + 1 + REG_HEIGHT + 1 + ALU_CENTER_HEIGHT + 1;
 
-		Model::draw_register(reg_x_x, reg_xy_y, x, "X", RegisterAttachment::VerticalUp(2), summary.x, RegisterBusXFerRole::from_summary(summary, Regs::X), summary.is_bus_active());
-		Model::draw_register(reg_y_x, reg_xy_y, x, "Y", RegisterAttachment::Horizontal, summary.y, RegisterBusXFerRole::from_summary(summary, Regs::Y), summary.is_bus_active());
-		Model::draw_register(reg_z_x, reg_z_y, x, "Z", RegisterAttachment::Horizontal, summary.z, RegisterBusXFerRole::from_summary(summary, Regs::Z), summary.is_bus_active());
+		Model::draw_register(out, reg_x_x, reg_xy_y, x, "X", summary.x, BusConnection::from_summary(summary, Regs::X, RegisterAttachment::VerticalUp(2)))?;
+		Model::draw_register(out, reg_y_x, reg_xy_y, x, "Y", summary.y, BusConnection::from_summary(summary, Regs::Y, RegisterAttachment::Horizontal))?;
+		Model::draw_register(out, reg_z_x, reg_z_y, x, "Z", summary.z, BusConnection::from_summary(summary, Regs::Z, RegisterAttachment::Horizontal))?;
 
 		// Pre-calculate some positions:
 		let center_x = x + ALU_X + ((ALU_WIDTH - ALU_CENTER_WIDTH) / 2);
@@ -328,8 +423,12 @@ impl Model
 			ALUOperation::Or 			=> '|',
 			ALUOperation::Xor 			=> '^',
 			ALUOperation::Equals 		=> '=',
+			ALUOperation::Subtract 		=> '-',
 			ALUOperation::Not 			=> '!',
 			ALUOperation::RotateRight 	=> 'R',
+			ALUOperation::RotateLeft 	=> 'L',
+			ALUOperation::Compare 		=> 'C',
+			ALUOperation::Custom(_) 	=> '?',
 		};
 
 		let (alu_color, op_center, attachment_end_char) = if let Some((op, rem)) = summary.alu_work
@@ -343,94 +442,98 @@ impl Model
 		};
 
 		// Box:
-		ui::draw_box(center_x, center_y, ALU_CENTER_WIDTH, ALU_CENTER_HEIGHT, alu_color, false);
+		ui::draw_box(out, center_x, center_y, ALU_CENTER_WIDTH, ALU_CENTER_HEIGHT, alu_color, false)?;
 
 		if let Some((op_char, op_char_color)) = op_center
 		{
-			print!("{goto}{fg_color}{style}{op}{reset}",
+			write!(out, "{goto}{fg_color}{style}{op}{reset}",
 				goto = cursor::Goto(op_x, op_y),
 				fg_color = color::Fg(op_char_color),
 				style = style::Bold,
 				op = op_char,
-				reset = style::Reset);
+				reset = style::Reset)?;
 		}
 
 		// Center -> Z attachment:
-		ui::draw_perpendicular_line(reg_z_connector_x, center_y + ALU_CENTER_HEIGHT - 1, reg_z_connector_y - 1, ui::LineDirection::Vertical, '┬', '│', attachment_end_char, alu_color);
+		ui::draw_perpendicular_line(out, reg_z_connector_x, center_y + ALU_CENTER_HEIGHT - 1, reg_z_connector_y - 1, ui::LineDirection::Vertical, ['┬', '│', attachment_end_char], alu_color)?;
 
 		// Connectors at the center to X and Y:
-		ui::draw_char('┴', reg_x_connector_x, center_y, alu_color);
-		ui::draw_char('┴', reg_y_connector_x, center_y, alu_color);
+		ui::draw_char(out, '┴', reg_x_connector_x, center_y, alu_color)?;
+		ui::draw_char(out, '┴', reg_y_connector_x, center_y, alu_color)?;
 
 		// Draw the register connectors:
-		ui::draw_char('┬', reg_x_connector_x, reg_xy_connector_y, color::LightBlack);
-		ui::draw_char('┬', reg_y_connector_x, reg_xy_connector_y, color::LightBlack);
-		ui::draw_char('┴', reg_z_connector_x, reg_z_connector_y, color::LightBlack);
+		ui::draw_char(out, '┬', reg_x_connector_x, reg_xy_connector_y, color::LightBlack)?;
+		ui::draw_char(out, '┬', reg_y_connector_x, reg_xy_connector_y, color::LightBlack)?;
+		ui::draw_char(out, '┴', reg_z_connector_x, reg_z_connector_y, color::LightBlack)?;
 
 		// Draw the ALU signal if there is one:
 		if let Some(op) = summary.descriptor.alu_op
 		{
-			print!("{goto}{fg_color}{style}{op}{reset}",
+			write!(out, "{goto}{fg_color}{style}{op}{reset}",
 				goto = cursor::Goto(signal_x_start, signal_y),
 				fg_color = color::Fg(color::Green),
 				style = style::Bold,
 				op = select_alu_op_char(op),
-				reset = style::Reset);
+				reset = style::Reset)?;
 
-			ui::draw_perpendicular_line(signal_x_start + 2, signal_y, signal_x_end, ui::LineDirection::Horizontal, '├', '─', '>', color::Green);
+			ui::draw_perpendicular_line(out, signal_x_start + 2, signal_y, signal_x_end, ui::LineDirection::Horizontal, ['├', '─', '>'], color::Green)?;
 
 			// (X, Y) -> Center attachment:
-			ui::draw_perpendicular_line(reg_x_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, '│', '│', 'V', color::Green);
-			ui::draw_perpendicular_line(reg_y_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, '│', '│', 'V', color::Green);
+			ui::draw_perpendicular_line(out, reg_x_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, ['│', '│', 'V'], color::Green)?;
+			ui::draw_perpendicular_line(out, reg_y_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, ['│', '│', 'V'], color::Green)?;
 		}
 		else
 		{
 			// (X, Y) -> Center attachment:
-			ui::draw_perpendicular_line(reg_x_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, '│', '│', '│', color::LightBlack);
-			ui::draw_perpendicular_line(reg_y_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, '│', '│', '│', color::LightBlack);
+			ui::draw_perpendicular_line(out, reg_x_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, ['│', '│', '│'], color::LightBlack)?;
+			ui::draw_perpendicular_line(out, reg_y_connector_x, reg_xy_connector_y + 1, center_y - 1, ui::LineDirection::Vertical, ['│', '│', '│'], color::LightBlack)?;
 		}
+
+		Ok(())
 	}
 
-	fn draw_control_unit(summary: &MicrocycleSummary, x: u16, y: u16)
+	fn draw_control_unit(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		// Draw the outer box:
-		ui::draw_named_box(x + CONTROL_X, y + CONTROL_Y, CONTROL_WIDTH, CONTROL_HEIGHT, color::Blue, "Control Unit", color::Blue, true);
+		ui::draw_named_box(out, x + CONTROL_X, y + CONTROL_Y, CONTROL_WIDTH, CONTROL_HEIGHT, "Control Unit", ui::BoxStyle::new(color::Blue, color::Blue, true))?;
 
 		// Draw the registers:
-		Model::draw_register(x + CONTROL_X + 2, y + CONTROL_Y + 1, x, "IAR", RegisterAttachment::Horizontal, summary.iar, RegisterBusXFerRole::from_summary(summary, Regs::IAR), summary.is_bus_active());
-		Model::draw_register(x + CONTROL_X + 2 + REG_WIDTH + 1, y + CONTROL_Y + 1, x, "IR", RegisterAttachment::VerticalDown(4), summary.ir, RegisterBusXFerRole::from_summary(summary, Regs::IR), summary.is_bus_active());
+		Model::draw_register(out, x + CONTROL_X + 2, y + CONTROL_Y + 1, x, "IAR", summary.iar, BusConnection::from_summary(summary, Regs::IAR, RegisterAttachment::Horizontal))?;
+		Model::draw_register(out, x + CONTROL_X + 2 + REG_WIDTH + 1, y + CONTROL_Y + 1, x, "IR", summary.ir, BusConnection::from_summary(summary, Regs::IR, RegisterAttachment::VerticalDown(4)))?;
 
 		// Draw the flags:
-		Model::draw_flag(x + CONTROL_X + 2, y + CONTROL_Y + REG_HEIGHT + 1, "RUN", summary.run);
-		Model::draw_flag(x + CONTROL_X + 2 + FLAG_WIDTH, y + CONTROL_Y + REG_HEIGHT + 1, "TRA", summary.tra);
+		Model::draw_flag(out, x + CONTROL_X + 2, y + CONTROL_Y + REG_HEIGHT + 1, "RUN", summary.run)?;
+		Model::draw_flag(out, x + CONTROL_X + 2 + FLAG_WIDTH, y + CONTROL_Y + REG_HEIGHT + 1, "TRA", summary.tra)?;
 
 		// Draw the cycle:
 		let cycle_x = x + CONTROL_X + 2 + FLAG_WIDTH + FLAG_WIDTH + 1;
 		let cycle_y = y + CONTROL_Y + REG_HEIGHT + 1;
 
-		ui::draw_named_box(cycle_x, cycle_y, 6, 3, color::LightBlack, "CYCL", color::White, false);
+		ui::draw_named_box(out, cycle_x, cycle_y, 6, 3, "CYCL", ui::BoxStyle::PLAIN)?;
 
-		print!("{goto}{fg_color}{cycle}",
+		write!(out, "{goto}{fg_color}{cycle:02}",
 			goto = cursor::Goto(cycle_x + 2, cycle_y + 1),
 			fg_color = color::Fg(color::White),
-			cycle = format!("{:02}", summary.microcycle));
+			cycle = summary.microcycle)?;
 
 		// Draw the command:
 		let cmd_x = cycle_x + 7;
 		let cmd_y = y + CONTROL_Y + REG_HEIGHT + 1;
 
-		ui::draw_named_box(cmd_x, cmd_y, 7, 3, color::LightBlack, "INS", color::White, false);
+		ui::draw_named_box(out, cmd_x, cmd_y, 7, 3, "INS", ui::BoxStyle::PLAIN)?;
 
-		print!("{goto}{fg_color}{instr}",
+		write!(out, "{goto}{fg_color}{instr}",
 			goto = cursor::Goto(cmd_x + 2, cmd_y + 1),
 			fg_color = color::Fg(color::White),
-			instr = summary.instruction.map_or("───", |i| i.format_opcode()));
+			instr = summary.instruction.map_or(ui::glyphs("───"), |i| String::from(i.format_opcode())))?;
+
+		Ok(())
 	}
 
-	fn draw_memory_unit(summary: &MicrocycleSummary, x: u16, y: u16)
+	fn draw_memory_unit(out: &mut dyn fmt::Write, summary: &MicrocycleSummary, x: u16, y: u16) -> fmt::Result
 	{
 		// Draw the outer box:
-		ui::draw_named_box(x + MEMORY_X, y + MEMORY_Y, MEMORY_WIDTH, MEMORY_HEIGHT, color::Red, "Memory Unit", color::Red, true);
+		ui::draw_named_box(out, x + MEMORY_X, y + MEMORY_Y, MEMORY_WIDTH, MEMORY_HEIGHT, "Memory Unit", ui::BoxStyle::new(color::Red, color::Red, true))?;
 
 		// Draw the registers:
 		let reg_sir_x = x + MEMORY_X + 2;
@@ -438,8 +541,8 @@ impl Model
 		let reg_sar_x = reg_sir_x + 7;
 		let reg_sar_y = y + MEMORY_Y + 1;
 
-		Model::draw_register(reg_sar_x, reg_sar_y, x, "SAR", RegisterAttachment::Horizontal, summary.sar, RegisterBusXFerRole::from_summary(summary, Regs::SAR), summary.is_bus_active());
-		Model::draw_register(reg_sir_x, reg_sir_y, x, "SIR", RegisterAttachment::Horizontal, summary.sir, RegisterBusXFerRole::from_summary(summary, Regs::SIR), summary.is_bus_active());
+		Model::draw_register(out, reg_sar_x, reg_sar_y, x, "SAR", summary.sar, BusConnection::from_summary(summary, Regs::SAR, RegisterAttachment::Horizontal))?;
+		Model::draw_register(out, reg_sir_x, reg_sir_y, x, "SIR", summary.sir, BusConnection::from_summary(summary, Regs::SIR, RegisterAttachment::Horizontal))?;
 
 		// Do we export from SAR and / or SIR?
 		let (is_sar_lin_active, sar_lin_end, is_sar_io_active, sar_io_end,
@@ -504,34 +607,34 @@ impl Model
 		let io_op_y = io_y + (IO_BUS_HEIGHT / 2);
 
 		// Attach SIR and SAR to the linear memory:
-		ui::draw_perpendicular_line(sar_lin_connector_start_x + 1, sar_lin_connector_y, sar_lin_connector_end_x - 1, ui::LineDirection::Horizontal, '─', '─', sar_lin_end, if is_sar_lin_active { color::Green } else { color::LightBlack });
-		ui::draw_perpendicular_line(sir_lin_connector_start_x + 1, sir_lin_connector_y, sir_lin_connector_end_x - 1, ui::LineDirection::Horizontal, sir_lin_start, '─', sir_lin_end, if is_sir_lin_active || is_lin_sir_active { color::Green } else { color::LightBlack });
+		ui::draw_perpendicular_line(out, sar_lin_connector_start_x + 1, sar_lin_connector_y, sar_lin_connector_end_x - 1, ui::LineDirection::Horizontal, ['─', '─', sar_lin_end], if is_sar_lin_active { color::Green } else { color::LightBlack })?;
+		ui::draw_perpendicular_line(out, sir_lin_connector_start_x + 1, sir_lin_connector_y, sir_lin_connector_end_x - 1, ui::LineDirection::Horizontal, [sir_lin_start, '─', sir_lin_end], if is_sir_lin_active || is_lin_sir_active { color::Green } else { color::LightBlack })?;
 
 		// Attach SAR and SIR to the I/O bus:
-		ui::draw_perpendicular_line(sar_io_connector_x, sar_io_connector_start_y + 1, sar_io_connector_end_y - 1, ui::LineDirection::Vertical, '│', '│', sar_io_end, if is_sar_io_active { color::Green } else { color::LightBlack });
-		ui::draw_perpendicular_line(sir_io_connector_x, sir_io_connector_start_y + 1, sir_io_connector_end_y - 1, ui::LineDirection::Vertical, sir_io_start, '│', sir_io_end, if is_sir_io_active { color::Green } else { color::LightBlack });
+		ui::draw_perpendicular_line(out, sar_io_connector_x, sar_io_connector_start_y + 1, sar_io_connector_end_y - 1, ui::LineDirection::Vertical, ['│', '│', sar_io_end], if is_sar_io_active { color::Green } else { color::LightBlack })?;
+		ui::draw_perpendicular_line(out, sir_io_connector_x, sir_io_connector_start_y + 1, sir_io_connector_end_y - 1, ui::LineDirection::Vertical, [sir_io_start, '│', sir_io_end], if is_sir_io_active { color::Green } else { color::LightBlack })?;
 
 		// Draw the connectors at the registers:
-		ui::draw_char('├', sar_lin_connector_start_x, sar_lin_connector_y, color::LightBlack);
-		ui::draw_char('├', sir_lin_connector_start_x, sir_lin_connector_y, color::LightBlack);
-		ui::draw_char('┬', sar_io_connector_x, sar_io_connector_start_y, color::LightBlack);
-		ui::draw_char('┬', sir_io_connector_x, sir_io_connector_start_y, color::LightBlack);
+		ui::draw_char(out, '├', sar_lin_connector_start_x, sar_lin_connector_y, color::LightBlack)?;
+		ui::draw_char(out, '├', sir_lin_connector_start_x, sir_lin_connector_y, color::LightBlack)?;
+		ui::draw_char(out, '┬', sar_io_connector_x, sar_io_connector_start_y, color::LightBlack)?;
+		ui::draw_char(out, '┬', sir_io_connector_x, sir_io_connector_start_y, color::LightBlack)?;
 
 		// Draw the linear memory with connectors and signal:
 		let (lin_color, lin_name_color, lin_op_color) = if is_lin_sir_active { (color::Green, color::Green, color::Green) } else { (color::LightBlack, color::White, color::Yellow) };
 
-		ui::draw_named_box(mem_x, mem_y, MEMORY_MEM_WIDTH, MEMORY_MEM_HEIGHT, lin_color, "MEM", lin_name_color, false);
-		ui::draw_char('┤', sar_lin_connector_end_x, sar_lin_connector_y, lin_color);
-		ui::draw_char('┤', sir_lin_connector_end_x, sir_lin_connector_y, lin_color);
+		ui::draw_named_box(out, mem_x, mem_y, MEMORY_MEM_WIDTH, MEMORY_MEM_HEIGHT, "MEM", ui::BoxStyle::new(lin_color, lin_name_color, false))?;
+		ui::draw_char(out, '┤', sar_lin_connector_end_x, sar_lin_connector_y, lin_color)?;
+		ui::draw_char(out, '┤', sir_lin_connector_end_x, sir_lin_connector_y, lin_color)?;
 
 		if let Some(lin_op) = lin_op
 		{
-			print!("{goto}{fg_color}{style}{op}{reset}",
+			write!(out, "{goto}{fg_color}{style}{op}{reset}",
 				goto = cursor::Goto(lin_op_x, lin_op_y),
 				fg_color = color::Fg(lin_op_color),
 				style = style::Bold,
 				op = lin_op,
-				reset = style::Reset);
+				reset = style::Reset)?;
 		}
 
 		if let Some(access) = lin_mem_access
@@ -540,7 +643,7 @@ impl Model
 			let signal_x_end = mem_x - 1;
 			let signal_y = mem_y + (MEMORY_MEM_HEIGHT / 2);
 
-			print!("{goto}{fg_color}{style}{signal}{reset}",
+			write!(out, "{goto}{fg_color}{style}{signal}{reset}",
 				goto = cursor::Goto(signal_x_start, signal_y),
 				fg_color = color::Fg(color::Green),
 				style = style::Bold,
@@ -549,30 +652,30 @@ impl Model
 					MemoryAccess::Read 		=> 'R',
 					MemoryAccess::Write 	=> 'W',
 				},
-				reset = style::Reset);
+				reset = style::Reset)?;
 
-			ui::draw_perpendicular_line(signal_x_start + 2, signal_y, signal_x_end, ui::LineDirection::Horizontal, '├', '─', '>', color::Green);
+			ui::draw_perpendicular_line(out, signal_x_start + 2, signal_y, signal_x_end, ui::LineDirection::Horizontal, ['├', '─', '>'], color::Green)?;
 		}
 
 		// Draw the IO memory with connectors and signal (yeah, technically, that one is located outside of the memory unit ...):
 		let (io_color, io_op_color) = if is_io_sir_active { (color::Green, color::Green) } else { (color::LightBlack, color::Yellow) };
 
-		ui::draw_box(io_x, io_y, IO_BUS_WIDTH, IO_BUS_HEIGHT, io_color, true);
-		ui::draw_char('╧', sar_io_connector_x, sar_io_connector_end_y, io_color);
-		ui::draw_char('╧', sir_io_connector_x, sir_io_connector_end_y, io_color);
+		ui::draw_box(out, io_x, io_y, IO_BUS_WIDTH, IO_BUS_HEIGHT, io_color, true)?;
+		ui::draw_char(out, '╧', sar_io_connector_x, sar_io_connector_end_y, io_color)?;
+		ui::draw_char(out, '╧', sir_io_connector_x, sir_io_connector_end_y, io_color)?;
 
-		ui::draw_char('I', io_x + (IO_BUS_WIDTH / 2) - 1, io_y + (IO_BUS_HEIGHT / 2), io_color);
-		ui::draw_char('/', io_x + (IO_BUS_WIDTH / 2), io_y + (IO_BUS_HEIGHT / 2), io_color);
-		ui::draw_char('O', io_x + (IO_BUS_WIDTH / 2) + 1, io_y + (IO_BUS_HEIGHT / 2), io_color);
+		ui::draw_char(out, 'I', io_x + (IO_BUS_WIDTH / 2) - 1, io_y + (IO_BUS_HEIGHT / 2), io_color)?;
+		ui::draw_char(out, '/', io_x + (IO_BUS_WIDTH / 2), io_y + (IO_BUS_HEIGHT / 2), io_color)?;
+		ui::draw_char(out, 'O', io_x + (IO_BUS_WIDTH / 2) + 1, io_y + (IO_BUS_HEIGHT / 2), io_color)?;
 
 		if let Some(io_op) = io_op
 		{
-			print!("{goto}{fg_color}{style}{op}{reset}",
+			write!(out, "{goto}{fg_color}{style}{op}{reset}",
 				goto = cursor::Goto(io_op_x, io_op_y),
 				fg_color = color::Fg(io_op_color),
 				style = style::Bold,
 				op = io_op,
-				reset = style::Reset);
+				reset = style::Reset)?;
 		}
 
 		if let Some(access) = io_mem_access
@@ -581,7 +684,7 @@ impl Model
 			let signal_x_end = signal_x_start + 4;
 			let signal_y = io_y + (IO_BUS_HEIGHT / 2);
 
-			print!("{goto}{fg_color}{style}{signal}{reset}",
+			write!(out, "{goto}{fg_color}{style}{signal}{reset}",
 				goto = cursor::Goto(signal_x_end + 2, signal_y),
 				fg_color = color::Fg(color::Green),
 				style = style::Bold,
@@ -590,9 +693,11 @@ impl Model
 					MemoryAccess::Read 		=> 'R',
 					MemoryAccess::Write 	=> 'W',
 				},
-				reset = style::Reset);
+				reset = style::Reset)?;
 
-			ui::draw_perpendicular_line(signal_x_start, signal_y, signal_x_end, ui::LineDirection::Horizontal, '<', '─', '┤', color::Green);
+			ui::draw_perpendicular_line(out, signal_x_start, signal_y, signal_x_end, ui::LineDirection::Horizontal, ['<', '─', '┤'], color::Green)?;
 		}
+
+		Ok(())
 	}
 }
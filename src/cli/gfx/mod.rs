@@ -1,5 +1,9 @@
 mod cycle_diagram;
+mod memory_panel;
 mod microcycle_diagram;
+mod watch_panel;
 
 pub use cycle_diagram::Model as CycleDiagram;
+pub use memory_panel::Model as MemoryPanel;
 pub use microcycle_diagram::Model as MicrocycleDiagram;
+pub use watch_panel::Model as WatchPanel;
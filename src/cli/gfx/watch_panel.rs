@@ -0,0 +1,61 @@
+use std::fmt;
+use std::io::{stdout, Write};
+use crate::cli::term::{color, cursor, style, ui};
+use mimasim::mima::Mima;
+use mimasim::watch::WatchExpression;
+
+pub enum Model { }
+
+// Measures:
+const HEX_WIDTH: u16 = 2 + 8;
+const SIGNED_WIDTH: u16 = 4 + 11;
+const PANEL_WIDTH: u16 = 1 + 1 + EXPR_WIDTH + 3 + HEX_WIDTH + 1 + SIGNED_WIDTH + 1 + 1;
+const EXPR_WIDTH: u16 = 20;
+
+impl Model
+{
+	// Draw the panel to the terminal:
+	pub fn draw_from_mima(watches: &[WatchExpression], mima: &Mima, x: u16, y: u16)
+	{
+		print!("{:}", Model::draw_to_string(watches, mima, x, y));
+
+		// Flush the output:
+		stdout().flush().expect("Failed to flush terminal.");
+	}
+
+	// Draw the panel into a string (e. g. to embed it or to compare it against a snapshot):
+	pub fn draw_to_string(watches: &[WatchExpression], mima: &Mima, x: u16, y: u16) -> String
+	{
+		let mut out = String::new();
+		Model::draw(&mut out, watches, mima, x, y).expect("Failed to draw into a string.");
+
+		out
+	}
+
+	// Draw a panel that lists all watch expressions and their current values.
+	// Values are shown in hex and as signed decimal, expressions that fault (e. g. bad addresses) are shown as "<fault>".
+	pub fn draw(out: &mut dyn fmt::Write, watches: &[WatchExpression], mima: &Mima, x: u16, y: u16) -> fmt::Result
+	{
+		ui::draw_named_box(out, x, y, PANEL_WIDTH, (watches.len() as u16) + 2, "Watch", ui::BoxStyle::PLAIN)?;
+
+		for (i, watch) in watches.iter().enumerate()
+		{
+			let (value_color, value) = match watch.evaluate(mima)
+			{
+				Ok(word) 	=> (color::Fg(color::White), format!("{:} {:<signed_width$}", word, format!("(= {:})", word.to_signed_string()), signed_width = SIGNED_WIDTH as usize)),
+				Err(_) 		=> (color::Fg(color::Red), String::from("<fault>")),
+			};
+
+			write!(out, "{goto}{expr_color}{expr:<expr_width$.expr_width$} = {value_color}{value}{reset}",
+				goto = cursor::Goto(x + 2, y + 1 + (i as u16)),
+				expr_color = color::Fg(color::LightBlack),
+				expr = watch.source(),
+				expr_width = EXPR_WIDTH as usize,
+				value_color = value_color,
+				value = value,
+				reset = style::Reset)?;
+		}
+
+		Ok(())
+	}
+}
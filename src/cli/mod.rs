@@ -1,3 +1,5 @@
+pub mod args;
 pub mod term;
-pub mod record;
 pub mod gfx;
+pub mod pacer;
+pub mod stepper;
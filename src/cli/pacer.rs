@@ -0,0 +1,106 @@
+use std::thread;
+use std::time::Duration;
+
+// The pacer decides how long to wait between two microcycles.
+// The speed can be changed at runtime by stepping through a fixed list of delays.
+// A custom start delay (e. g. from the command line) moves to the next level in the list.
+pub struct Pacer
+{
+	delay_ms: u64,
+}
+
+impl Pacer
+{
+	// The delays (in milliseconds) from slowest to fastest:
+	const DELAYS_MS: [u64; 7] = [2000, 1000, 500, 250, 100, 50, 10];
+
+	// By default, we wait half a second between microcycles:
+	const DEFAULT_LEVEL: usize = 2;
+
+	pub fn new() -> Pacer
+	{
+		Pacer::with_delay(Duration::from_millis(Pacer::DELAYS_MS[Pacer::DEFAULT_LEVEL]))
+	}
+
+	pub fn with_delay(delay: Duration) -> Pacer
+	{
+		Pacer
+		{
+			delay_ms: delay.as_millis() as u64,
+		}
+	}
+
+	pub fn delay(&self) -> Duration
+	{
+		Duration::from_millis(self.delay_ms)
+	}
+
+	// Speed up resp. slow down to the next level (saturating at the fastest / slowest one):
+	pub fn speed_up(&mut self)
+	{
+		self.delay_ms = Pacer::DELAYS_MS.iter().copied().find(|&delay_ms| delay_ms < self.delay_ms).unwrap_or(self.delay_ms);
+	}
+
+	pub fn slow_down(&mut self)
+	{
+		self.delay_ms = Pacer::DELAYS_MS.iter().rev().copied().find(|&delay_ms| delay_ms > self.delay_ms).unwrap_or(self.delay_ms);
+	}
+
+	// Wait for the current delay:
+	pub fn wait(&self)
+	{
+		thread::sleep(self.delay());
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn step_through_levels()
+	{
+		let mut pacer = Pacer::new();
+		assert_eq!(pacer.delay(), Duration::from_millis(500));
+
+		pacer.speed_up();
+		assert_eq!(pacer.delay(), Duration::from_millis(250));
+
+		pacer.slow_down();
+		pacer.slow_down();
+		assert_eq!(pacer.delay(), Duration::from_millis(1000));
+	}
+
+	#[test]
+	fn saturate_at_slowest_and_fastest_level()
+	{
+		let mut pacer = Pacer::new();
+
+		for _ in 0..10
+		{
+			pacer.speed_up();
+		}
+
+		assert_eq!(pacer.delay(), Duration::from_millis(10));
+
+		for _ in 0..10
+		{
+			pacer.slow_down();
+		}
+
+		assert_eq!(pacer.delay(), Duration::from_millis(2000));
+	}
+
+	#[test]
+	fn custom_delay_moves_to_next_level()
+	{
+		let mut pacer = Pacer::with_delay(Duration::from_millis(300));
+		pacer.speed_up();
+		assert_eq!(pacer.delay(), Duration::from_millis(250));
+
+		let mut pacer = Pacer::with_delay(Duration::from_millis(300));
+		pacer.slow_down();
+		assert_eq!(pacer.delay(), Duration::from_millis(500));
+	}
+}
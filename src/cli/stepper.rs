@@ -0,0 +1,141 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+use crate::cli::{pacer::Pacer, term::event::Key};
+
+// How the animation advances from one microcycle to the next:
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Mode
+{
+	// Advance automatically after the delay of the pacer ("+" / "-" change the speed):
+	Auto,
+
+	// Wait for a key before every microcycle:
+	Step,
+
+	// Advance until the current instruction is complete (microcycle 12), then wait again:
+	Instruction,
+
+	// Advance without delay until the MiMA halts:
+	Run,
+}
+
+// The stepper decides when the next microcycle is performed.
+// In interactive mode, space advances one microcycle, "i" a full instruction, "r" runs until halt and "q" quits.
+pub struct Stepper
+{
+	mode: Mode,
+	pacer: Pacer,
+}
+
+impl Stepper
+{
+	// How often we look for a key while waiting for one:
+	const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+	pub fn new(mode: Mode, pacer: Pacer) -> Stepper
+	{
+		Stepper
+		{
+			mode,
+			pacer,
+		}
+	}
+
+	// Wait until the next microcycle should be performed (the one that has just been drawn is given).
+	// Returns false if the user wants to quit.
+	pub fn wait(&mut self, keys: &mut impl Iterator<Item = io::Result<Key>>, microcycle: u8) -> bool
+	{
+		// A full instruction ends with its last microcycle, so we wait for the user again:
+		if (self.mode == Mode::Instruction) && (microcycle == 12)
+		{
+			self.mode = Mode::Step;
+		}
+
+		match self.mode
+		{
+			Mode::Auto =>
+			{
+				for key in keys.flatten()
+				{
+					match key
+					{
+						Key::Char('+') 						=> self.pacer.speed_up(),
+						Key::Char('-') 						=> self.pacer.slow_down(),
+						Key::Char('q') | Key::Ctrl('c') 	=> return false,
+						_ 									=> (),
+					}
+				}
+
+				self.pacer.wait();
+				true
+			},
+
+			// Space interrupts a running instruction or program:
+			Mode::Instruction | Mode::Run =>
+			{
+				for key in keys.flatten()
+				{
+					match key
+					{
+						Key::Char(' ') 						=> self.mode = Mode::Step,
+						Key::Char('q') | Key::Ctrl('c') 	=> return false,
+						_ 									=> (),
+					}
+				}
+
+				true
+			},
+
+			Mode::Step => loop
+			{
+				// The keys are read asynchronously, so we have to poll them:
+				match keys.next()
+				{
+					Some(Ok(Key::Char(' '))) 							=> return true,
+					Some(Ok(Key::Char('i'))) 							=>
+					{
+						self.mode = Mode::Instruction;
+						return true;
+					},
+					Some(Ok(Key::Char('r'))) 							=>
+					{
+						self.mode = Mode::Run;
+						return true;
+					},
+					Some(Ok(Key::Char('q'))) | Some(Ok(Key::Ctrl('c'))) => return false,
+
+					// No key will ever arrive, so we animate the rest instead of polling forever:
+					Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof =>
+					{
+						self.mode = Mode::Auto;
+						self.pacer.wait();
+						return true;
+					},
+
+					Some(_) 											=> (),
+					None 												=> thread::sleep(Stepper::POLL_INTERVAL),
+				}
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn end_of_input_falls_back_to_auto()
+	{
+		let mut stepper = Stepper::new(Mode::Step, Pacer::with_delay(Duration::from_millis(0)));
+		let mut keys = vec![Err(io::Error::from(io::ErrorKind::UnexpectedEof))].into_iter();
+
+		assert!(stepper.wait(&mut keys, 1));
+		assert!(stepper.mode == Mode::Auto);
+
+		// Without any keys, the animation goes on:
+		assert!(stepper.wait(&mut keys, 2));
+	}
+}
@@ -1,30 +1,65 @@
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::env;
 use termion::color;
 
 // A color enum for Termion.
 // color::Color itself is a trait, therefore, color::Blue, color::Green and friends all have different types.
 // To avoid trait objects, we define our own enum type that invokes the trait methods of the colors.
 // With this enum type, we can use colors as result of if-conditions, store them in constants, ...
-// The dead code warning suppression is necessary because we don't use all the colors, but might need them in the future.
+// It only has the variants the drawings use (add more from termion::color if needed).
 
 // Allow to use this instead of termion::color:
-pub use color::{Bg, Fg, Reset};
+pub use color::Fg;
 
 // Use all the color variants so we can e. g. type "color::Green":
 pub use Color::*;
 
 #[derive(Copy, Clone, Debug)]
-#[allow(dead_code)]
 pub enum Color
 {
-	LightBlack, Black,
-	LightBlue, Blue,
-	LightCyan, Cyan,
-	LightGreen, Green,
-	LightMagenta, Magenta,
-	LightRed, Red,
-	LightWhite, White,
-	LightYellow, Yellow,
+	LightBlack,
+	Blue,
+	Green,
+	Red,
+	White,
+	LightYellow,
+	Yellow,
+	Reset,
+}
+
+// Output for ANSI terminals (colors, styles and Unicode box drawing) or plain output (no escapes for colors / styles and ASCII boxes) for logs and dumb terminals:
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColorMode
+{
+	Ansi,
+	None,
+}
+
+// The drawing helpers are free functions, so the mode is global:
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+impl ColorMode
+{
+	// Honor the NO_COLOR convention (see https://no-color.org): If it is set to a non-empty value, use plain output.
+	pub fn from_env() -> ColorMode
+	{
+		match env::var_os("NO_COLOR")
+		{
+			Some(value) if !value.is_empty() 	=> ColorMode::None,
+			_ 									=> ColorMode::Ansi,
+		}
+	}
+}
+
+pub fn set_mode(mode: ColorMode)
+{
+	PLAIN.store(mode == ColorMode::None, Ordering::Relaxed);
+}
+
+pub fn mode() -> ColorMode
+{
+	if PLAIN.load(Ordering::Relaxed) { ColorMode::None } else { ColorMode::Ansi }
 }
 
 impl color::Color for Color
@@ -32,48 +67,42 @@ impl color::Color for Color
 	// Background:
     fn write_bg(&self, f: &mut fmt::Formatter) -> fmt::Result
     {
+    	if mode() == ColorMode::None
+    	{
+    		return Ok(());
+    	}
+
     	match self
     	{
 			Color::LightBlack 	=> color::LightBlack.write_bg(f),
-			Color::Black 		=> color::Black.write_bg(f),
-			Color::LightBlue 	=> color::LightBlue.write_bg(f),
 			Color::Blue 		=> color::Blue.write_bg(f),
-			Color::LightCyan 	=> color::LightCyan.write_bg(f),
-			Color::Cyan 		=> color::Cyan.write_bg(f),
-			Color::LightGreen 	=> color::LightGreen.write_bg(f),
 			Color::Green 		=> color::Green.write_bg(f),
-			Color::LightMagenta => color::LightMagenta.write_bg(f),
-			Color::Magenta 		=> color::Magenta.write_bg(f),
-			Color::LightRed 	=> color::LightRed.write_bg(f),
 			Color::Red 			=> color::Red.write_bg(f),
-			Color::LightWhite 	=> color::LightWhite.write_bg(f),
 			Color::White 		=> color::White.write_bg(f),
 			Color::LightYellow 	=> color::LightYellow.write_bg(f),
 			Color::Yellow 		=> color::Yellow.write_bg(f),
+			Color::Reset 		=> color::Reset.write_bg(f),
     	}
     }
 
     // Foreground:
     fn write_fg(&self, f: &mut fmt::Formatter) -> fmt::Result
     {
+    	if mode() == ColorMode::None
+    	{
+    		return Ok(());
+    	}
+
     	match self
     	{
 			Color::LightBlack 	=> color::LightBlack.write_fg(f),
-			Color::Black 		=> color::Black.write_fg(f),
-			Color::LightBlue 	=> color::LightBlue.write_fg(f),
 			Color::Blue 		=> color::Blue.write_fg(f),
-			Color::LightCyan 	=> color::LightCyan.write_fg(f),
-			Color::Cyan 		=> color::Cyan.write_fg(f),
-			Color::LightGreen 	=> color::LightGreen.write_fg(f),
 			Color::Green 		=> color::Green.write_fg(f),
-			Color::LightMagenta => color::LightMagenta.write_fg(f),
-			Color::Magenta 		=> color::Magenta.write_fg(f),
-			Color::LightRed 	=> color::LightRed.write_fg(f),
 			Color::Red 			=> color::Red.write_fg(f),
-			Color::LightWhite 	=> color::LightWhite.write_fg(f),
 			Color::White 		=> color::White.write_fg(f),
 			Color::LightYellow 	=> color::LightYellow.write_fg(f),
 			Color::Yellow 		=> color::Yellow.write_fg(f),
+			Color::Reset 		=> color::Reset.write_fg(f),
     	}
     }
 }
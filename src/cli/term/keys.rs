@@ -0,0 +1,58 @@
+use std::io;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use termion::{event::Key, input::TermRead};
+
+// Like "termion::async_stdin().keys()", but the end of stdin is reported instead of looking like a missing key.
+// The keys are read by another thread and polled without blocking.
+pub struct AsyncKeys
+{
+	recv: Option<Receiver<io::Result<Key>>>,
+}
+
+impl Iterator for AsyncKeys
+{
+	type Item = io::Result<Key>;
+
+	// None means that there is no key at the moment.
+	// Once stdin is closed, a single "UnexpectedEof" error is returned (and None afterwards):
+	fn next(&mut self) -> Option<io::Result<Key>>
+	{
+		let received = self.recv.as_ref()?.try_recv();
+
+		match received
+		{
+			Ok(key) 							=> Some(key),
+			Err(TryRecvError::Empty) 			=> None,
+			Err(TryRecvError::Disconnected) 	=>
+			{
+				self.recv = None;
+				Some(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stdin has been closed")))
+			},
+		}
+	}
+}
+
+pub fn async_keys() -> AsyncKeys
+{
+	let (send, recv) = mpsc::channel();
+
+	// The thread stops at the end of stdin or after the first error (dropping the sender disconnects the channel):
+	thread::spawn(move ||
+	{
+		for key in io::stdin().keys()
+		{
+			let failed = key.is_err();
+
+			if send.send(key).is_err() || failed
+			{
+				return;
+			}
+		}
+	});
+
+	AsyncKeys
+	{
+		recv: Some(recv),
+	}
+}
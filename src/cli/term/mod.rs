@@ -2,8 +2,10 @@
 
 // Our own modules:
 pub mod color;
+pub mod keys;
+pub mod style;
 pub mod ui;
 
 // Import the other termion modules we need here, too.
 // This allows us to completely elide termion module uses.
-pub use termion::{clear, cursor, style};
+pub use termion::{clear, cursor, event, is_tty, raw};
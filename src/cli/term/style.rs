@@ -0,0 +1,24 @@
+use std::fmt;
+use termion::style;
+use crate::cli::term::color::{self, ColorMode};
+
+// Wrappers around the Termion styles that honor the color mode (plain output has no styles):
+
+pub struct Bold;
+pub struct Reset;
+
+impl fmt::Display for Bold
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		if color::mode() == ColorMode::Ansi { write!(f, "{:}", style::Bold) } else { Ok(()) }
+	}
+}
+
+impl fmt::Display for Reset
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		if color::mode() == ColorMode::Ansi { write!(f, "{:}", style::Reset) } else { Ok(()) }
+	}
+}
@@ -1,5 +1,6 @@
-use termion::cursor;
-use crate::cli::term::color;
+use std::fmt;
+use termion::{cursor, terminal_size};
+use crate::cli::term::color::{self, ColorMode};
 
 // How to draw a perpendicular line?
 pub enum LineDirection
@@ -8,49 +9,104 @@ pub enum LineDirection
 	Vertical,
 }
 
-pub fn draw_char(c: char, x: u16, y: u16, color: color::Color)
+// The colors and the line style of a named box:
+#[derive(Copy, Clone)]
+pub struct BoxStyle
 {
-	print!("{color}{goto}{chr}",
+	pub border_color: color::Color,
+	pub name_color: color::Color,
+	pub thick: bool,
+}
+
+impl BoxStyle
+{
+	// The gray, thin box with a white name that frames registers, flags and panels:
+	pub const PLAIN: BoxStyle = BoxStyle::new(color::LightBlack, color::White, false);
+
+	pub const fn new(border_color: color::Color, name_color: color::Color, thick: bool) -> BoxStyle
+	{
+		BoxStyle
+		{
+			border_color,
+			name_color,
+			thick,
+		}
+	}
+}
+
+// In plain mode, box drawing characters are replaced by ASCII fallbacks:
+pub fn glyph(c: char) -> char
+{
+	if color::mode() == ColorMode::Ansi
+	{
+		return c;
+	}
+
+	// Apart from the lines, the characters of the box drawing block are junctions or corners:
+	match c
+	{
+		'─' | '═' 				=> '-',
+		'│' | '║' 				=> '|',
+		'\u{2500}'..='\u{257F}' => '+',
+		_ 						=> c,
+	}
+}
+
+pub fn glyphs(s: &str) -> String
+{
+	s.chars().map(glyph).collect()
+}
+
+pub fn draw_char(out: &mut dyn fmt::Write, c: char, x: u16, y: u16, color: color::Color) -> fmt::Result
+{
+	write!(out, "{color}{goto}{chr}",
 		color = color::Fg(color),
 		goto = cursor::Goto(x, y),
-		chr = c);
+		chr = glyph(c))?;
+
+	Ok(())
 }
 
-pub fn draw_perpendicular_line(start_x: u16, start_y: u16, end_xy: u16, dir: LineDirection, start: char, inner: char, end: char, color: color::Color)
+// The characters are the start, the inner part and the end of the line:
+pub fn draw_perpendicular_line(out: &mut dyn fmt::Write, start_x: u16, start_y: u16, end_xy: u16, dir: LineDirection, chars: [char; 3], color: color::Color) -> fmt::Result
 {
+	let [start, inner, end] = chars;
+
 	match dir
 	{
 		LineDirection::Horizontal =>
 		{
-			draw_char(start, start_x, start_y, color);
+			draw_char(out, start, start_x, start_y, color)?;
 
 			for _ in (start_x + 1)..end_xy
 			{
-				print!("{:}", inner);
+				write!(out, "{:}", glyph(inner))?;
 			}
 
-			print!("{:}", end);
+			write!(out, "{:}", glyph(end))?;
 		},
 		LineDirection::Vertical =>
 		{
-			draw_char(start, start_x, start_y, color);
+			draw_char(out, start, start_x, start_y, color)?;
 
 			// We always need to position the cursor here!
 			for y in (start_y + 1)..end_xy
 			{
-				print!("{goto}{inner_char}",
+				write!(out, "{goto}{inner_char}",
 					goto = cursor::Goto(start_x, y),
-					inner_char = inner);
+					inner_char = glyph(inner))?;
 			}
 
-			print!("{goto}{end_char}",
+			write!(out, "{goto}{end_char}",
 				goto = cursor::Goto(start_x, end_xy),
-				end_char = end);
+				end_char = glyph(end))?;
 		},
 	}
+
+	Ok(())
 }
 
-pub fn draw_box(x: u16, y: u16, width: u16, height: u16, color: color::Color, thick: bool)
+pub fn draw_box(out: &mut dyn fmt::Write, x: u16, y: u16, width: u16, height: u16, color: color::Color, thick: bool) -> fmt::Result
 {
 	// Select the charset:
 	let (lower_left, lower_right, upper_left, upper_right, horz_inner, vert_inner) = if thick
@@ -64,22 +120,47 @@ pub fn draw_box(x: u16, y: u16, width: u16, height: u16, color: color::Color, th
 
 	// Draw four lines.
 	// The horizontal lines contain the corner characters.
-	draw_perpendicular_line(x, y, x + width - 1, LineDirection::Horizontal, upper_left, horz_inner, upper_right, color);
-	draw_perpendicular_line(x, y + height - 1, x + width - 1, LineDirection::Horizontal, lower_left, horz_inner, lower_right, color);
-	draw_perpendicular_line(x, y + 1, y + height - 2, LineDirection::Vertical, vert_inner, vert_inner, vert_inner, color);
-	draw_perpendicular_line(x + width - 1, y + 1, y + height - 2, LineDirection::Vertical, vert_inner, vert_inner, vert_inner, color);
+	draw_perpendicular_line(out, x, y, x + width - 1, LineDirection::Horizontal, [upper_left, horz_inner, upper_right], color)?;
+	draw_perpendicular_line(out, x, y + height - 1, x + width - 1, LineDirection::Horizontal, [lower_left, horz_inner, lower_right], color)?;
+	draw_perpendicular_line(out, x, y + 1, y + height - 2, LineDirection::Vertical, [vert_inner, vert_inner, vert_inner], color)?;
+	draw_perpendicular_line(out, x + width - 1, y + 1, y + height - 2, LineDirection::Vertical, [vert_inner, vert_inner, vert_inner], color)?;
+
+	Ok(())
 }
 
-pub fn draw_named_box(x: u16, y: u16, width: u16, height: u16, border_color: color::Color, name: &str, name_color: color::Color, thick: bool)
+pub fn draw_named_box(out: &mut dyn fmt::Write, x: u16, y: u16, width: u16, height: u16, name: &str, style: BoxStyle) -> fmt::Result
 {
 	// Draw the box itself:
-	draw_box(x, y, width, height, border_color, thick);
+	draw_box(out, x, y, width, height, style.border_color, style.thick)?;
 
 	// Write the box name to the top:
 	let name_x = x + (width - (name.len() as u16)) / 2;
 
-	print!("{goto}{name_color}{name}",
+	write!(out, "{goto}{name_color}{name}",
 		goto = cursor::Goto(name_x, y),
-		name_color = color::Fg(name_color),
-		name = name);
+		name_color = color::Fg(style.name_color),
+		name = name)?;
+
+	Ok(())
+}
+
+// Does a rectangle at the given position fit into the terminal?
+// If the size cannot be queried (e. g. because the output is redirected), assume that it fits.
+pub fn fits_terminal(x: u16, y: u16, width: u16, height: u16) -> bool
+{
+	match terminal_size()
+	{
+		Ok((columns, rows)) 	=> (x + width - 1 <= columns) && (y + height - 1 <= rows),
+		Err(_) 					=> true,
+	}
+}
+
+// Instead of scribbling off-screen, tell the user how large the terminal must be for a rectangle at the given position:
+pub fn draw_too_small(out: &mut dyn fmt::Write, x: u16, y: u16, width: u16, height: u16) -> fmt::Result
+{
+	write!(out, "{goto}{fg_color}Terminal too small: {columns}x{rows} characters are needed.",
+		goto = cursor::Goto(x, y),
+		fg_color = color::Fg(color::Red),
+		columns = x + width - 1,
+		rows = y + height - 1)
 }
@@ -11,3 +11,9 @@ pub mod unit;
 // Helper modules for bus transfers and microcycles:
 pub mod bus;
 pub mod microcycle;
+
+// Recording of state changes during (micro)cycles:
+pub mod record;
+
+// Watch expressions over the machine state:
+pub mod watch;
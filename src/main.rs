@@ -1,79 +1,216 @@
 mod cli;
 
-use std::time::Duration;
-use std::thread;
-use mimasim::{assembly::ObjectCode, mima::Mima};
-use crate::cli::{gfx::{CycleDiagram, MicrocycleDiagram}, record::{CycleSummary, MicrocycleSummary}, term::clear};
+use std::{env, fs, io, iter, process};
+use mimasim::{assembly::ObjectCode, mima::Mima, record::{render_oneline, CycleSummary}, watch::WatchExpression};
+use crate::cli::{args::{Args, View}, gfx::{CycleDiagram, MemoryPanel, MicrocycleDiagram, WatchPanel}, pacer::Pacer, stepper::{Mode, Stepper}, term::{clear, color::{self, ColorMode}, cursor::{self, HideCursor}, event::Key, is_tty, keys::async_keys, raw::IntoRawMode}};
 
-fn main()
-{
-	let (object_code, _) = ObjectCode::assemble("
+// The program that runs if no source file is given (Fibonacci numbers).
+// A fresh MiMA has no devices, so unlike "examples/fibonacci.asm", the demo neither reads the count nor prints the numbers.
+// Watch them in the memory instead.
+const DEMO_PROGRAM: &str = "
 
-		jmp loop
+	jmp loop
 
-		last: DAT 0 # starts undefined
-		curr: DAT 0
-		next: DAT 1
+	last: DAT 0 # starts undefined
+	curr: DAT 0
+	next: DAT 1
 
-		count: DAT 6 # starts undefined
-		decr: DAT -1
+	count: DAT 6
+	decr: DAT -1
 
-		# Read count
-		# TODO
+	loop:
 
-		loop:
+	# Check if done
+	LDV count
+	ADD decr
+	JMN out
+	STV count
 
-		# Check if done
-		LDV count
-		ADD decr
-		JMN out
-		STV count
+	# curr -> last
+	LDV curr
+	STV last
 
-		# Print curr
-		LDV curr
-		# TODO
+	# next -> curr
+	LDV next
+	STV curr
 
-		# curr -> last
-		STV last
+	# last + curr -> next
+	ADD last
+	STV next
 
-		# next -> curr
-		LDV next
-		STV curr
+	# Next iteration
+	JMP loop
 
-		# last + curr -> next
-		ADD last
-		STV next
+	# End of program
+	out:
+	HLT
 
-		# Next iteration
-		JMP loop
+	";
 
-		# End of program
-		out:
-		HLT
+fn main()
+{
+	let args = match Args::parse(env::args().skip(1))
+	{
+		Ok(args) 	=> args,
+		Err(err) 	=>
+		{
+			eprintln!("{:}\n{:}", err, Args::USAGE);
+			process::exit(2);
+		},
+	};
+
+	// Assemble the given source file (or the demo) and print the diagnostics.
+	// Programs that cannot be read or assembled end the simulator with an error.
+	let source = match &args.path
+	{
+		Some(path) 	=> fs::read_to_string(path).unwrap_or_else(|err|
+		{
+			eprintln!("Failed to read \"{:}\": {:}", path, err);
+			process::exit(1);
+		}),
+		None 		=> String::from(DEMO_PROGRAM),
+	};
 
-	").unwrap();
+	let (object_code, diagnostics) = match ObjectCode::assemble(&source)
+	{
+		Ok((object_code, diagnostics)) =>
+		{
+			for diagnostic in diagnostics.iter()
+			{
+				eprintln!("{:}", diagnostic);
+			}
+
+			(object_code, diagnostics)
+		},
+		Err(err) =>
+		{
+			eprintln!("{:}", err);
+			process::exit(1);
+		},
+	};
 
 	let mut mima = Mima::new();
-	mima.memory_unit.load_code(&object_code).unwrap();
+
+	// A fresh MiMA has no devices, so programs with device labels cannot be linked:
+	if let Err(err) = mima.load_code(&object_code)
+	{
+		eprintln!("{:}", err);
+		process::exit(1);
+	}
+
+	// Watch the Fibonacci numbers and the counter of the demo (or the basic registers for other programs):
+	let watch_sources: &[&str] = if args.path.is_none() { &["mem[1]", "mem[2]", "mem[3]", "mem[4]", "IAR - 1"] } else { &["ACC", "IAR"] };
+	let watches: Vec<_> = watch_sources.iter().map(|source| WatchExpression::parse(source).unwrap()).collect();
+
+	// Draw plain output (no colors or styles, ASCII boxes) if NO_COLOR is set:
+	color::set_mode(ColorMode::from_env());
+
+	// Keys can only be read from a terminal (and raw mode needs one for the output).
+	// Otherwise (e. g. if the output is redirected to a file), the microcycles are animated without keys:
+	let interactive = is_tty(&io::stdin()) && is_tty(&io::stdout());
+
+	// By default, the user steps through the microcycles (see "Stepper").
+	// With "--auto" (or "--speed"), they are animated like a slideshow instead.
+	let mode = if args.auto || !interactive { Mode::Auto } else { Mode::Step };
+	let pacer = args.speed.map_or_else(Pacer::new, Pacer::with_delay);
+	let mut stepper = Stepper::new(mode, pacer);
+
+	// Read keys without waiting for a line break and hide the cursor.
+	// Both are undone when the guard is dropped (also if the MiMA halts or faults).
+	let _terminal = if interactive
+	{
+		match io::stdout().into_raw_mode()
+		{
+			Ok(raw_terminal) 	=> Some(HideCursor::from(raw_terminal)),
+			Err(err) 			=>
+			{
+				eprintln!("Failed to switch the terminal to raw mode: {:}", err);
+				process::exit(1);
+			},
+		}
+	}
+	else
+	{
+		None
+	};
+
+	let mut keys: Box<dyn Iterator<Item = io::Result<Key>>> = if interactive { Box::new(async_keys()) } else { Box::new(iter::empty()) };
 
 	let mut start_summary = None;
+	let mut microcycles = mima.microcycles();
 
-	while let Some(microcycle_summary) = MicrocycleSummary::record_microcycle(&mut mima)
+	while let Some(microcycle_summary) = microcycles.next()
 	{
-		println!("{clear}", clear = clear::All);
+		let microcycle = microcycle_summary.microcycle;
 
-		MicrocycleDiagram::draw_from_summary(&microcycle_summary, 1, 4);
+		// Draw the microcycle in the selected view (lines need an explicit carriage return in raw mode):
+		match args.view
+		{
+			View::Full 		=>
+			{
+				println!("{clear}", clear = clear::All);
+
+				MicrocycleDiagram::draw_from_summary(&microcycle_summary, 1, 4);
+				WatchPanel::draw_from_mima(&watches, microcycles.mima(), 2, 4 + MicrocycleDiagram::HEIGHT);
+				MemoryPanel::draw_from_mima(microcycles.mima(), microcycle_summary.mem_write, 2, 5, 2 + MicrocycleDiagram::WIDTH, 5);
+			},
+			View::Cycle 	=> (),
+			View::Oneline 	=> print!("{:}\r\n", render_oneline(&microcycle_summary)),
+		}
 
-		if microcycle_summary.microcycle == 1
+		if microcycle == 1
 		{
 			start_summary = Some(microcycle_summary);
 		}
-		else if microcycle_summary.microcycle == 12
+		else if microcycle == 12
 		{
 			let cycle_summary = CycleSummary::from_microcycle_summaries(start_summary.as_ref().unwrap(), &microcycle_summary);
-			CycleDiagram::draw_from_summary(&cycle_summary, 1, 1);
+
+			match args.view
+			{
+				View::Full 		=> CycleDiagram::draw_from_summary(&cycle_summary, 1, 1),
+				View::Cycle 	=>
+				{
+					println!("{clear}", clear = clear::All);
+					CycleDiagram::draw_from_summary(&cycle_summary, 1, 1);
+				},
+				View::Oneline 	=> (),
+			}
+		}
+
+		// The cycle view only changes after an instruction, so there is nothing to wait for in between:
+		if (args.view == View::Cycle) && (microcycle != 12)
+		{
+			continue;
+		}
+
+		if !stepper.wait(&mut keys, microcycle)
+		{
+			break;
 		}
+	}
+
+	// The diagrams clear the screen, so the diagnostics are repeated below them (raw mode needs an explicit carriage return):
+	let bottom = match args.view
+	{
+		View::Full 		=> Some(4 + MicrocycleDiagram::HEIGHT + (watches.len() as u16) + 2),
+		View::Cycle 	=> Some(1 + CycleDiagram::HEIGHT),
+		View::Oneline 	=> None,
+	};
+
+	if let Some(bottom) = bottom
+	{
+		print!("{:}", cursor::Goto(1, bottom));
 
-		thread::sleep(Duration::from_millis(500));
+		for diagnostic in diagnostics.iter()
+		{
+			print!("{:}\r\n", diagnostic);
+		}
+	}
+
+	// Tell the user why the MiMA has stopped if it faulted:
+	if let Some(fault) = mima.fault()
+	{
+		print!("Fault: {:}\r\n", fault);
 	}
 }
@@ -1,8 +1,10 @@
 use crate::types::{*, Registers as Regs};
-use crate::bus::Xfer as BusXfer;
+use crate::bus::{Xfer as BusXfer, AccCondition};
 use crate::unit::{ALUOperation, MemoryAccess};
 
 // A microcycle descriptor encapsulates an optional bus transfer, an optional ALU signal and an optional memory signal.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Descriptor
 {
 	pub bus_xfer: Option<BusXfer>,
@@ -38,9 +40,16 @@ impl Descriptor
 	}
 
 	// Mark as accumulator-dependent:
-	pub(crate) fn acc_dependent(mut self) -> Descriptor
+	pub(crate) fn acc_dependent(mut self, condition: AccCondition) -> Descriptor
 	{
-		self.bus_xfer.as_mut().expect("Create bus transfer first!").make_acc_dependent();
+		self.bus_xfer.as_mut().expect("Create bus transfer first!").make_acc_dependent(condition);
+		self
+	}
+
+	// Mark the masked source as signed immediate:
+	pub(crate) fn sign_extended(mut self) -> Descriptor
+	{
+		self.bus_xfer.as_mut().expect("Create bus transfer first!").make_sign_extending();
 		self
 	}
 
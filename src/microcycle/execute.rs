@@ -1,5 +1,5 @@
 use crate::types::{*, Registers as Regs};
-use crate::bus::Xfer as BusXfer;
+use crate::bus::{Xfer as BusXfer, AccCondition};
 use crate::unit::{ALUOperation::*, MemoryAccess::*};
 use super::descriptor::Descriptor;
 
@@ -27,12 +27,21 @@ pub fn descriptor(microcycle: u8, instruction: Instruction) -> Descriptor
 		Instruction::StoreValue(_) 		=> descriptor_store_value(microcycle),
 		Instruction::LoadConstant(_)	=> descriptor_load_constant(microcycle),
 		Instruction::Jump(_)			=> descriptor_jump(microcycle),
-		Instruction::JumpIfNegative(_) 	=> descriptor_jump_if_negative(microcycle),
+		Instruction::JumpIfNegative(_) 	=> descriptor_conditional_jump(microcycle, AccCondition::Negative),
+		Instruction::JumpIfZero(_) 		=> descriptor_conditional_jump(microcycle, AccCondition::Zero),
 		Instruction::Equals(_) 			=> descriptor_equals(microcycle),
+		Instruction::Compare(_) 		=> descriptor_compare(microcycle),
+		Instruction::JumpIfEqual(_) 	=> descriptor_conditional_jump(microcycle, AccCondition::Equal),
+		Instruction::Subtract(_) 		=> descriptor_subtract(microcycle),
 		Instruction::Halt 				=> descriptor_halt(microcycle),
 		Instruction::Not 				=> descriptor_not(microcycle),
 		Instruction::RotateRight(_) 	=> descriptor_rotate_right(microcycle),
+		Instruction::RotateLeft(_) 		=> descriptor_rotate_left(microcycle),
+		Instruction::SkipIfNegative 	=> descriptor_skip_if_negative(microcycle),
 		Instruction::NoOperation 		=> descriptor_no_operation(microcycle),
+
+		// The MiMA faults before it executes an illegal instruction:
+		Instruction::Illegal(_) 		=> empty_desc(),
 	}
 }
 
@@ -108,7 +117,7 @@ fn descriptor_load_constant(microcycle: u8) -> Descriptor
 {
 	match microcycle
 	{
-		6 => empty_desc().with_masked_bus_xfer(Regs::IR, Regs::ACC, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD),
+		6 => empty_desc().with_masked_bus_xfer(Regs::IR, Regs::ACC, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD).sign_extended(),
 		_ => empty_desc(),
 	}
 }
@@ -122,11 +131,11 @@ fn descriptor_jump(microcycle: u8) -> Descriptor
 	}
 }
 
-fn descriptor_jump_if_negative(microcycle: u8) -> Descriptor
+fn descriptor_conditional_jump(microcycle: u8, condition: AccCondition) -> Descriptor
 {
 	match microcycle
 	{
-		6 => empty_desc().with_masked_bus_xfer(Regs::IR, Regs::IAR, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD).acc_dependent(),
+		6 => empty_desc().with_masked_bus_xfer(Regs::IR, Regs::IAR, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD).acc_dependent(condition),
 		_ => empty_desc(),
 	}
 }
@@ -143,14 +152,35 @@ fn descriptor_equals(microcycle: u8) -> Descriptor
 	}
 }
 
-fn descriptor_halt(microcycle: u8) -> Descriptor
+// Like EQL, but Z is not written back to ACC (the ALU stores the result in its EQUAL flag):
+fn descriptor_compare(microcycle: u8) -> Descriptor
 {
 	match microcycle
 	{
-		_ => empty_desc(),
+		6 	=> empty_desc().with_masked_bus_xfer(Regs::IR, Regs::SAR, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD).with_mem_access(Read),
+		7 	=> empty_desc().with_bus_xfer(Regs::ACC, Regs::X),
+		10 	=> empty_desc().with_bus_xfer(Regs::SIR, Regs::Y).with_alu_op(Compare),
+		_ 	=> empty_desc(),
+	}
+}
+
+fn descriptor_subtract(microcycle: u8) -> Descriptor
+{
+	match microcycle
+	{
+		6 	=> empty_desc().with_masked_bus_xfer(Regs::IR, Regs::SAR, BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD).with_mem_access(Read),
+		7 	=> empty_desc().with_bus_xfer(Regs::ACC, Regs::X),
+		10 	=> empty_desc().with_bus_xfer(Regs::SIR, Regs::Y).with_alu_op(Subtract),
+		12 	=> empty_desc().with_bus_xfer(Regs::Z, Regs::ACC),
+		_ 	=> empty_desc(),
 	}
 }
 
+fn descriptor_halt(_microcycle: u8) -> Descriptor
+{
+	empty_desc()
+}
+
 fn descriptor_not(microcycle: u8) -> Descriptor
 {
 	match microcycle
@@ -172,10 +202,31 @@ fn descriptor_rotate_right(microcycle: u8) -> Descriptor
 	}
 }
 
-fn descriptor_no_operation(microcycle: u8) -> Descriptor
+fn descriptor_rotate_left(microcycle: u8) -> Descriptor
 {
 	match microcycle
 	{
-		_ => empty_desc(),
+		6 	=> empty_desc().with_bus_xfer(Regs::ACC, Regs::X),
+		7 	=> empty_desc().with_masked_bus_xfer(Regs::IR, Regs::Y, BusXfer::SOURCE_BITMASK_EXTENDED_PAYLOAD).with_alu_op(RotateLeft),
+		9 	=> empty_desc().with_bus_xfer(Regs::Z, Regs::ACC),
+		_ 	=> empty_desc(),
+	}
+}
+
+// IAR already points behind SKN, so the ALU increments it once more (like the fetch does).
+// Only the final transfer back to IAR depends on the accumulator:
+fn descriptor_skip_if_negative(microcycle: u8) -> Descriptor
+{
+	match microcycle
+	{
+		6 	=> empty_desc().with_bus_xfer(Regs::IAR, Regs::X),
+		7 	=> empty_desc().with_bus_xfer(Regs::ONE, Regs::Y).with_alu_op(Add),
+		9 	=> empty_desc().with_bus_xfer(Regs::Z, Regs::IAR).acc_dependent(AccCondition::Negative),
+		_ 	=> empty_desc(),
 	}
 }
+
+fn descriptor_no_operation(_microcycle: u8) -> Descriptor
+{
+	empty_desc()
+}
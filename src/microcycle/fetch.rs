@@ -13,6 +13,8 @@ pub fn descriptor(microcycle: u8) -> Descriptor
 {
 	debug_assert!((1..=5).contains(&microcycle), "Fetch microcycles must be in [1, 5].");
 
+	// IAR is incremented without a mask, but it cannot wrap:
+	// Running past the end of the linear memory leaves IAR at the start of the device I/O address space and the next fetch faults there (see "Fault::FetchFromDeviceIO").
 	match microcycle
 	{
 		1 => empty_desc().with_bus_xfer(Regs::IAR, Regs::SAR | Regs::X).with_mem_access(Read),
@@ -2,6 +2,49 @@ mod descriptor;
 mod fetch;
 mod execute;
 
+use std::ops::RangeInclusive;
+use crate::types::Instruction;
+
 pub use descriptor::Descriptor;
-pub(crate) use fetch::descriptor as fetch_descriptor;
-pub(crate) use execute::descriptor as execute_descriptor;
+pub use fetch::descriptor as fetch_descriptor;
+pub use execute::descriptor as execute_descriptor;
+
+// The microcycles of the fetch stage (shared by all instructions) and of the execute stage:
+pub const FETCH_MICROCYCLES: RangeInclusive<u8> = 1..=5;
+pub const EXECUTE_MICROCYCLES: RangeInclusive<u8> = 6..=12;
+
+// The stage a microcycle belongs to:
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Stage
+{
+	Fetch,
+	Execute,
+}
+
+// Return the stage of the microcycle in [1, 12]:
+pub fn stage(microcycle: u8) -> Stage
+{
+	debug_assert!((1..=12).contains(&microcycle), "Microcycles must be in [1, 12].");
+
+	if FETCH_MICROCYCLES.contains(&microcycle)
+	{
+		Stage::Fetch
+	}
+	else
+	{
+		Stage::Execute
+	}
+}
+
+// Return the descriptor that is generated for the instruction in the microcycle in [1, 12].
+// This allows to render the full microcode table without running a MiMA.
+// The fetch stage does not know the instruction yet, so its descriptors are the same for all instructions (see "stage").
+pub fn describe(instruction: Instruction, microcycle: u8) -> Descriptor
+{
+	match stage(microcycle)
+	{
+		Stage::Fetch 	=> fetch_descriptor(microcycle),
+		Stage::Execute 	=> execute_descriptor(microcycle, instruction),
+	}
+}
@@ -0,0 +1,113 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use crate::types::*;
+use crate::unit::*;
+use crate::assembly::ObjectCode;
+use super::{Mima, Fault, RunOutcome};
+
+// The state of a MiMA after a program has been evaluated headlessly (e. g. to check the result in a test).
+// The memory holds the words of the requested range after the run (if any has been requested).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalState
+{
+	pub acc: Word,
+	pub iar: Word,
+	pub run: bool,
+	pub memory: Option<Box<[Word]>>,
+}
+
+// This error type occurs when a program cannot be evaluated:
+// It cannot be linked (a fresh MiMA has no devices), the requested memory range is not part of the linear memory, it does not halt within the given number of microcycles or faults.
+#[derive(Debug)]
+pub enum EvaluateError<'oc>
+{
+	Link(LinkError<'oc>),
+	BadRange(Range<Word>),
+	MicrocycleLimitReached(usize),
+	Faulted(Fault),
+}
+
+impl<'oc> fmt::Display for EvaluateError<'oc>
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			EvaluateError::Link(err) 						=> write!(f, "{:}", err),
+			EvaluateError::BadRange(range) 					=> write!(f, "The range [{:}, {:}) is not part of the linear address space.", range.start, range.end),
+			EvaluateError::MicrocycleLimitReached(count) 	=> write!(f, "The MiMA did not halt within {:} microcycles.", count),
+			EvaluateError::Faulted(fault) 					=> write!(f, "The MiMA faulted: {:}", fault),
+		}
+	}
+}
+
+impl<'oc> Error for EvaluateError<'oc> { }
+
+impl<'oc> From<LinkError<'oc>> for EvaluateError<'oc>
+{
+	fn from(err: LinkError<'oc>) -> EvaluateError<'oc>
+	{
+		EvaluateError::Link(err)
+	}
+}
+
+impl Mima
+{
+	// Load the object code into a fresh MiMA and run it via microcycles until it halts.
+	// At most "max_microcycles" microcycles are performed.
+	pub fn evaluate(object_code: &ObjectCode, max_microcycles: usize) -> Result<FinalState, EvaluateError<'_>>
+	{
+		Mima::evaluate_with_memory(object_code, max_microcycles, None)
+	}
+
+	// Like "evaluate", but the final state also holds the words of the given range of the linear memory:
+	pub fn evaluate_with_memory(object_code: &ObjectCode, max_microcycles: usize, memory: Option<Range<Word>>) -> Result<FinalState, EvaluateError<'_>>
+	{
+		if let Some(range) = memory.as_ref().filter(|range| (range.start > range.end) || (range.end > LINEAR_ADDRESS_SPACE_RANGE.end))
+		{
+			return Err(EvaluateError::BadRange(range.clone()));
+		}
+
+		let mut mima = Mima::new();
+		mima.load_code(object_code)?;
+
+		match mima.run_with_limit(max_microcycles)
+		{
+			RunOutcome::Halted 				=> (),
+			RunOutcome::Faulted(fault) 		=> return Err(EvaluateError::Faulted(fault)),
+			_ 								=> return Err(EvaluateError::MicrocycleLimitReached(max_microcycles)),
+		}
+
+		Ok(FinalState
+		{
+			acc: mima.arithmetic_unit.acc,
+			iar: mima.control_unit.iar,
+			run: mima.control_unit.status().run.0,
+			memory: memory.map(|range| mima.memory_unit.linear_memory()[(range.start.0 as usize)..(range.end.0 as usize)].into()),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn memory_is_only_copied_on_request()
+	{
+		let (code, _) = ObjectCode::assemble("ldv a\nadd b\nstv sum\nhlt\na: dat 3\nb: dat 5\nsum: dat 0").unwrap();
+
+		let state = Mima::evaluate(&code, 1000).unwrap();
+		assert_eq!(state.acc, Word(8));
+		assert!(!state.run);
+		assert_eq!(state.memory, None);
+
+		let state = Mima::evaluate_with_memory(&code, 1000, Some(Word(4)..Word(7))).unwrap();
+		assert_eq!(state.memory.as_deref(), Some(&[Word(3), Word(5), Word(8)][..]));
+
+		let beyond = Word(4)..Word(LINEAR_ADDRESS_SPACE_RANGE.end.0 + 1);
+		assert!(matches!(Mima::evaluate_with_memory(&code, 1000, Some(beyond)), Err(EvaluateError::BadRange(_))));
+	}
+}
@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::fmt;
+use crate::types::{Registers as Regs, Word};
+use crate::unit::{ALUFault, MemoryFault};
+
+// A fault stops the MiMA in an erroneous state (in contrast to HLT):
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault
+{
+	// A bus transfer read Z or SIR before the pending ALU / memory work has delivered its result.
+	// A stale Z is only detected in strict mode.
+	ResultNotReady(Regs),
+
+	// An instruction has been fetched from the device I/O address space (e. g. after a jump there).
+	// The MiMA is halted, too.
+	FetchFromDeviceIO(Word),
+
+	// An instruction has been fetched from beyond the address space.
+	// The microcode cannot get there (see "microcycle::fetch_descriptor"), so IAR must have been set from outside.
+	// The MiMA is halted, too.
+	FetchOutOfRange(Word),
+
+	// Pending memory work failed (it accessed an address outside of the linear memory or wrote to protected memory).
+	// The MiMA is halted, too.
+	Memory(MemoryFault),
+
+	// The ALU has been signaled with a custom operation that has not been registered at it.
+	// The MiMA is halted, too.
+	ALU(ALUFault),
+
+	// The instruction word at the given address (first) has a reserved opcode (see "Instruction::Illegal").
+	// The MiMA is halted, too.
+	IllegalInstruction(Word, Word),
+}
+
+impl fmt::Display for Fault
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			Fault::ResultNotReady(reg) 				=> write!(f, "The result in {:?} has been consumed before the pending work completed.", reg),
+			Fault::FetchFromDeviceIO(addr) 			=> write!(f, "The instruction at {:} cannot be fetched because it is in the device I/O address space.", addr),
+			Fault::FetchOutOfRange(addr) 			=> write!(f, "The instruction at {:} cannot be fetched because it is outside of the address space.", addr),
+			Fault::Memory(fault) 					=> write!(f, "{:}", fault),
+			Fault::ALU(fault) 						=> write!(f, "{:}", fault),
+			Fault::IllegalInstruction(addr, word) 	=> write!(f, "The word {:} at {:} is not a valid instruction.", word, addr),
+		}
+	}
+}
+
+impl Error for Fault { }
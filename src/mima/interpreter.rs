@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::fmt;
+use crate::types::*;
+use crate::unit::*;
+use crate::bus::{Xfer as BusXfer, AccCondition};
+use super::{Mima, Fault};
+
+// This error type occurs when the fast interpreter does not reach a HLT instruction in time.
+// It holds the number of executed instructions.
+// Faults occur at the same points as in the microcycle engine (the instruction that faults is performed via microcycles if necessary).
+// With latencies other than the defaults, whole programs are performed via microcycles (see "has_default_latencies").
+#[derive(Debug)]
+pub enum RunError
+{
+	InstructionLimitReached(usize),
+	Faulted(Fault),
+}
+
+impl fmt::Display for RunError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			RunError::InstructionLimitReached(count) 	=> write!(f, "The MiMA did not halt within {:} instructions.", count),
+			RunError::Faulted(fault) 					=> write!(f, "The MiMA faulted: {:}", fault),
+		}
+	}
+}
+
+impl Error for RunError { }
+
+impl Mima
+{
+	// Run the MiMA until it halts, executing whole instructions instead of microcycles.
+	// The final state is identical to the one produced by the microcycle engine.
+	// Return the number of executed instructions or an error if the limit is hit before HLT.
+	pub fn run_to_halt(&mut self, max_instructions: usize) -> Result<usize, RunError>
+	{
+		// If an instruction is in progress, finish it via microcycles first:
+		while self.control_unit.is_running() && (self.control_unit.microcycle() != 1) && self.fault.is_none()
+		{
+			self.perform_microcycle();
+		}
+
+		// A faulted MiMA does not execute anything:
+		if let Some(fault) = self.fault
+		{
+			return Err(RunError::Faulted(fault));
+		}
+
+		if !self.has_default_latencies()
+		{
+			return self.run_to_halt_via_microcycles(max_instructions);
+		}
+
+		let mut executed = 0;
+
+		while self.control_unit.is_running()
+		{
+			if executed == max_instructions
+			{
+				return Err(RunError::InstructionLimitReached(executed));
+			}
+
+			self.begin_instruction_trace();
+
+			// Like microcycle 1, fault if the instruction would be fetched from the device I/O address space or beyond:
+			let iar = self.control_unit.iar;
+
+			if !LINEAR_ADDRESS_SPACE_RANGE.contains(&iar)
+			{
+				self.memory_unit.sar = iar;
+				self.arithmetic_unit.x = iar;
+
+				if ADDRESS_SPACE_RANGE.contains(&iar)
+				{
+					self.fault_fetch_from_device_io();
+				}
+				else
+				{
+					self.fault_fetch_out_of_range();
+				}
+
+				return Err(RunError::Faulted(self.fault.expect("The MiMA must have faulted.")));
+			}
+
+			self.fetch_instruction();
+
+			// Like microcycle 6, fault if the fetched word is not a valid instruction:
+			if let instruction @ Instruction::Illegal(word) = Instruction::from(self.control_unit.ir)
+			{
+				let status = *self.control_unit.status();
+				self.control_unit.restore_state(status, 6, Some(instruction));
+				self.fault_illegal_instruction(word);
+
+				return Err(RunError::Faulted(self.fault.expect("The MiMA must have faulted.")));
+			}
+
+			// A store to protected memory faults in the middle of its execution, so we let the microcycles take over:
+			if let Instruction::StoreValue(address) = Instruction::from(self.control_unit.ir)
+			{
+				if self.memory_unit.is_protected(address)
+				{
+					let status = *self.control_unit.status();
+					self.control_unit.restore_state(status, 6, Some(Instruction::StoreValue(address)));
+
+					while self.perform_microcycle().is_some() { }
+
+					return Err(RunError::Faulted(self.fault.expect("The MiMA must have faulted.")));
+				}
+			}
+
+			self.execute_instruction();
+			self.finish_instruction_trace(Instruction::from(self.control_unit.ir));
+			self.control_unit.count_instruction();
+
+			executed += 1;
+		}
+
+		Ok(executed)
+	}
+
+	// The fetch and execute microcycles are designed for the default latencies, so only then results arrive in time.
+	// Otherwise, the MiMA consumes stale results or faults, which the fast interpreter cannot reproduce.
+	fn has_default_latencies(&self) -> bool
+	{
+		(self.memory_unit.microcycles_per_access() == DEFAULT_MICROCYCLES_PER_ACCESS) &&
+		matches!(self.arithmetic_unit.microcycles_per_op(), None | Some(DEFAULT_MICROCYCLES_PER_OP))
+	}
+
+	// Perform whole instructions via microcycles and count them like the fast interpreter does:
+	fn run_to_halt_via_microcycles(&mut self, max_instructions: usize) -> Result<usize, RunError>
+	{
+		let start = self.control_unit.total_instructions();
+		let executed = |mima: &Mima| (mima.control_unit.total_instructions() - start) as usize;
+
+		while self.control_unit.is_running()
+		{
+			if executed(self) == max_instructions
+			{
+				return Err(RunError::InstructionLimitReached(executed(self)));
+			}
+
+			// Stop after microcycle 12 (the counter is back at 1) or if the MiMA halts or faults:
+			while self.perform_microcycle().is_some() && (self.control_unit.microcycle() != 1) { }
+
+			if let Some(fault) = self.fault
+			{
+				return Err(RunError::Faulted(fault));
+			}
+		}
+
+		Ok(executed(self))
+	}
+}
+
+impl Mima
+{
+	// Equivalent of the microcycles [1, 5]:
+	fn fetch_instruction(&mut self)
+	{
+		let iar = self.control_unit.iar;
+
+		// Increment IAR via X, Y and Z:
+		self.memory_unit.sar = iar;
+		self.arithmetic_unit.x = iar;
+		self.arithmetic_unit.y = self.arithmetic_unit.one;
+		self.arithmetic_unit.perform_immediately(ALUOperation::Add);
+		self.control_unit.iar = self.arithmetic_unit.z;
+
+		// Load the instruction word via SIR:
+		self.memory_unit.sir = self.memory_unit.read_immediately(iar);
+		self.control_unit.ir = self.memory_unit.sir;
+	}
+
+	// Equivalent of the microcycles [6, 12]:
+	fn execute_instruction(&mut self)
+	{
+		use Instruction::*;
+
+		// Extract the payloads from IR the same way the masked bus transfers do:
+		let ir = self.control_unit.ir;
+		let basic_payload = Word(ir.0 & BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD.0);
+		let extended_payload = Word(ir.0 & BusXfer::SOURCE_BITMASK_EXTENDED_PAYLOAD.0);
+
+		match Instruction::from(ir)
+		{
+			Add(_) 				=> self.execute_alu_with_memory(ALUOperation::Add, basic_payload),
+			And(_) 				=> self.execute_alu_with_memory(ALUOperation::And, basic_payload),
+			Or(_) 				=> self.execute_alu_with_memory(ALUOperation::Or, basic_payload),
+			Xor(_) 				=> self.execute_alu_with_memory(ALUOperation::Xor, basic_payload),
+			Equals(_) 			=> self.execute_alu_with_memory(ALUOperation::Equals, basic_payload),
+			Subtract(_) 		=> self.execute_alu_with_memory(ALUOperation::Subtract, basic_payload),
+			Compare(_) 			=> self.execute_comparison(basic_payload),
+
+			LoadValue(_) =>
+			{
+				self.memory_unit.sar = basic_payload;
+				self.memory_unit.sir = self.memory_unit.read_immediately(basic_payload);
+				self.arithmetic_unit.acc = self.memory_unit.sir;
+			},
+
+			StoreValue(_) =>
+			{
+				self.memory_unit.sar = basic_payload;
+				self.memory_unit.sir = self.arithmetic_unit.acc;
+				self.memory_unit.write_immediately(basic_payload, self.memory_unit.sir);
+			},
+
+			LoadConstant(_) 	=> self.arithmetic_unit.acc = if self.sign_extend_constants { basic_payload.sign_extend(BusXfer::SOURCE_BITMASK_BASIC_PAYLOAD) } else { basic_payload },
+			Jump(_) 			=> self.control_unit.iar = basic_payload,
+
+			JumpIfNegative(_) 	=> self.execute_conditional_jump(AccCondition::Negative, basic_payload),
+			JumpIfZero(_) 		=> self.execute_conditional_jump(AccCondition::Zero, basic_payload),
+			JumpIfEqual(_) 		=> self.execute_conditional_jump(AccCondition::Equal, basic_payload),
+
+			Halt 				=> self.control_unit.halt(),
+
+			Not =>
+			{
+				self.arithmetic_unit.x = self.arithmetic_unit.acc;
+				self.arithmetic_unit.perform_immediately(ALUOperation::Not);
+				self.arithmetic_unit.acc = self.arithmetic_unit.z;
+			},
+
+			RotateRight(_) 		=> self.execute_rotation(ALUOperation::RotateRight, extended_payload),
+			RotateLeft(_) 		=> self.execute_rotation(ALUOperation::RotateLeft, extended_payload),
+
+			SkipIfNegative 		=> self.execute_skip(AccCondition::Negative),
+			NoOperation 		=> (),
+
+			// Illegal instructions fault before they are executed (see "run_to_halt"):
+			Illegal(_) 			=> (),
+		}
+	}
+
+	// SKN increments IAR via the ALU (X, Y and Z are changed in any case), but only writes it back if ACC satisfies the condition:
+	fn execute_skip(&mut self, condition: AccCondition)
+	{
+		self.arithmetic_unit.x = self.control_unit.iar;
+		self.arithmetic_unit.y = self.arithmetic_unit.one;
+		self.arithmetic_unit.perform_immediately(ALUOperation::Add);
+
+		if condition.is_satisfied(self.arithmetic_unit.acc, self.arithmetic_unit.equal())
+		{
+			self.control_unit.iar = self.arithmetic_unit.z;
+		}
+	}
+
+	// JMN, JMZ and JME only jump if ACC (or the EQUAL flag) satisfies their condition:
+	fn execute_conditional_jump(&mut self, condition: AccCondition, address: Word)
+	{
+		if condition.is_satisfied(self.arithmetic_unit.acc, self.arithmetic_unit.equal())
+		{
+			self.control_unit.iar = address;
+		}
+	}
+
+	// RAR and RAL rotate ACC by the amount in the payload:
+	fn execute_rotation(&mut self, op: ALUOperation, amount: Word)
+	{
+		self.arithmetic_unit.x = self.arithmetic_unit.acc;
+		self.arithmetic_unit.y = amount;
+		self.arithmetic_unit.perform_immediately(op);
+		self.arithmetic_unit.acc = self.arithmetic_unit.z;
+	}
+
+	// ADD, AND, OR, XOR, EQL and SUB combine ACC with a value from memory:
+	fn execute_alu_with_memory(&mut self, op: ALUOperation, address: Word)
+	{
+		self.memory_unit.sar = address;
+		self.memory_unit.sir = self.memory_unit.read_immediately(address);
+
+		self.arithmetic_unit.x = self.arithmetic_unit.acc;
+		self.arithmetic_unit.y = self.memory_unit.sir;
+		self.arithmetic_unit.perform_immediately(op);
+		self.arithmetic_unit.acc = self.arithmetic_unit.z;
+	}
+
+	// CMP compares ACC with the word at the given address, but only Z and the EQUAL flag receive the result:
+	fn execute_comparison(&mut self, address: Word)
+	{
+		self.memory_unit.sar = address;
+		self.memory_unit.sir = self.memory_unit.read_immediately(address);
+
+		self.arithmetic_unit.x = self.arithmetic_unit.acc;
+		self.arithmetic_unit.y = self.memory_unit.sir;
+		self.arithmetic_unit.perform_immediately(ALUOperation::Compare);
+	}
+}
@@ -1,14 +1,70 @@
+use std::collections::HashSet;
 use crate::types::{*, Registers as Regs};
 use crate::unit::*;
-use crate::bus::Xfer as BusXfer;
+use crate::assembly::ObjectCode;
+use crate::bus::{BusValue, Xfer as BusXfer};
 use crate::microcycle::{self, Descriptor as MicrocycleDescriptor};
 
+mod evaluate;
+mod fault;
+mod interpreter;
+mod restart;
+mod run;
+mod snapshot;
+mod stats;
+mod trace;
+
+pub use evaluate::{FinalState, EvaluateError};
+pub use fault::Fault;
+pub use interpreter::RunError;
+pub use restart::NoProgramError;
+pub use run::{RunOutcome, CsvNumberFormat, Microcycles, Cycles};
+pub use snapshot::{RegisterSnapshot, MachineState, StateError};
+pub use stats::Stats;
+pub use trace::{InstructionTrace, InstructionHook, BusEvent};
+
 pub struct Mima
 {
 	// The units of the MiMA:
 	pub arithmetic_unit: ArithmeticUnit,
 	pub control_unit: ControlUnit,
 	pub memory_unit: MemoryUnit,
+
+	// In strict mode, consuming the result of pending ALU work faults the MiMA (a pending memory read always does):
+	pub strict: bool,
+
+	// If enabled, LDC sign-extends bit 27 of its constant (e. g. "ldc -1" loads 0xFFFFFFFF instead of 0x0FFFFFFF):
+	pub sign_extend_constants: bool,
+
+	// The fault that stopped the MiMA (if any):
+	fault: Option<Fault>,
+
+	// Instruction addresses to pause at (before the instruction is fetched):
+	breakpoints: HashSet<Word>,
+
+	// The entry point of the loaded program:
+	entry: Word,
+
+	// The linked image and entry point of the program that has been loaded last (to restart it):
+	loaded_program: Option<(Box<[Word]>, Word)>,
+
+	// The hook that is called after every executed instruction and the fetch address / ACC of the current instruction:
+	instruction_hook: Option<InstructionHook>,
+	trace_start: Option<(Word, Word)>,
+
+	// What traveled over the bus during the last microcycle (None if it had no transfer):
+	bus_value: Option<BusValue>,
+
+	// The recorded signals of the data path (None if bus tracing is disabled):
+	bus_trace: Option<Vec<BusEvent>>,
+}
+
+impl Default for Mima
+{
+	fn default() -> Self
+	{
+		Mima::new()
+	}
 }
 
 impl Mima
@@ -20,24 +76,133 @@ impl Mima
 			arithmetic_unit: ArithmeticUnit::new(),
 			control_unit: ControlUnit::new(),
 			memory_unit: MemoryUnit::new(),
+			strict: false,
+			sign_extend_constants: false,
+			fault: None,
+			breakpoints: HashSet::new(),
+			entry: Word(0),
+			loaded_program: None,
+			instruction_hook: None,
+			trace_start: None,
+			bus_value: None,
+			bus_trace: None,
+		}
+	}
+
+	// Load (and link) the object code into the memory unit and point IAR to its entry point.
+	// The first microcycle afterwards fetches the instruction at the entry point.
+	pub fn load_code<'oc>(&mut self, code: &'oc ObjectCode) -> Result<(), LinkError<'oc>>
+	{
+		self.memory_unit.load_code(code)?;
+
+		let image = self.memory_unit.linear_memory()[..code.raw_code.len()].into();
+		self.loaded_program = Some((image, code.entry));
+
+		self.entry = code.entry;
+		self.control_unit.iar = code.entry;
+
+		Ok(())
+	}
+
+	// Reset all three units to their initial state, but keep the loaded program.
+	// IAR points to the entry point again, so the program can be run another time.
+	// Breakpoints, strict mode and sign extension are configuration and survive the reset.
+	pub fn reset(&mut self)
+	{
+		self.arithmetic_unit.reset();
+		self.control_unit.reset();
+		self.memory_unit.reset();
+
+		self.control_unit.iar = self.entry;
+		self.fault = None;
+		self.trace_start = None;
+		self.bus_value = None;
+	}
+
+	// Clear the linear memory (all "Halt").
+	// Without a program, the entry point is 0 again.
+	pub fn reset_memory(&mut self)
+	{
+		self.memory_unit.reset_memory();
+		self.entry = Word(0);
+	}
+
+	// The entry point of the program that has been loaded last (0 if there is none):
+	pub fn entry(&self) -> Word
+	{
+		self.entry
+	}
+
+	pub fn bus_value(&self) -> Option<BusValue>
+	{
+		self.bus_value
+	}
+
+	pub fn fault(&self) -> Option<Fault>
+	{
+		self.fault
+	}
+
+	pub fn add_breakpoint(&mut self, addr: Word)
+	{
+		self.breakpoints.insert(addr);
+	}
+
+	pub fn remove_breakpoint(&mut self, addr: Word)
+	{
+		self.breakpoints.remove(&addr);
+	}
+
+	// Is the MiMA about to fetch an instruction from a breakpoint address?
+	// This is the case in microcycle 1 when IAR holds the address of the next instruction.
+	pub fn is_at_breakpoint(&self) -> bool
+	{
+		(self.control_unit.microcycle() == 1) && self.breakpoints.contains(&self.control_unit.iar)
+	}
+
+	// Read the value of a single register:
+	pub fn register(&self, reg: Regs) -> Word
+	{
+		match reg
+		{
+			Regs::ACC 	=> self.arithmetic_unit.acc,
+			Regs::ONE 	=> self.arithmetic_unit.one,
+			Regs::X 	=> self.arithmetic_unit.x,
+			Regs::Y 	=> self.arithmetic_unit.y,
+			Regs::Z 	=> self.arithmetic_unit.z,
+			Regs::IAR 	=> self.control_unit.iar,
+			Regs::IR 	=> self.control_unit.ir,
+			Regs::SAR 	=> self.memory_unit.sar,
+			Regs::SIR 	=> self.memory_unit.sir,
+			_ 			=> panic!("Expected exactly one register, got {:}.", reg),
 		}
 	}
 
 	// Perform a microcycle.
 	// Return the descriptor in the end to allow graphical output of the microcycle.
+	// Callbacks (devices, the instruction hook) cannot reenter it: They don't get access to the MiMA and the MiMA is borrowed mutably while they run.
 	pub fn perform_microcycle(&mut self) -> Option<MicrocycleDescriptor>
 	{
 		// Is the MiMA running?
 		// Otherwise, we don't do anything.
-		if !self.control_unit.is_running()
+		if !self.control_unit.is_running() || self.fault.is_some()
 		{
 			//TODO: Logging
 			return None
 		}
 
 		// First, let arithmetic and memory unit continue pending work:
-		self.arithmetic_unit.poll_work();
-		self.memory_unit.poll_work();
+		if let Err(fault) = self.arithmetic_unit.poll_work()
+		{
+			self.fault_alu(fault);
+			return None
+		}
+
+		if let Err(fault) = self.memory_unit.poll_work()
+		{
+			self.fault_memory(fault);
+			return None
+		}
 
 		// Get the current microcycle index from the control unit:
 		let microcycle = self.control_unit.microcycle();
@@ -49,8 +214,40 @@ impl Mima
 								.map(|instruction| microcycle::execute_descriptor(microcycle, instruction))
 								.unwrap_or_else(|| microcycle::fetch_descriptor(microcycle));
 
+		// The fetch of an illegal instruction completes normally, but its execution faults right away:
+		if let (6, Some(Instruction::Illegal(word))) = (microcycle, self.control_unit.instruction())
+		{
+			self.fault_illegal_instruction(word);
+			return None
+		}
+
+		// Make sure that the descriptor does not consume results that are not ready yet:
+		if let Some(reg) = self.check_results_ready(&microcycle_desc)
+		{
+			self.fault_result_not_ready(reg);
+			return None
+		}
+
+		// A new instruction is fetched in microcycle 1:
+		if microcycle == 1
+		{
+			self.begin_instruction_trace();
+		}
+
 		self.process_microcycle_descriptor(&microcycle_desc);
 
+		// A fault while processing the descriptor stops the MiMA immediately:
+		if self.fault.is_some()
+		{
+			return None
+		}
+
+		// The execution of the instruction completes in microcycle 12 (the control unit drops it at the end of the microcycle):
+		if let (12, Some(instruction)) = (microcycle, self.control_unit.instruction())
+		{
+			self.finish_instruction_trace(instruction);
+		}
+
 		// The control unit ends the microcycle by manipulating the instruction and incrementing the counter.
 		self.control_unit.end_microcycle();
 
@@ -65,10 +262,7 @@ impl Mima
 	fn process_microcycle_descriptor(&mut self, microcycle_desc: &MicrocycleDescriptor)
 	{
 		// Is there a bus transfer?
-		if let Some(bus_xfer) = &microcycle_desc.bus_xfer
-		{
-			self.perform_bus_xfer(bus_xfer)
-		}
+		self.bus_value = microcycle_desc.bus_xfer.as_ref().map(|bus_xfer| self.perform_bus_xfer(bus_xfer));
 
 		// Do we have to signal the ALU?
 		if let Some(alu_op) = microcycle_desc.alu_op
@@ -83,12 +277,44 @@ impl Mima
 		}
 	}
 
-	fn perform_bus_xfer(&mut self, bus_xfer: &BusXfer)
+	// Z holds the result of ALU work, SIR the result of memory reads.
+	// A stale Z is only reported in strict mode, but SIR is never consumed while a read is in flight:
+	fn check_results_ready(&self, microcycle_desc: &MicrocycleDescriptor) -> Option<Regs>
+	{
+		let source = microcycle_desc.bus_xfer.as_ref()?.source();
+
+		let is_pending = match source
+		{
+			Regs::Z 	=> self.strict && self.arithmetic_unit.work().is_some(),
+			Regs::SIR 	=> self.memory_unit.is_read_pending(),
+			_ 			=> false,
+		};
+
+		if is_pending { Some(source) } else { None }
+	}
+
+	fn perform_bus_xfer(&mut self, bus_xfer: &BusXfer) -> BusValue
+	{
+		let value = self.transfer_on_bus(bus_xfer);
+
+		self.record_bus_event(BusEvent::Xfer
+		{
+			microcycle: self.control_unit.microcycle(),
+			source: bus_xfer.source(),
+			destinations: bus_xfer.destinations(),
+			mask: bus_xfer.source_bitmask(),
+			value,
+		});
+
+		value
+	}
+
+	fn transfer_on_bus(&mut self, bus_xfer: &BusXfer) -> BusValue
 	{
 		// Cancel accumulator-dependent bus transfers that are not satisfied:
-		if bus_xfer.is_acc_dependent() && ((self.arithmetic_unit.acc.0 & (1u32 << 31)) == 0)
+		if !bus_xfer.is_satisfied(self.arithmetic_unit.acc, self.arithmetic_unit.equal())
 		{
-			return;
+			return BusValue::Suppressed;
 		}
 
 		// Fetch the source and mask it accordingly:
@@ -102,12 +328,15 @@ impl Mima
 				Regs::IAR 	=> self.control_unit.iar,
 				Regs::IR 	=> self.control_unit.ir,
 				Regs::SIR 	=> self.memory_unit.sir,
-			_ 			=> panic!("Unexpected bus source"),
+				_ 			=> panic!("Unexpected bus source"),
 			}
 		).0);
 
+		// Signed immediates are only sign-extended on demand (the original MiMA zero-extends them):
+		let value = if self.sign_extend_constants && bus_xfer.is_sign_extending() { value.sign_extend(bus_xfer.source_bitmask()) } else { value };
+
 		// Write it to all indicated destinations:
-		for &dest in Regs::ALL_REGISTERS.iter().filter(|&&dest| bus_xfer.destinations().contains(dest))
+		for dest in bus_xfer.destinations().iter()
 		{
 			match dest
 			{
@@ -121,15 +350,70 @@ impl Mima
 				_ 			=> panic!("Unexpected bus destination"),
 			}
 		}
+
+		BusValue::Propagated(value)
 	}
 
 	fn perform_alu_signal(&mut self, alu_op: ALUOperation)
 	{
-		self.arithmetic_unit.signal_alu(alu_op);
+		self.record_bus_event(BusEvent::ALUSignal { microcycle: self.control_unit.microcycle(), op: alu_op });
+		if let Err(fault) = self.arithmetic_unit.signal_alu(alu_op)
+		{
+			self.fault_alu(fault);
+		}
+	}
+
+	// Halt the MiMA because a bus transfer would consume the stale content of the given register:
+	fn fault_result_not_ready(&mut self, reg: Regs)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::ResultNotReady(reg));
+	}
+
+	// Halt the MiMA because pending memory work accessed an address it must not access:
+	fn fault_memory(&mut self, fault: MemoryFault)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::Memory(fault));
+	}
+
+	// Halt the MiMA because the ALU does not know the operation of its work:
+	fn fault_alu(&mut self, fault: ALUFault)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::ALU(fault));
+	}
+
+	// Halt the MiMA because the instruction at SAR cannot be fetched:
+	fn fault_fetch_from_device_io(&mut self)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::FetchFromDeviceIO(self.memory_unit.sar));
+	}
+
+	// Halt the MiMA because the fetched word is not a valid instruction (IAR already points behind it):
+	fn fault_illegal_instruction(&mut self, word: Word)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::IllegalInstruction(Word(self.control_unit.iar.0.wrapping_sub(1)), word));
+	}
+
+	// Halt the MiMA because SAR holds an address beyond the address space:
+	fn fault_fetch_out_of_range(&mut self)
+	{
+		self.control_unit.halt();
+		self.fault = Some(Fault::FetchOutOfRange(self.memory_unit.sar));
 	}
 
 	fn perform_mem_signal(&mut self, mem_access: MemoryAccess)
 	{
+		// Operands always have a 28 bit address, but IAR may have been set to anything from outside:
+		if !ADDRESS_SPACE_RANGE.contains(&self.memory_unit.sar) && self.control_unit.instruction().is_none()
+		{
+			self.fault_fetch_out_of_range();
+			return;
+		}
+
 		// If the memory access will be I/O, we have to frame it with the TRA bit:
 		let is_xfer = match MemoryType::from_address(self.memory_unit.sar)
 		{
@@ -137,6 +421,15 @@ impl Mima
 			MemoryType::DeviceIO 	=> true
 		};
 
+		// Instructions can only be fetched from the linear memory (the control unit holds no instruction while fetching):
+		if is_xfer && self.control_unit.instruction().is_none()
+		{
+			self.fault_fetch_from_device_io();
+			return;
+		}
+
+		self.record_bus_event(BusEvent::MemorySignal { microcycle: self.control_unit.microcycle(), access: mem_access, address: self.memory_unit.sar });
+
 		if is_xfer
 		{
 			self.control_unit.start_xfer();
@@ -150,3 +443,272 @@ impl Mima
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+	use std::cell::{Cell, RefCell};
+	use std::rc::{Rc, Weak};
+	use super::*;
+
+	fn load(source: &str) -> Mima
+	{
+		let (code, _) = ObjectCode::assemble(source).unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		mima
+	}
+
+	#[test]
+	fn strict_mode_detects_late_alu_result()
+	{
+		// The fetch has the same time window as ADD, so the latency is raised after the fetch.
+		// With a latency of 2, ADD consumes Z in microcycle 12 before the result has arrived:
+		let mut mima = load("add 0x10\nhlt");
+		mima.strict = true;
+		mima.run_with_limit(5);
+		mima.arithmetic_unit.set_microcycles_per_op(Some(2));
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::ResultNotReady(Regs::Z)));
+		assert_eq!(mima.control_unit.microcycle(), 12);
+		assert!(!mima.control_unit.is_running());
+
+		// Without strict mode, the stale Z is consumed silently:
+		let mut mima = load("add 0x10\nhlt");
+		mima.run_with_limit(5);
+		mima.arithmetic_unit.set_microcycles_per_op(Some(2));
+
+		assert_eq!(mima.run_with_limit(7), RunOutcome::LimitReached);
+		assert_eq!(mima.fault(), None);
+	}
+
+	#[test]
+	fn start_directive_sets_entry()
+	{
+		let mut mima = load("START main\ndat 0xE0000000\ndat 0xE0000000\nmain: ldc 3\nhlt");
+		assert_eq!(mima.entry(), Word(2));
+		assert_eq!(mima.register(Regs::IAR), Word(2));
+
+		// The first instruction is fetched from "main":
+		let cycle = mima.step_instruction().unwrap();
+		assert_eq!(cycle.iar.initial_value(), Word(2));
+		assert!(matches!(cycle.instruction, Instruction::LoadConstant(Word(3))));
+		assert_eq!(cycle.entry, Word(2));
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Halted);
+		assert_eq!(mima.register(Regs::ACC), Word(3));
+	}
+
+	#[test]
+	fn program_runs_identically_after_reset()
+	{
+		let mut mima = load("ldc 3\nadd one\nstv 0x100\nnot\nhlt\none: dat 1");
+
+		let (first_outcome, first_run) = mima.run_capturing(1000);
+		mima.reset();
+		assert_eq!(mima.register(Regs::IAR), Word(0));
+		assert_eq!(mima.register(Regs::ACC), Word(0));
+		assert_eq!(mima.fault(), None);
+
+		let (second_outcome, second_run) = mima.run_capturing(1000);
+
+		assert_eq!(first_outcome, RunOutcome::Halted);
+		assert_eq!(second_outcome, first_outcome);
+		assert_eq!(second_run.len(), first_run.len());
+
+		for (first, second) in first_run.iter().zip(second_run.iter())
+		{
+			assert_eq!(first.microcycle, second.microcycle);
+			assert_eq!(first.iar.final_value(), second.iar.final_value());
+			assert_eq!(first.acc.final_value(), second.acc.final_value());
+		}
+
+		assert_eq!(mima.register(Regs::ACC), Word(!4));
+		assert_eq!(mima.memory_unit.linear_memory()[0x100], Word(4));
+	}
+
+	#[test]
+	fn operand_read_is_ready_in_microcycle_10()
+	{
+		let mut mima = load("add value\nhlt\nvalue: dat 42");
+
+		// The operand read is signaled in microcycle 6 and still in flight after microcycle 9:
+		mima.run_with_limit(9);
+		assert!(mima.memory_unit.is_read_pending());
+
+		// Microcycle 10 polls the result into SIR before transferring it to Y:
+		mima.perform_microcycle();
+		assert!(!mima.memory_unit.is_read_pending());
+		assert_eq!(mima.register(Regs::Y), Word(42));
+	}
+
+	#[test]
+	fn late_memory_result_faults()
+	{
+		// Raise the latency after the fetch, so the operand read of ADD completes one microcycle too late.
+		// This is detected without strict mode, too:
+		for strict in [false, true]
+		{
+			let mut mima = load("add value\nhlt\nvalue: dat 42");
+			mima.strict = strict;
+			mima.run_with_limit(5);
+			mima.memory_unit.set_microcycles_per_access(4);
+
+			assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::ResultNotReady(Regs::SIR)));
+			assert_eq!(mima.control_unit.microcycle(), 10);
+			assert!(!mima.control_unit.is_running());
+		}
+	}
+
+	#[test]
+	fn restored_foreign_alu_work_faults()
+	{
+		// Capture a custom operation that is in flight and restore it into a MiMA that does not know it:
+		let mut other = Mima::new();
+		let nand = other.arithmetic_unit.register_op("nand", |x, y| Word(!(x.0 & y.0)));
+		other.arithmetic_unit.signal_alu(nand).unwrap();
+		let snapshot = other.register_snapshot();
+		drop(other);
+
+		let mut mima = load("hlt");
+		mima.restore_registers(&snapshot);
+
+		// The work completes when it is polled at the start of microcycle 2:
+		assert!(mima.perform_microcycle().is_some());
+		assert!(mima.perform_microcycle().is_none());
+		assert_eq!(mima.fault(), Some(Fault::ALU(ALUFault::UnknownOperation(0))));
+		assert!(!mima.control_unit.is_running());
+	}
+
+	// A device that tries to get hold of the MiMA it is attached to while it is read:
+	struct ReentrantDevice
+	{
+		mima: Weak<RefCell<Mima>>,
+		reentered: Rc<Cell<Option<bool>>>,
+	}
+
+	impl Device for ReentrantDevice
+	{
+		fn read(&mut self, _offset: Word) -> Word
+		{
+			let mima = self.mima.upgrade().unwrap();
+			self.reentered.set(Some(mima.try_borrow_mut().is_ok()));
+
+			Word(0)
+		}
+
+		fn write(&mut self, _offset: Word, _value: Word)
+		{
+		}
+	}
+
+	#[test]
+	fn devices_cannot_reenter_the_mima()
+	{
+		let mima = Rc::new(RefCell::new(load(&format!("ldv {:}\nhlt", DEVICE_IO_ADDRESS_SPACE_RANGE.start))));
+		let reentered = Rc::new(Cell::new(None));
+
+		let device = ReentrantDevice { mima: Rc::downgrade(&mima), reentered: Rc::clone(&reentered) };
+		let range = DEVICE_IO_ADDRESS_SPACE_RANGE.start..Word(DEVICE_IO_ADDRESS_SPACE_RANGE.start.0 + 1);
+		mima.borrow_mut().memory_unit.attach_device(range, Box::new(device));
+
+		// The device is read while the MiMA is borrowed for the microcycle, so it cannot drive it:
+		assert_eq!(mima.borrow_mut().run_with_limit(100), RunOutcome::Halted);
+		assert_eq!(reentered.get(), Some(false));
+	}
+
+	#[test]
+	fn jump_into_device_region_faults()
+	{
+		let target = Word(DEVICE_IO_ADDRESS_SPACE_RANGE.start.0 + 0x10);
+		let mut mima = load(&format!("jmp {:}", target));
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::FetchFromDeviceIO(target)));
+		assert!(!mima.control_unit.is_running());
+	}
+
+	#[test]
+	fn store_into_protected_memory_faults()
+	{
+		// Loading code ignores the protection:
+		let mut mima = Mima::new();
+		mima.memory_unit.protect(Word(2)..Word(3));
+		let (code, _) = ObjectCode::assemble("ldc 7\nstv rom\nrom: dat 3").unwrap();
+		mima.load_code(&code).unwrap();
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::Memory(MemoryFault::WriteProtected(Word(2)))));
+		assert!(!mima.control_unit.is_running());
+		assert_eq!(mima.memory_unit.linear_memory()[2], Word(3));
+	}
+
+	// Put "ldc 5" into the last word of the linear memory and start there:
+	fn load_at_end_of_linear_memory() -> Mima
+	{
+		let last = Word(LINEAR_ADDRESS_SPACE_RANGE.end.0 - 1);
+		let mut mima = Mima::new();
+		mima.memory_unit.load_cells(&[(last, Word::from(Instruction::LoadConstant(Word(5))))]).unwrap();
+		mima.control_unit.iar = last;
+
+		mima
+	}
+
+	#[test]
+	fn iar_runs_into_device_region()
+	{
+		// IAR is not wrapped: The next fetch after the last linear word faults in the device I/O address space.
+		let device_start = DEVICE_IO_ADDRESS_SPACE_RANGE.start;
+
+		let mut mima = load_at_end_of_linear_memory();
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::FetchFromDeviceIO(device_start)));
+		assert_eq!(mima.register(Regs::ACC), Word(5));
+		assert_eq!(mima.register(Regs::IAR), device_start);
+		drop(mima);
+
+		// The fast interpreter behaves the same:
+		let mut mima = load_at_end_of_linear_memory();
+		assert!(matches!(mima.run_to_halt(100), Err(RunError::Faulted(Fault::FetchFromDeviceIO(addr))) if addr == device_start));
+		assert_eq!(mima.register(Regs::ACC), Word(5));
+	}
+
+	#[test]
+	fn iar_beyond_address_space_faults()
+	{
+		// IAR can only get there if it is set from outside:
+		let mut mima = load("hlt");
+		mima.control_unit.iar = Word(0xFFFFFFFF);
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::FetchOutOfRange(Word(0xFFFFFFFF))));
+		assert_eq!(mima.control_unit.microcycle(), 1);
+		assert!(!mima.control_unit.is_running());
+
+		mima.reset();
+		mima.control_unit.iar = Word(0xFFFFFFFF);
+		assert!(matches!(mima.run_to_halt(100), Err(RunError::Faulted(Fault::FetchOutOfRange(Word(0xFFFFFFFF))))));
+	}
+
+	#[test]
+	fn interpreter_follows_non_default_latencies()
+	{
+		// Both engines have to agree (the latencies survive the reset):
+		let mut mima = load("add value\nhlt\nvalue: dat 42");
+		mima.memory_unit.set_microcycles_per_access(4);
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::ResultNotReady(Regs::SIR)));
+		let acc = mima.register(Regs::ACC);
+
+		mima.reset();
+		assert!(matches!(mima.run_to_halt(100), Err(RunError::Faulted(Fault::ResultNotReady(Regs::SIR)))));
+		assert_eq!(mima.register(Regs::ACC), acc);
+
+		// The same holds for a slow ALU in strict mode:
+		mima.memory_unit.set_microcycles_per_access(DEFAULT_MICROCYCLES_PER_ACCESS);
+		mima.arithmetic_unit.set_microcycles_per_op(Some(3));
+		mima.strict = true;
+		mima.reset();
+
+		assert_eq!(mima.run_with_limit(100), RunOutcome::Faulted(Fault::ResultNotReady(Regs::Z)));
+
+		mima.reset();
+		assert!(matches!(mima.run_to_halt(100), Err(RunError::Faulted(Fault::ResultNotReady(Regs::Z)))));
+	}
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fmt;
+use super::Mima;
+
+// This error occurs when restarting a MiMA that has never loaded a program:
+#[derive(Debug)]
+pub struct NoProgramError;
+
+impl fmt::Display for NoProgramError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write!(f, "There is no program to restart.")
+	}
+}
+
+impl Error for NoProgramError { }
+
+impl Mima
+{
+	// Reset the MiMA and restore the image of the program that has been loaded last.
+	// In contrast to "reset", memory modifications of the previous run are undone.
+	// The attached devices are kept (and the program is not linked again).
+	pub fn restart(&mut self) -> Result<(), NoProgramError>
+	{
+		let (image, entry) = self.loaded_program.as_ref().ok_or(NoProgramError)?;
+
+		self.memory_unit.reset_memory();
+		self.memory_unit.load_raw_code(image).expect("The loaded program must fit into the linear memory.");
+		self.entry = *entry;
+
+		self.reset();
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::types::{Word, Instruction, Registers as Regs};
+	use crate::assembly::ObjectCode;
+
+	#[test]
+	fn restart_restores_image_and_registers()
+	{
+		let (code, _) = ObjectCode::assemble("START main\nvalue: dat 7\nmain: ldc 1\nstv value\nstv 0x100\nhlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+		mima.run_with_limit(1000);
+
+		assert_eq!(mima.memory_unit.linear_memory()[0], Word(1));
+		assert_eq!(mima.register(Regs::ACC), Word(1));
+
+		mima.restart().unwrap();
+
+		assert_eq!(&mima.memory_unit.linear_memory()[..code.raw_code.len()], &*code.raw_code);
+		assert_eq!(mima.memory_unit.linear_memory()[0x100], Instruction::Halt.into());
+		assert_eq!(mima.register(Regs::ACC), Word(0));
+		assert_eq!(mima.register(Regs::IAR), Word(1));
+		assert_eq!(mima.control_unit.microcycle(), 1);
+		assert!(mima.control_unit.is_running());
+	}
+
+	#[test]
+	fn restart_without_program_fails()
+	{
+		assert!(Mima::new().restart().is_err());
+	}
+}
@@ -0,0 +1,298 @@
+use std::io::{self, Write};
+use crate::types::*;
+use crate::record::{MicrocycleSummary, CycleSummary};
+use super::{Mima, Fault};
+
+// The outcome of running the MiMA with a limit:
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RunOutcome
+{
+	// The MiMA executed HLT.
+	Halted,
+
+	// The limit has been reached while the MiMA was still running.
+	LimitReached,
+
+	// The MiMA has been stopped by a fault.
+	Faulted(Fault),
+
+	// The MiMA is about to fetch the instruction at a breakpoint address.
+	BreakpointHit(Word),
+}
+
+// The notation of register values in CSV exports:
+#[derive(Copy, Clone, PartialEq)]
+pub enum CsvNumberFormat
+{
+	Hexadecimal,
+	SignedDecimal,
+}
+
+impl CsvNumberFormat
+{
+	fn format(self, word: Word) -> String
+	{
+		match self
+		{
+			CsvNumberFormat::Hexadecimal 	=> format!("{:}", word),
+			CsvNumberFormat::SignedDecimal 	=> word.to_signed_string(),
+		}
+	}
+}
+
+// An iterator that performs one microcycle per item and records its summary (see "Mima::microcycles").
+// It ends as soon as the MiMA is stopped (by HLT or a fault), so the pace is entirely up to the caller.
+pub struct Microcycles<'m>
+{
+	mima: &'m mut Mima,
+}
+
+impl<'m> Microcycles<'m>
+{
+	// Inspect the MiMA between two microcycles (e. g. to draw its memory):
+	pub fn mima(&self) -> &Mima
+	{
+		self.mima
+	}
+}
+
+impl<'m> Iterator for Microcycles<'m>
+{
+	type Item = MicrocycleSummary;
+
+	fn next(&mut self) -> Option<MicrocycleSummary>
+	{
+		MicrocycleSummary::record_microcycle(self.mima)
+	}
+}
+
+// An iterator that groups the microcycles into instructions and yields a cycle summary for each of them (see "Mima::cycles").
+// If the MiMA stops in the middle of an instruction (e. g. because of a fault), the last summary covers the partial instruction.
+// It is omitted if the MiMA stops before the instruction has been decoded.
+pub struct Cycles<'m>
+{
+	microcycles: Microcycles<'m>,
+}
+
+impl<'m> Cycles<'m>
+{
+	// Inspect the MiMA between two instructions:
+	pub fn mima(&self) -> &Mima
+	{
+		self.microcycles.mima()
+	}
+}
+
+impl<'m> Iterator for Cycles<'m>
+{
+	type Item = CycleSummary;
+
+	fn next(&mut self) -> Option<CycleSummary>
+	{
+		// Record the first microcycle (None if the MiMA is stopped):
+		let start = self.microcycles.next()?;
+		let mut end = None;
+
+		// Run until microcycle 12 has been performed or the MiMA stops:
+		let mut microcycle = start.microcycle;
+
+		while microcycle != 12
+		{
+			match self.microcycles.next()
+			{
+				Some(summary) 	=>
+				{
+					microcycle = summary.microcycle;
+					end = Some(summary);
+				},
+				None 			=> break,
+			}
+		}
+
+		let end = end.as_ref().unwrap_or(&start);
+		end.instruction?;
+
+		Some(CycleSummary::from_span(&start, end))
+	}
+}
+
+impl Mima
+{
+	// Perform the microcycles on demand (e. g. to animate them or to replay them at a variable speed):
+	pub fn microcycles(&mut self) -> Microcycles<'_>
+	{
+		Microcycles
+		{
+			mima: self,
+		}
+	}
+
+	// Perform the instructions on demand (the cycle summaries hide the microcycles 1 and 12 that delimit them):
+	pub fn cycles(&mut self) -> Cycles<'_>
+	{
+		Cycles
+		{
+			microcycles: self.microcycles(),
+		}
+	}
+
+	// Run the MiMA until the current instruction has been fetched and executed completely and summarize it.
+	// Returns None if the MiMA is stopped or stops before the instruction is complete.
+	// If the MiMA is in the middle of an instruction, the summary covers the rest of it.
+	pub fn step_instruction(&mut self) -> Option<CycleSummary>
+	{
+		// Record the first microcycle (None if the MiMA is stopped):
+		let start = MicrocycleSummary::record_microcycle(self)?;
+		let mut end = None;
+
+		// Run until microcycle 12 has been performed.
+		// If the MiMA stops before (e. g. because of a fault), the instruction is incomplete.
+		let mut microcycle = start.microcycle;
+
+		while microcycle != 12
+		{
+			let summary = MicrocycleSummary::record_microcycle(self)?;
+			microcycle = summary.microcycle;
+			end = Some(summary);
+		}
+
+		Some(CycleSummary::from_span(&start, end.as_ref().unwrap_or(&start)))
+	}
+
+	// Run the MiMA for at most "max" microcycles and record a summary for every performed microcycle.
+	// This is the programmatic equivalent of the animated demo.
+	pub fn run_capturing(&mut self, max: usize) -> (RunOutcome, Vec<MicrocycleSummary>)
+	{
+		let summaries = self.microcycles().take(max).collect();
+		(self.run_outcome(), summaries)
+	}
+
+	// Run the MiMA for at most "max" microcycles and write one CSV row per performed microcycle (register values in hex).
+	pub fn run_to_csv(&mut self, max: usize, w: &mut impl Write) -> io::Result<RunOutcome>
+	{
+		self.run_to_csv_with_format(max, w, CsvNumberFormat::Hexadecimal)
+	}
+
+	// The rows hold the index of the microcycle and the register / flag values at its end:
+	pub fn run_to_csv_with_format(&mut self, max: usize, w: &mut impl Write, number_format: CsvNumberFormat) -> io::Result<RunOutcome>
+	{
+		writeln!(w, "microcycle,IAR,ACC,X,Y,Z,SAR,SIR,RUN,TRA")?;
+
+		for summary in self.microcycles().take(max)
+		{
+			let registers = [summary.iar, summary.acc, summary.x, summary.y, summary.z, summary.sar, summary.sir];
+			let columns: Vec<_> = registers.iter().map(|reg| number_format.format(reg.final_value())).collect();
+
+			writeln!(w, "{:},{:},{:},{:}", summary.microcycle, columns.join(","),
+				summary.run.final_value().0 as u8, summary.tra.final_value().0 as u8)?;
+		}
+
+		Ok(self.run_outcome())
+	}
+
+	// Run the MiMA until it halts or faults, but perform at most "max_microcycles" microcycles (e. g. to stop programs without HLT).
+	// The limit is enforced via the microcycle counter of the control unit (see end_microcycle).
+	pub fn run_with_limit(&mut self, max_microcycles: usize) -> RunOutcome
+	{
+		let limit = self.control_unit.total_microcycles() + (max_microcycles as u64);
+
+		while self.control_unit.total_microcycles() < limit
+		{
+			if self.perform_microcycle().is_none()
+			{
+				break;
+			}
+		}
+
+		self.run_outcome()
+	}
+
+	// Run the MiMA for at most "max" microcycles or until it reaches a breakpoint.
+	// A breakpoint at the current IAR does not stop the run immediately (this allows to continue after a hit).
+	pub fn run_to_breakpoint(&mut self, max: usize) -> RunOutcome
+	{
+		for performed in 0..max
+		{
+			if (performed > 0) && self.is_at_breakpoint()
+			{
+				return RunOutcome::BreakpointHit(self.control_unit.iar);
+			}
+
+			if self.perform_microcycle().is_none()
+			{
+				break;
+			}
+		}
+
+		self.run_outcome()
+	}
+
+	// Determine the outcome after a run has stopped.
+	// If the MiMA is still running (and not faulted), we hit the limit.
+	fn run_outcome(&self) -> RunOutcome
+	{
+		match self.fault
+		{
+			Some(fault) 								=> RunOutcome::Faulted(fault),
+			None if self.control_unit.is_running() 		=> RunOutcome::LimitReached,
+			None 										=> RunOutcome::Halted,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::assembly::ObjectCode;
+
+	fn load(source: &str) -> Mima
+	{
+		let (code, _) = ObjectCode::assemble(source).unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		mima
+	}
+
+	#[test]
+	fn run_capturing_records_every_microcycle()
+	{
+		let mut mima = load("ldc 1\nstv 0x10\nnot\nhlt");
+		let (outcome, summaries) = mima.run_capturing(1000);
+
+		assert_eq!(outcome, RunOutcome::Halted);
+		assert_eq!(summaries.len(), 4 * 12);
+		assert_eq!(summaries.iter().filter(|summary| summary.microcycle == 12).count(), 4);
+	}
+
+	#[test]
+	fn csv_has_header_and_one_row_per_microcycle()
+	{
+		let mut mima = load("ldc 1\nnot\nhlt");
+		let mut csv = vec![];
+
+		let outcome = mima.run_to_csv(1000, &mut csv).unwrap();
+		let csv = String::from_utf8(csv).unwrap();
+		let rows: Vec<&str> = csv.lines().collect();
+
+		assert_eq!(outcome, RunOutcome::Halted);
+		assert_eq!(rows[0], "microcycle,IAR,ACC,X,Y,Z,SAR,SIR,RUN,TRA");
+		assert_eq!(rows.len() - 1, mima.control_unit.total_microcycles() as usize);
+		assert_eq!(rows.len() - 1, 3 * 12);
+		assert!(rows[1].starts_with("1,"));
+	}
+
+	#[test]
+	fn csv_in_signed_decimal()
+	{
+		let mut mima = load("ldc 1\nnot\nhlt");
+		let mut csv = vec![];
+
+		mima.run_to_csv_with_format(24, &mut csv, CsvNumberFormat::SignedDecimal).unwrap();
+		let csv = String::from_utf8(csv).unwrap();
+
+		// After NOT, ACC holds -2:
+		assert_eq!(csv.lines().last().unwrap().split(',').nth(2), Some("-2"));
+	}
+}
@@ -0,0 +1,354 @@
+use std::error::Error;
+use std::fmt;
+use crate::types::*;
+use crate::unit::*;
+use super::{Mima, Fault};
+
+// A lightweight snapshot of the register file.
+// In contrast to the memory, it is cheap to capture (e.g. after every microcycle).
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegisterSnapshot
+{
+	// Arithmetic unit:
+	pub acc: Word,
+	pub one: Word,
+	pub x: Word,
+	pub y: Word,
+	pub z: Word,
+	pub carry: Flag,
+	pub overflow: Flag,
+	pub equal: Flag,
+
+	// Control unit:
+	pub iar: Word,
+	pub ir: Word,
+	pub status: ControlStatus,
+	pub microcycle: u8,
+	pub instruction: Option<Instruction>,
+
+	// Memory unit:
+	pub sar: Word,
+	pub sir: Word,
+
+	// Pending work:
+	pub alu_work: Option<ALUWork>,
+	pub memory_work: Option<MemoryWork>,
+}
+
+// The full state of the machine (registers and linear memory).
+// With the "serde" feature, it can be written to disk and restored later to resume the MiMA.
+// The linear memory is huge, so only the cells that do not hold "Halt" are stored (as address / value pairs).
+// The fault (if any) and the modes that change how microcycles are performed are part of it, too.
+// Attached devices and device labels are not part of the state.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone)]
+pub struct MachineState
+{
+	pub registers: RegisterSnapshot,
+	pub linear_memory: Vec<(Word, Word)>,
+	pub entry: Word,
+	pub fault: Option<Fault>,
+	pub strict: bool,
+	pub sign_extend_constants: bool,
+}
+
+// This error occurs when a machine state cannot be restored (e. g. because it has been deserialized from a broken file).
+// The microcycle variants hold the offending microcycle counter.
+#[derive(Debug)]
+pub enum StateError
+{
+	Load(LoadError),
+	BadMicrocycle(u8),
+	MissingInstruction(u8),
+	UnexpectedInstruction(u8),
+}
+
+impl fmt::Display for StateError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			StateError::Load(err) 							=> write!(f, "{:}", err),
+			StateError::BadMicrocycle(microcycle) 			=> write!(f, "The microcycle counter {:} is not in [1, 12].", microcycle),
+			StateError::MissingInstruction(microcycle) 		=> write!(f, "There is no instruction to execute in microcycle {:}.", microcycle),
+			StateError::UnexpectedInstruction(microcycle) 	=> write!(f, "There is an instruction although microcycle {:} is part of the fetch.", microcycle),
+		}
+	}
+}
+
+impl Error for StateError { }
+
+impl From<LoadError> for StateError
+{
+	fn from(err: LoadError) -> StateError
+	{
+		StateError::Load(err)
+	}
+}
+
+impl Mima
+{
+	// Capture the registers (but not the memory):
+	pub fn register_snapshot(&self) -> RegisterSnapshot
+	{
+		RegisterSnapshot
+		{
+			acc: self.arithmetic_unit.acc,
+			one: self.arithmetic_unit.one,
+			x: self.arithmetic_unit.x,
+			y: self.arithmetic_unit.y,
+			z: self.arithmetic_unit.z,
+			carry: self.arithmetic_unit.carry(),
+			overflow: self.arithmetic_unit.overflow(),
+			equal: self.arithmetic_unit.equal(),
+
+			iar: self.control_unit.iar,
+			ir: self.control_unit.ir,
+			status: *self.control_unit.status(),
+			microcycle: self.control_unit.microcycle(),
+			instruction: self.control_unit.instruction(),
+
+			sar: self.memory_unit.sar,
+			sir: self.memory_unit.sir,
+
+			alu_work: self.arithmetic_unit.work().copied(),
+			memory_work: self.memory_unit.work().copied(),
+		}
+	}
+
+	// Restore the registers from a snapshot.
+	// The memory is left untouched.
+	pub fn restore_registers(&mut self, snapshot: &RegisterSnapshot)
+	{
+		self.arithmetic_unit.acc = snapshot.acc;
+		self.arithmetic_unit.one = snapshot.one;
+		self.arithmetic_unit.x = snapshot.x;
+		self.arithmetic_unit.y = snapshot.y;
+		self.arithmetic_unit.z = snapshot.z;
+		self.arithmetic_unit.restore_flags(snapshot.carry, snapshot.overflow, snapshot.equal);
+		self.arithmetic_unit.restore_work(snapshot.alu_work);
+
+		self.control_unit.iar = snapshot.iar;
+		self.control_unit.ir = snapshot.ir;
+		self.control_unit.restore_state(snapshot.status, snapshot.microcycle, snapshot.instruction);
+
+		self.memory_unit.sar = snapshot.sar;
+		self.memory_unit.sir = snapshot.sir;
+		self.memory_unit.restore_work(snapshot.memory_work);
+
+		// The fetch of the current instruction (if any) has not been observed:
+		self.trace_start = None;
+	}
+
+	// Capture the full machine state (including pending work and the linear memory):
+	pub fn save_state(&self) -> MachineState
+	{
+		MachineState
+		{
+			registers: self.register_snapshot(),
+			linear_memory: self.memory_unit.linear_memory().iter()
+				.enumerate()
+				.filter(|&(_, &word)| word != Instruction::Halt.into())
+				.map(|(addr, &word)| (Word(addr as u32), word))
+				.collect(),
+			entry: self.entry,
+			fault: self.fault,
+			strict: self.strict,
+			sign_extend_constants: self.sign_extend_constants,
+		}
+	}
+
+	// Restore the full machine state.
+	// All memory cells must be located inside the linear address space.
+	// The state is checked before anything is changed: The microcycle counter must be in [1, 12], and there must be an instruction exactly in the execute phase.
+	pub fn load_state(&mut self, state: &MachineState) -> Result<(), StateError>
+	{
+		let microcycle = state.registers.microcycle;
+
+		match (microcycle, state.registers.instruction)
+		{
+			(1..=5, None) | (6..=12, Some(_)) 	=> (),
+			(1..=5, Some(_)) 					=> return Err(StateError::UnexpectedInstruction(microcycle)),
+			(6..=12, None) 						=> return Err(StateError::MissingInstruction(microcycle)),
+			_ 									=> return Err(StateError::BadMicrocycle(microcycle)),
+		}
+
+		self.memory_unit.load_cells(&state.linear_memory)?;
+		self.restore_registers(&state.registers);
+		self.entry = state.entry;
+		self.fault = state.fault;
+		self.strict = state.strict;
+		self.sign_extend_constants = state.sign_extend_constants;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::types::Registers as Regs;
+	use crate::assembly::ObjectCode;
+
+	fn run_microcycles(mima: &mut Mima, count: usize)
+	{
+		for _ in 0..count
+		{
+			mima.perform_microcycle();
+		}
+	}
+
+	#[test]
+	fn restore_registers_leaves_memory_untouched()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 5\nstv 0x100\nldc 7\nhlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		// Capture the registers after "ldc 5", then store and load another constant:
+		run_microcycles(&mut mima, 12);
+		let snapshot = mima.register_snapshot();
+		run_microcycles(&mut mima, 24);
+
+		assert_eq!(mima.register(Regs::ACC), Word(7));
+		assert_eq!(mima.register(Regs::IAR), Word(3));
+		assert_eq!(mima.memory_unit.linear_memory()[0x100], Word(5));
+
+		// Restoring rewinds the registers, but keeps the stored value:
+		mima.restore_registers(&snapshot);
+
+		assert_eq!(mima.register(Regs::ACC), Word(5));
+		assert_eq!(mima.register(Regs::IAR), Word(1));
+		assert_eq!(mima.control_unit.microcycle(), snapshot.microcycle);
+		assert_eq!(mima.memory_unit.linear_memory()[0x100], Word(5));
+	}
+
+	#[test]
+	fn load_state_restores_fault_and_modes()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 5\nstv 0x100\ndat 0xE0000000").unwrap();
+		let mut faulted = Mima::new();
+		faulted.strict = true;
+		faulted.sign_extend_constants = true;
+		faulted.load_code(&code).unwrap();
+		faulted.run_with_limit(100);
+
+		let fault = faulted.fault();
+		assert!(matches!(fault, Some(Fault::IllegalInstruction(Word(2), _))));
+
+		// Loading the state into another MiMA takes the fault and the modes along:
+		let state = faulted.save_state();
+		let mut mima = Mima::new();
+		mima.load_state(&state).unwrap();
+
+		assert_eq!(mima.fault(), fault);
+		assert!(mima.strict && mima.sign_extend_constants);
+		assert_eq!(mima.register(Regs::ACC), Word(5));
+		assert_eq!(mima.memory_unit.linear_memory()[0x100], Word(5));
+
+		// A state without fault clears it:
+		let clean = MachineState { fault: None, strict: false, sign_extend_constants: false, ..state };
+		faulted.load_state(&clean).unwrap();
+
+		assert_eq!(faulted.fault(), None);
+		assert!(!faulted.strict && !faulted.sign_extend_constants);
+	}
+
+	// A MiMA in the middle of "ldc 5" (in microcycle 8):
+	fn mima_in_ldc() -> Mima
+	{
+		let (code, _) = ObjectCode::assemble("ldc 5\nhlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+		run_microcycles(&mut mima, 7);
+
+		mima
+	}
+
+	// Copy the state and break its registers with the given function:
+	fn broken_state(state: &MachineState, f: impl FnOnce(&mut RegisterSnapshot)) -> MachineState
+	{
+		let mut broken = state.clone();
+		f(&mut broken.registers);
+
+		broken
+	}
+
+	// Make sure that the MiMA is still in the middle of "ldc 5":
+	fn assert_in_ldc(mima: &Mima)
+	{
+		assert_eq!(mima.control_unit.microcycle(), 8);
+		assert!(matches!(mima.control_unit.instruction(), Some(Instruction::LoadConstant(Word(5)))));
+		assert_eq!(mima.memory_unit.linear_memory()[0], Word::from(Instruction::LoadConstant(Word(5))));
+	}
+
+	#[test]
+	fn load_state_rejects_bad_microcycles()
+	{
+		let mut mima = mima_in_ldc();
+		let state = mima.save_state();
+
+		let broken = broken_state(&state, |registers| registers.microcycle = 13);
+		assert!(matches!(mima.load_state(&broken), Err(StateError::BadMicrocycle(13))));
+
+		let broken = broken_state(&state, |registers| registers.microcycle = 0);
+		assert!(matches!(mima.load_state(&broken), Err(StateError::BadMicrocycle(0))));
+
+		// Nothing has been changed:
+		assert_in_ldc(&mima);
+	}
+
+	#[test]
+	fn load_state_rejects_missing_instructions()
+	{
+		let mut mima = mima_in_ldc();
+		let state = mima.save_state();
+
+		let broken = broken_state(&state, |registers| registers.instruction = None);
+		assert!(matches!(mima.load_state(&broken), Err(StateError::MissingInstruction(8))));
+
+		let broken = broken_state(&state, |registers| registers.microcycle = 2);
+		assert!(matches!(mima.load_state(&broken), Err(StateError::UnexpectedInstruction(2))));
+
+		// Nothing has been changed:
+		assert_in_ldc(&mima);
+
+		// The intact state is accepted:
+		mima.load_state(&state).unwrap();
+		assert_in_ldc(&mima);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn state_survives_serialization()
+	{
+		use crate::mima::RunOutcome;
+
+		let (code, _) = ObjectCode::assemble("ldc 5\nstv 0x100\nadd 0x100\nstv 0x101\nhlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		// Save the state in the middle of the first store (while the memory is busy):
+		run_microcycles(&mut mima, 20);
+		assert!(mima.memory_unit.work().is_some());
+		let json = serde_json::to_string(&mima.save_state()).unwrap();
+
+		// Let the original run to its end:
+		assert_eq!(mima.run_with_limit(1000), RunOutcome::Halted);
+		let registers = mima.register_snapshot();
+		let memory = mima.save_state().linear_memory;
+
+		// Resume the saved state and compare:
+		let state: MachineState = serde_json::from_str(&json).unwrap();
+		mima.load_state(&state).unwrap();
+		assert_eq!(mima.run_with_limit(1000), RunOutcome::Halted);
+
+		let resumed = mima.register_snapshot();
+		assert_eq!((resumed.acc, resumed.iar, resumed.ir, resumed.sar, resumed.sir, resumed.z), (registers.acc, registers.iar, registers.ir, registers.sar, registers.sir, registers.z));
+		assert_eq!(mima.save_state().linear_memory, memory);
+		assert_eq!(mima.memory_unit.linear_memory()[0x101], Word(10));
+	}
+}
@@ -0,0 +1,78 @@
+use super::Mima;
+
+// Performance counters of a MiMA (since its construction or the last reset):
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stats
+{
+	pub total_microcycles: u64,
+	pub total_instructions: u64,
+}
+
+impl Stats
+{
+	// The average number of microcycles per completed instruction (None if there is none yet).
+	// Partially executed instructions count as microcycles, but not as instructions.
+	pub fn microcycles_per_instruction(&self) -> Option<f64>
+	{
+		if self.total_instructions == 0
+		{
+			None
+		}
+		else
+		{
+			Some((self.total_microcycles as f64) / (self.total_instructions as f64))
+		}
+	}
+}
+
+impl Mima
+{
+	pub fn stats(&self) -> Stats
+	{
+		Stats
+		{
+			total_microcycles: self.control_unit.total_microcycles(),
+			total_instructions: self.control_unit.total_instructions(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::assembly::ObjectCode;
+
+	// Three full iterations (5 instructions each), the final check (3 instructions) and HLT:
+	const LOOP: &str = "START loop\ncount: dat 3\ndecr: dat -1\nloop: ldv count\nadd decr\njmn out\nstv count\njmp loop\nout: hlt";
+	const LOOP_INSTRUCTIONS: u64 = (3 * 5) + 3 + 1;
+
+	fn load_loop() -> Mima
+	{
+		let (code, _) = ObjectCode::assemble(LOOP).unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		mima
+	}
+
+	#[test]
+	fn count_loop()
+	{
+		let mut mima = load_loop();
+		assert!(mima.stats().microcycles_per_instruction().is_none());
+
+		mima.run_with_limit(10_000);
+
+		let stats = mima.stats();
+		assert_eq!(stats, Stats { total_microcycles: LOOP_INSTRUCTIONS * 12, total_instructions: LOOP_INSTRUCTIONS });
+		assert_eq!(stats.microcycles_per_instruction(), Some(12.0));
+
+		// The fast interpreter counts the same:
+		mima.restart().unwrap();
+		assert_eq!(mima.stats(), Stats { total_microcycles: 0, total_instructions: 0 });
+
+		mima.run_to_halt(1000).unwrap();
+		assert_eq!(mima.stats(), stats);
+	}
+}
@@ -0,0 +1,92 @@
+use crate::types::*;
+use crate::unit::*;
+use crate::bus::BusValue;
+use super::Mima;
+
+// A record of a single executed instruction:
+// The decoded instruction, the address it has been fetched from and the accumulator before and after its execution.
+#[derive(Copy, Clone)]
+pub struct InstructionTrace
+{
+	pub instruction: Instruction,
+	pub address: Word,
+	pub acc_before: Word,
+	pub acc_after: Word,
+}
+
+// The hook is called after every executed instruction.
+// It only sees the trace (not the MiMA), so it cannot drive the machine from within a microcycle.
+pub type InstructionHook = Box<dyn FnMut(&InstructionTrace)>;
+
+// A signal on the data path during a microcycle (below the instruction boundary).
+// Every event carries the index of the microcycle it has been signaled in:
+#[derive(Copy, Clone)]
+pub enum BusEvent
+{
+	// A bus transfer with its (masked) value, or "Suppressed" if its accumulator condition did not hold:
+	Xfer { microcycle: u8, source: Registers, destinations: Registers, mask: Word, value: BusValue },
+
+	// A new operation has been signaled to the ALU:
+	ALUSignal { microcycle: u8, op: ALUOperation },
+
+	// A memory access has been signaled (SAR holds the address):
+	MemorySignal { microcycle: u8, access: MemoryAccess, address: Word },
+}
+
+impl Mima
+{
+	pub fn set_instruction_hook(&mut self, hook: InstructionHook)
+	{
+		self.instruction_hook = Some(hook);
+	}
+
+	pub fn clear_instruction_hook(&mut self)
+	{
+		self.instruction_hook = None;
+	}
+
+	// The bus trace is opt-in: While it is enabled, every bus transfer, ALU signal and memory signal is recorded.
+	// Disabling it drops the events that have not been taken yet.
+	pub fn set_bus_tracing(&mut self, enabled: bool)
+	{
+		self.bus_trace = if enabled { Some(self.bus_trace.take().unwrap_or_default()) } else { None };
+	}
+
+	pub fn is_bus_tracing(&self) -> bool
+	{
+		self.bus_trace.is_some()
+	}
+
+	// Drain the events that have been recorded since the last call (empty if tracing is disabled):
+	pub fn take_trace(&mut self) -> Vec<BusEvent>
+	{
+		self.bus_trace.as_mut().map(std::mem::take).unwrap_or_default()
+	}
+}
+
+impl Mima
+{
+	// Remember address and accumulator when an instruction is about to be fetched:
+	pub(super) fn begin_instruction_trace(&mut self)
+	{
+		self.trace_start = Some((self.control_unit.iar, self.arithmetic_unit.acc));
+	}
+
+	pub(super) fn record_bus_event(&mut self, event: BusEvent)
+	{
+		if let Some(bus_trace) = self.bus_trace.as_mut()
+		{
+			bus_trace.push(event);
+		}
+	}
+
+	// Report the instruction to the hook when its execution has completed.
+	// Instructions whose fetch has not been observed (e. g. after restoring registers mid-instruction) are not reported.
+	pub(super) fn finish_instruction_trace(&mut self, instruction: Instruction)
+	{
+		if let (Some((address, acc_before)), Some(hook)) = (self.trace_start.take(), self.instruction_hook.as_mut())
+		{
+			hook(&InstructionTrace { instruction, address, acc_before, acc_after: self.arithmetic_unit.acc });
+		}
+	}
+}
@@ -0,0 +1,336 @@
+use crate::types::*;
+use crate::microcycle::Descriptor as MicrocycleDescriptor;
+use crate::unit::*;
+use crate::mima::Mima;
+use crate::bus::BusValue;
+
+// Information about register / flag values and changes are stored in enums to record changes:
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Value<T>
+{
+	Stasis(T),
+	Change(T, T),
+}
+
+impl<T> Value<T>
+{
+	pub fn initial_value(&self) -> T
+		where T: Copy
+	{
+		match self
+		{
+			Value::Change(w, _) 	=> *w,
+			Value::Stasis(w) 		=> *w,
+		}
+	}
+
+	pub fn final_value(&self) -> T
+		where T: Copy
+	{
+		match self
+		{
+			Value::Stasis(w) 		=> *w,
+			Value::Change(_, w) 	=> *w,
+		}
+	}
+}
+
+impl<T> Value<T>
+	where T: PartialEq
+{
+	fn make_diff(self, new: T) -> Self
+	{
+		match self
+		{
+			Value::Stasis(old) 		=> if old != new { Value::Change(old, new) } else { Value::Stasis(old) },
+			Value::Change(_, _) 	=> panic!("Tried to diff a value that is already marked as changed."),
+		}
+	}
+}
+
+pub type RegisterValue = Value<Word>;
+pub type FlagValue = Value<Flag>;
+
+// This struct allows to record a "flat" summary of all events that occur during a microcycle.
+// For all registers, there are old and new values.
+// We also include information about ALU and memory work and new operations at the end of the cycle.
+// With the "serde" feature, summaries can be serialized (see "render_json" for one JSON object per microcycle).
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MicrocycleSummary
+{
+	// Arithmetic unit registers (without "one", it is constant):
+	pub acc: RegisterValue,
+	pub x: RegisterValue,
+	pub y: RegisterValue,
+	pub z: RegisterValue,
+
+	// ALU flags:
+	pub carry: FlagValue,
+	pub overflow: FlagValue,
+	pub equal: FlagValue,
+
+	// ALU work (at the beginning of the microcycle):
+	pub alu_work: Option<(ALUOperation, u8)>,
+
+	// Control unit registers:
+	pub iar: RegisterValue,
+	pub ir: RegisterValue,
+
+	// Control unit flags:
+	pub run: FlagValue,
+	pub tra: FlagValue,
+
+	// Microcycle index and instruction:
+	pub microcycle: u8,
+	pub instruction: Option<Instruction>,
+
+	// Memory unit registers:
+	pub sar: RegisterValue,
+	pub sir: RegisterValue,
+
+	// Memory work (at the beginning of the microcycle):
+	pub mem_work: Option<(MemoryType, MemoryAccess, u8)>,
+
+	// The linear memory cell that has been written during the microcycle (address and value):
+	pub mem_write: Option<(Word, RegisterValue)>,
+
+	// The word that traveled over the bus (or whether an accumulator-dependent transfer has been suppressed):
+	pub bus_value: Option<BusValue>,
+
+	// The descriptor for this microcycle:
+	pub descriptor: MicrocycleDescriptor,
+
+	// The entry point of the running program:
+	pub entry: Word,
+}
+
+impl MicrocycleSummary
+{
+	pub fn record_microcycle(mima: &mut Mima) -> Option<MicrocycleSummary>
+	{
+		// Record the state before executing the microcycle (= everything still in stasis):
+		let mut acc = RegisterValue::Stasis(mima.arithmetic_unit.acc);
+		let mut x = RegisterValue::Stasis(mima.arithmetic_unit.x);
+		let mut y = RegisterValue::Stasis(mima.arithmetic_unit.y);
+		let mut z = RegisterValue::Stasis(mima.arithmetic_unit.z);
+		let mut carry = FlagValue::Stasis(mima.arithmetic_unit.carry());
+		let mut overflow = FlagValue::Stasis(mima.arithmetic_unit.overflow());
+		let mut equal = FlagValue::Stasis(mima.arithmetic_unit.equal());
+		let alu_work = mima.arithmetic_unit.work().map(|work| (work.op, work.remaining_cycles));
+
+		let mut iar = RegisterValue::Stasis(mima.control_unit.iar);
+		let mut ir = RegisterValue::Stasis(mima.control_unit.ir);
+		let mut run = FlagValue::Stasis(mima.control_unit.status().run);
+		let mut tra = FlagValue::Stasis(mima.control_unit.status().tra);
+		let microcycle = mima.control_unit.microcycle();
+		let instruction = mima.control_unit.instruction();
+
+		let mut sar = RegisterValue::Stasis(mima.memory_unit.sar);
+		let mut sir = RegisterValue::Stasis(mima.memory_unit.sir);
+		let mem_work = mima.memory_unit.work().map(|work| (work.mem_type, work.access, work.remaining_cycles));
+
+		// A linear write without remaining cycles is committed during this microcycle:
+		let mut mem_write = match mima.memory_unit.work()
+		{
+			Some(work @ MemoryWork { mem_type: MemoryType::Linear, access: MemoryAccess::Write, remaining_cycles: 0, .. }) =>
+			{
+				let addr = work.address();
+				Some((addr, RegisterValue::Stasis(mima.memory_unit.linear_memory()[addr.0 as usize])))
+			},
+
+			_ => None,
+		};
+
+		// Now execute the cycle.
+		// If it returns None because the MiMA is stopped, we are done.
+		// Otherwise, we have the descriptor.
+		if let Some(descriptor) = mima.perform_microcycle()
+		{
+			// Look for changes in the registers / flags:
+			acc = acc.make_diff(mima.arithmetic_unit.acc);
+			x = x.make_diff(mima.arithmetic_unit.x);
+			y = y.make_diff(mima.arithmetic_unit.y);
+			z = z.make_diff(mima.arithmetic_unit.z);
+			carry = carry.make_diff(mima.arithmetic_unit.carry());
+			overflow = overflow.make_diff(mima.arithmetic_unit.overflow());
+			equal = equal.make_diff(mima.arithmetic_unit.equal());
+
+			iar = iar.make_diff(mima.control_unit.iar);
+			ir = ir.make_diff(mima.control_unit.ir);
+			run = run.make_diff(mima.control_unit.status().run);
+			tra = tra.make_diff(mima.control_unit.status().tra);
+
+			sar = sar.make_diff(mima.memory_unit.sar);
+			sir = sir.make_diff(mima.memory_unit.sir);
+			mem_write = mem_write.map(|(addr, cell)| (addr, cell.make_diff(mima.memory_unit.linear_memory()[addr.0 as usize])));
+
+			// Summarize everything^^
+			Some(MicrocycleSummary
+			{
+				acc, x, y, z, carry, overflow, equal, alu_work,
+				iar, ir, run, tra, microcycle, instruction,
+				sar, sir, mem_work, mem_write, descriptor,
+				bus_value: mima.bus_value(),
+				entry: mima.entry(),
+			})
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn is_bus_active(&self) -> bool
+	{
+		match self.descriptor.bus_xfer.as_ref()
+		{
+			Some(xfer) 	=> xfer.is_satisfied(self.acc.initial_value(), self.equal.initial_value()),
+			None 		=> false,
+		}
+	}
+}
+
+// Render a summary as a single grep-able line (e. g. "C07 ADD acc=0x0000000A iar=0x00000004 z=0x00000007 [bus ACC->X]").
+// The register values are the ones at the end of the microcycle, the bus transfer is only shown if it is active.
+// A memory cell that has been changed by a store is appended with its old and new word (e. g. "[mem 0x00000003: 0x00000003->0x00000007]").
+pub fn render_oneline(summary: &MicrocycleSummary) -> String
+{
+	let mut line = format!("C{cycle:02} {instr:<3} acc={acc:} iar={iar:} z={z:}",
+		cycle = summary.microcycle,
+		instr = summary.instruction.map_or("---", |i| i.format_opcode()),
+		acc = summary.acc.final_value(),
+		iar = summary.iar.final_value(),
+		z = summary.z.final_value());
+
+	if let Some(xfer) = summary.descriptor.bus_xfer.as_ref().filter(|_| summary.is_bus_active())
+	{
+		let destinations: Vec<_> = xfer.destinations().iter().map(|dest| dest.name()).collect();
+		line.push_str(&format!(" [bus {:}->{:}]", xfer.source().name(), destinations.join(",")));
+	}
+
+	// A memory cell is only shown if a write has changed it:
+	if let Some((addr, Value::Change(old, new))) = summary.mem_write
+	{
+		line.push_str(&format!(" [mem {:}: {:}->{:}]", addr, old, new));
+	}
+
+	line
+}
+
+// Render a summary as a single-line JSON object (without line break, so a stream of microcycles becomes JSON Lines):
+#[cfg(feature = "json")]
+pub fn render_json(summary: &MicrocycleSummary) -> String
+{
+	serde_json::to_string(summary).expect("Microcycle summaries are always serializable.")
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CycleSummary
+{
+	// The accumulator and the program counter:
+	pub acc: RegisterValue,
+	pub iar: RegisterValue,
+
+	// The flags:
+	pub run: FlagValue,
+	pub tra: FlagValue,
+	pub carry: FlagValue,
+	pub overflow: FlagValue,
+	pub equal: FlagValue,
+
+	// The instruction that has been executed:
+	pub instruction: Instruction,
+
+	// The entry point of the running program:
+	pub entry: Word,
+}
+
+impl CycleSummary
+{
+	pub fn from_microcycle_summaries(start: &MicrocycleSummary, end: &MicrocycleSummary) -> CycleSummary
+	{
+		// The start must be in microcycle 1 and the end in microcycle 12:
+		debug_assert!((start.microcycle == 1) && (end.microcycle == 12), "Microcycle summaries for cycle summary must be from microcycle 1 and 12.");
+		CycleSummary::from_span(start, end)
+	}
+
+	// Summarize the microcycles from "start" to "end" (inclusive).
+	// The span may begin in the middle of an instruction and only end before microcycle 12 if the MiMA has stopped (see "Mima::cycles").
+	// The instruction must have been decoded at the end.
+	pub(crate) fn from_span(start: &MicrocycleSummary, end: &MicrocycleSummary) -> CycleSummary
+	{
+		// Calculate the state diff between the two cycles:
+		let acc = RegisterValue::Stasis(start.acc.initial_value()).make_diff(end.acc.final_value());
+		let iar = RegisterValue::Stasis(start.iar.initial_value()).make_diff(end.iar.final_value());
+		let run = FlagValue::Stasis(start.run.initial_value()).make_diff(end.run.final_value());
+		let tra = FlagValue::Stasis(start.tra.initial_value()).make_diff(end.tra.final_value());
+		let carry = FlagValue::Stasis(start.carry.initial_value()).make_diff(end.carry.final_value());
+		let overflow = FlagValue::Stasis(start.overflow.initial_value()).make_diff(end.overflow.final_value());
+		let equal = FlagValue::Stasis(start.equal.initial_value()).make_diff(end.equal.final_value());
+
+		// Take the instruction from the end:
+		let instruction = end.instruction.expect("Microcycle summary at the end must contain instruction.");
+
+		CycleSummary
+		{
+			acc,
+			iar,
+			run,
+			tra,
+			carry,
+			overflow,
+			equal,
+			instruction,
+			entry: end.entry,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::assembly::ObjectCode;
+
+	#[test]
+	fn store_renders_changed_cell()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 7\nstv x\nhlt\nx: dat 3").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		let lines: Vec<_> = std::iter::from_fn(|| MicrocycleSummary::record_microcycle(&mut mima))
+			.map(|summary| render_oneline(&summary))
+			.collect();
+
+		// Only the microcycle that commits the store shows the cell:
+		let mem_lines: Vec<_> = lines.iter().filter(|line| line.contains("[mem ")).collect();
+		assert_eq!(mem_lines.len(), 1);
+		assert!(mem_lines[0].starts_with("C11 STV"));
+		assert!(mem_lines[0].ends_with(&format!("[mem {:}: {:}->{:}]", Word(3), Word(3), Word(7))));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn fetch_renders_as_json()
+	{
+		let (code, _) = ObjectCode::assemble("hlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+
+		// Microcycle 1 transfers IAR to X and SAR and starts the read:
+		let summary = MicrocycleSummary::record_microcycle(&mut mima).unwrap();
+		let json = render_json(&summary);
+
+		assert!(!json.contains('\n'));
+		assert!(json.contains(r#""microcycle":1"#));
+		assert!(json.contains(r#""destinations":["X","SAR"]"#));
+		assert!(json.contains(r#""mem_access":"Read""#));
+
+		// Registers are read back from their names:
+		let destinations: Registers = serde_json::from_str(r#"["X","SAR"]"#).unwrap();
+		assert_eq!(destinations, Registers::X | Registers::SAR);
+		assert!(serde_json::from_str::<Registers>(r#"["PC"]"#).is_err());
+	}
+}
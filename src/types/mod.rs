@@ -1,11 +1,73 @@
 use bitflags::bitflags;
+use std::cmp::Ordering;
 use std::fmt;
 use std::ops::Range;
 
-// A MiMA machine word (32 bit, newtype idiom):
-#[derive(Copy, Clone, PartialEq, PartialOrd)]
+// A MiMA machine word (32 bit, newtype idiom).
+// The derived ordering compares the unsigned values (e. g. Word(0xFFFFFFFF) > Word(1)), use "signed_cmp" for 2's complement.
+// It stays because address ranges (like "LINEAR_ADDRESS_SPACE_RANGE") need it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Word(pub u32);
 
+impl Word
+{
+	// The width of a machine word in bits and the sign bit (the uppermost one) in 2's complement:
+	pub const BITS: u32 = u32::BITS;
+	pub const SIGN_BIT: Word = Word(1u32 << (Word::BITS - 1));
+
+	// Rotations by n bits (n is taken modulo the word width).
+	// They forward to the u32 rotations, which never shift by the full width: n = 0 (or any multiple of 32) leaves the word unchanged.
+	pub fn rotate_right(self, n: u32) -> Word
+	{
+		Word(self.0.rotate_right(n))
+	}
+
+	pub fn rotate_left(self, n: u32) -> Word
+	{
+		Word(self.0.rotate_left(n))
+	}
+
+	// Sign-extend a word that has been masked by a contiguous mask of low bits:
+	// If the uppermost bit of the mask is set, all bits above the mask are set, too (e. g. 0x0FFFFFFF becomes 0xFFFFFFFF for 0x0FFFFFFF).
+	pub fn sign_extend(self, mask: Word) -> Word
+	{
+		let sign_bit = mask.0 & !(mask.0 >> 1);
+
+		if (self.0 & sign_bit) != 0
+		{
+			Word(self.0 | !mask.0)
+		}
+		else
+		{
+			self
+		}
+	}
+
+	// The signed (2's complement) and unsigned interpretations of the word.
+	// They match the assembler's literals: "-3" and "4294967293" both yield 0xFFFFFFFD.
+	pub fn as_i32(&self) -> i32
+	{
+		self.0 as i32
+	}
+
+	pub fn as_u32(&self) -> u32
+	{
+		self.0
+	}
+
+	pub fn to_signed_string(&self) -> String
+	{
+		format!("{:}", self.as_i32())
+	}
+
+	// Compare two words as signed (2's complement) numbers, e. g. Word(0xFFFFFFFF) (= -1) is less than Word(1):
+	pub fn signed_cmp(&self, other: &Word) -> Ordering
+	{
+		self.as_i32().cmp(&other.as_i32())
+	}
+}
+
 impl fmt::Display for Word
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
@@ -14,13 +76,39 @@ impl fmt::Display for Word
 	}
 }
 
+// The radix formats forward to the unsigned value (including flags like "{:#010x}"):
+impl fmt::LowerHex for Word
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		fmt::LowerHex::fmt(&self.0, f)
+	}
+}
+
+impl fmt::UpperHex for Word
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		fmt::UpperHex::fmt(&self.0, f)
+	}
+}
+
+impl fmt::Binary for Word
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		fmt::Binary::fmt(&self.0, f)
+	}
+}
+
 // A MiMA machine flag (boolean, newtype idiom):
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Flag(pub bool);
 
 // The MiMA address space size in address bits, bytes and words:
 pub const ADDRESS_SPACE_BITS: usize 					= 28;
-pub const ADDRESS_SPACE_WORDS: usize 					= (1usize << ADDRESS_SPACE_BITS);
+pub const ADDRESS_SPACE_WORDS: usize 					= 1usize << ADDRESS_SPACE_BITS;
 
 // The uppermost quarter of the address space is device IO memory.
 // The lower three quarters are linear memory.
@@ -58,13 +146,38 @@ bitflags!
 
 impl Registers
 {
-	// A constant array of all register names (no iter() in bitflags ... yet?):
+	// A constant array of all register names (bitflags 1.x has no iter(), so we provide our own below):
 	pub const ALL_REGISTERS: [Registers; 9] =
 	[
 		Registers::ACC, Registers::ONE, Registers::X,   Registers::Y,
 		Registers::Z,   Registers::IR,  Registers::IAR, Registers::SAR,
 		Registers::SIR
 	];
+
+	// Iterate the single registers that are contained in the flags (in the order of ALL_REGISTERS):
+	pub fn iter(&self) -> impl Iterator<Item = Registers>
+	{
+		let regs = *self;
+		Registers::ALL_REGISTERS.iter().copied().filter(move |&reg| regs.contains(reg))
+	}
+
+	// The name of a single register (empty for combinations):
+	pub fn name(&self) -> &'static str
+	{
+		match *self
+		{
+			Registers::ACC 		=> "ACC",
+			Registers::ONE 		=> "ONE",
+			Registers::X 		=> "X",
+			Registers::Y 		=> "Y",
+			Registers::Z 		=> "Z",
+			Registers::IAR		=> "IAR",
+			Registers::IR 		=> "IR",
+			Registers::SAR		=> "SAR",
+			Registers::SIR		=> "SIR",
+			_ 					=> "",
+		}
+	}
 }
 
 impl fmt::Display for Registers
@@ -72,29 +185,42 @@ impl fmt::Display for Registers
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
 	{
 		// Get a vector of string slice representations of the flagged cases and join them:
-		let strings: Vec<_> = Registers::ALL_REGISTERS.iter().filter(|&&dest| self.contains(dest)).map(|&dest|
-		{
-			match dest
-			{
-				Registers::ACC 		=> "ACC",
-				Registers::ONE 		=> "ONE",
-				Registers::X 		=> "X",
-				Registers::Y 		=> "Y",
-				Registers::Z 		=> "Z",
-				Registers::IAR		=> "IAR",
-				Registers::IR 		=> "IR",
-				Registers::SAR		=> "SAR",
-				Registers::SIR		=> "SIR",
-				_ 					=> "",
-			}
-		}).collect();
+		let strings: Vec<_> = self.iter().map(|dest| dest.name()).collect();
 
 		write!(f, "[{}]", strings.join(", "))
 	}
 }
 
+// Registers are serialized as the list of their names (e. g. ["X", "SAR"]) instead of the raw bits (and deserialized from it):
+#[cfg(feature = "serde")]
+impl serde::Serialize for Registers
+{
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	{
+		serializer.collect_seq(self.iter().map(|reg| reg.name()))
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Registers
+{
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+	{
+		let names = Vec::<String>::deserialize(deserializer)?;
+
+		names.iter().try_fold(Registers::empty(), |regs, name|
+		{
+			Registers::ALL_REGISTERS.iter()
+				.find(|reg| reg.name() == name)
+				.map(|&reg| regs | reg)
+				.ok_or_else(|| serde::de::Error::custom(format!("Unknown register \"{:}\".", name)))
+		})
+	}
+}
+
 // The MiMA instructions are an algebraic datatype:
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction
 {
 	Add(Word),
@@ -106,56 +232,29 @@ pub enum Instruction
 	LoadConstant(Word),
 	Jump(Word),
 	JumpIfNegative(Word),
+	JumpIfZero(Word),
 	Equals(Word),
+	Subtract(Word),
+	Compare(Word),
+	JumpIfEqual(Word),
 	Halt,
 	Not,
 	RotateRight(Word),
+	RotateLeft(Word),
+	SkipIfNegative,
 	NoOperation,
+
+	// A word with a reserved opcode (kept as a whole, executing it faults the MiMA):
+	Illegal(Word),
 }
 
-// Disassemble instructions from machine words:
+// Disassemble instructions from machine words.
+// Words with reserved opcodes become illegal instructions (see "Instruction::canonical_word").
 impl From<Word> for Instruction
 {
 	fn from(word: Word) -> Instruction
 	{
-		use Instruction::*;
-
-		// Extract the opcode from the uppermost four bits:
-		let opcode = word.0 >> 28;
-
-		if opcode != 15
-		{
-			// Basic format:
-			let payload = Word(word.0 & 0x0F_FF_FF_FFu32);
-
-			match opcode
-			{
-				0x00 => Add(payload),
-				0x01 => And(payload),
-				0x02 => Or(payload),
-				0x03 => Xor(payload),
-				0x04 => LoadValue(payload),
-				0x05 => StoreValue(payload),
-				0x06 => LoadConstant(payload),
-				0x07 => Jump(payload),
-				0x08 => JumpIfNegative(payload),
-				0x09 => Equals(payload),
-				_ => NoOperation,
-			}
-		}
-		else
-		{
-			// Extended format:
-			let payload = Word(word.0 & 0x00_FF_FF_FFu32);
-
-			match (word.0 & 0x0F_00_00_00u32) >> 24
-			{
-				0x00  => Halt,
-				0x01  => Not,
-				0x02  => RotateRight(payload),
-				_  => NoOperation,
-			}
-		}
+		Instruction::decode(word).unwrap_or(Instruction::Illegal(word))
 	}
 }
 
@@ -180,15 +279,25 @@ impl From<Instruction> for Word
 			Jump(pl) 			=> (0x07, true, pl),
 			JumpIfNegative(pl) 	=> (0x08, true, pl),
 			Equals(pl) 			=> (0x09, true, pl),
+			Subtract(pl) 		=> (0x0A, true, pl),
+			JumpIfZero(pl) 		=> (0x0B, true, pl),
+			Compare(pl) 		=> (0x0C, true, pl),
+			JumpIfEqual(pl) 	=> (0x0D, true, pl),
 
 			// Extended format:
 			Halt 				=> (0x00, false, Word(0)),
 			Not 				=> (0x01, false, Word(0)),
 			RotateRight(pl) 	=> (0x02, false, pl),
+			RotateLeft(pl) 		=> (0x03, false, pl),
+			SkipIfNegative 		=> (0x04, false, Word(0)),
 			NoOperation 		=> (0x0F, false, Word(0)),
+
+			// Illegal instructions keep their original word:
+			Illegal(word) 		=> return word,
 		};
 
 		// Basic (28 bit payload) or extended (24 bit payload)?
+		// The assembler rejects literals that exceed the payload, so the asserts only guard against programming errors.
 		if is_basic_format
 		{
 			assert!(payload <= 0x0F_FF_FF_FFu32, "Payload for basic format exceeded ({:08X} > {:08X}).", payload, 0x0F_FF_FF_FFu32);
@@ -202,8 +311,80 @@ impl From<Instruction> for Word
 	}
 }
 
+impl fmt::Display for Instruction
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		// The opcode is followed by the payload (if there is one):
+		match self.payload()
+		{
+			Some(pl) 	=> write!(f, "{:} {:}", self.format_opcode(), pl),
+			None 		=> write!(f, "{:}", self.format_opcode()),
+		}
+	}
+}
+
 impl Instruction
 {
+	// Decode a machine word.
+	// Words with reserved opcodes yield None.
+	pub fn decode(word: Word) -> Option<Instruction>
+	{
+		use Instruction::*;
+
+		// Extract the opcode from the uppermost four bits:
+		let opcode = word.0 >> 28;
+
+		if opcode != 15
+		{
+			// Basic format:
+			let payload = Word(word.0 & 0x0F_FF_FF_FFu32);
+
+			match opcode
+			{
+				0x00 => Some(Add(payload)),
+				0x01 => Some(And(payload)),
+				0x02 => Some(Or(payload)),
+				0x03 => Some(Xor(payload)),
+				0x04 => Some(LoadValue(payload)),
+				0x05 => Some(StoreValue(payload)),
+				0x06 => Some(LoadConstant(payload)),
+				0x07 => Some(Jump(payload)),
+				0x08 => Some(JumpIfNegative(payload)),
+				0x09 => Some(Equals(payload)),
+				0x0A => Some(Subtract(payload)),
+				0x0B => Some(JumpIfZero(payload)),
+				0x0C => Some(Compare(payload)),
+				0x0D => Some(JumpIfEqual(payload)),
+				_ => None,
+			}
+		}
+		else
+		{
+			// Extended format:
+			let payload = Word(word.0 & 0x00_FF_FF_FFu32);
+
+			match (word.0 & 0x0F_00_00_00u32) >> 24
+			{
+				0x00  => Some(Halt),
+				0x01  => Some(Not),
+				0x02  => Some(RotateRight(payload)),
+				0x03  => Some(RotateLeft(payload)),
+				0x04  => Some(SkipIfNegative),
+				0x0F  => Some(NoOperation),
+				_  => None,
+			}
+		}
+	}
+
+	// The word that the MiMA effectively executes for the given word (decoded and encoded again).
+	// Decoding is not injective: Instructions without payload ignore the payload bits (e. g. 0xF0000005 is HLT).
+	// Those words map to the canonical encoding of their instruction, all other words (including illegal ones) map to themselves.
+	pub fn canonical_word(word: Word) -> Word
+	{
+		Word::from(Instruction::from(word))
+	}
+
 	pub fn format_opcode(&self) -> &'static str
 	{
 		use Instruction::*;
@@ -219,11 +400,160 @@ impl Instruction
 			LoadConstant(_) 	=> "LDC",
 			Jump(_) 			=> "JMP",
 			JumpIfNegative(_) 	=> "JMN",
+			JumpIfZero(_) 		=> "JMZ",
 			Equals(_) 			=> "EQL",
+			Subtract(_) 		=> "SUB",
+			Compare(_) 			=> "CMP",
+			JumpIfEqual(_) 		=> "JME",
 			Halt 				=> "HLT",
 			Not 				=> "NOT",
 			RotateRight(_) 		=> "RAR",
+			RotateLeft(_) 		=> "RAL",
+			SkipIfNegative 		=> "SKN",
 			NoOperation 		=> "NOP",
+			Illegal(_) 			=> "ILL",
+		}
+	}
+
+	// The address or immediate value of the instruction (None for instructions without operand, the whole word for illegal instructions):
+	pub fn payload(&self) -> Option<Word>
+	{
+		use Instruction::*;
+
+		match *self
+		{
+			Add(pl) | And(pl) | Or(pl) | Xor(pl) | LoadValue(pl) | StoreValue(pl) | LoadConstant(pl) | Jump(pl) | JumpIfNegative(pl) |
+			JumpIfZero(pl) | Equals(pl) | Subtract(pl) | Compare(pl) | JumpIfEqual(pl) | RotateRight(pl) | RotateLeft(pl) 	|
+			Illegal(pl) 																									=> Some(pl),
+			Halt | Not | SkipIfNegative | NoOperation 																		=> None,
 		}
 	}
-}
\ No newline at end of file
+
+	// Extended format instructions (opcode 0xF) have a 24 bit payload, all others a 28 bit one:
+	pub fn is_extended_format(&self) -> bool
+	{
+		use Instruction::*;
+
+		match self
+		{
+			Halt | Not | RotateRight(_) | RotateLeft(_) | SkipIfNegative | NoOperation 	=> true,
+			Illegal(word) 																=> (word.0 >> 28) == 0x0F,
+			_ 																			=> false,
+		}
+	}
+
+	// Describe the semantics of the instruction in one sentence (with the operand filled in):
+	pub fn explain(&self) -> String
+	{
+		use Instruction::*;
+
+		match self
+		{
+			Add(pl) 			=> format!("Add the value at address 0x{:X} to the accumulator.", pl.0),
+			And(pl) 			=> format!("Bitwise AND the value at address 0x{:X} into the accumulator.", pl.0),
+			Or(pl) 				=> format!("Bitwise OR the value at address 0x{:X} into the accumulator.", pl.0),
+			Xor(pl) 			=> format!("Bitwise XOR the value at address 0x{:X} into the accumulator.", pl.0),
+			LoadValue(pl) 		=> format!("Load the value at address 0x{:X} into the accumulator.", pl.0),
+			StoreValue(pl) 		=> format!("Store the accumulator at address 0x{:X}.", pl.0),
+			LoadConstant(pl) 	=> format!("Load the constant 0x{:X} into the accumulator.", pl.0),
+			Jump(pl) 			=> format!("Jump to address 0x{:X}.", pl.0),
+			JumpIfNegative(pl) 	=> format!("Jump to address 0x{:X} if the accumulator is negative.", pl.0),
+			JumpIfZero(pl) 		=> format!("Jump to address 0x{:X} if the accumulator is zero.", pl.0),
+			Equals(pl) 			=> format!("Set the accumulator to -1 if it equals the value at address 0x{:X}, otherwise to 0.", pl.0),
+			Subtract(pl) 		=> format!("Subtract the value at address 0x{:X} from the accumulator.", pl.0),
+			Compare(pl) 		=> format!("Set the EQUAL flag if the accumulator equals the value at address 0x{:X}, otherwise clear it.", pl.0),
+			JumpIfEqual(pl) 	=> format!("Jump to address 0x{:X} if the EQUAL flag is set.", pl.0),
+			Halt 				=> String::from("Halt the machine."),
+			Not 				=> String::from("Invert all bits of the accumulator."),
+			RotateRight(pl) 	=> format!("Rotate the accumulator right by {:} bit(s).", pl.0),
+			RotateLeft(pl) 		=> format!("Rotate the accumulator left by {:} bit(s).", pl.0),
+			SkipIfNegative 		=> String::from("Skip the next word if the accumulator is negative."),
+			NoOperation 		=> String::from("Do nothing."),
+			Illegal(word) 		=> format!("The word {:} has a reserved opcode, executing it faults the MiMA.", word),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn rotations_match_u32()
+	{
+		let word = Word(0x8000_0001);
+
+		for &n in [0, 1, 4, 31, 32, 33, 64].iter()
+		{
+			assert_eq!(word.rotate_right(n), Word(word.0.rotate_right(n)), "rotate_right by {:}", n);
+			assert_eq!(word.rotate_left(n), Word(word.0.rotate_left(n)), "rotate_left by {:}", n);
+		}
+
+		// Rotating by 0 or the full width leaves the word unchanged:
+		assert_eq!(word.rotate_right(0), word);
+		assert_eq!(word.rotate_right(32), word);
+		assert_eq!(Word(0x0000_0001).rotate_right(1), Word(0x8000_0000));
+		assert_eq!(Word(0x8000_0000).rotate_left(1), Word(0x0000_0001));
+	}
+
+	#[test]
+	fn sign_bit_is_uppermost_bit()
+	{
+		assert_eq!(Word::BITS, 32);
+		assert_eq!(Word::SIGN_BIT, Word(1 << 31));
+	}
+
+	#[test]
+	fn explanations()
+	{
+		assert_eq!(Instruction::Add(Word(0x10)).explain(), "Add the value at address 0x10 to the accumulator.");
+		assert_eq!(Instruction::StoreValue(Word(0xABC)).explain(), "Store the accumulator at address 0xABC.");
+		assert_eq!(Instruction::RotateRight(Word(3)).explain(), "Rotate the accumulator right by 3 bit(s).");
+		assert_eq!(Instruction::Halt.explain(), "Halt the machine.");
+		assert_eq!(Instruction::Not.explain(), "Invert all bits of the accumulator.");
+	}
+
+	#[test]
+	fn signed_cmp_differs_from_unsigned_order()
+	{
+		let minus_one = Word(0xFFFFFFFF);
+		let one = Word(1);
+
+		assert!(minus_one > one);
+		assert_eq!(minus_one.signed_cmp(&one), Ordering::Less);
+		assert_eq!(one.signed_cmp(&minus_one), Ordering::Greater);
+		assert_eq!(minus_one.signed_cmp(&minus_one), Ordering::Equal);
+
+		// The most negative word is less than the most positive one:
+		assert_eq!(Word::SIGN_BIT.signed_cmp(&Word(0x7FFFFFFF)), Ordering::Less);
+	}
+
+	#[test]
+	fn decoding_round_trips_to_canonical_words()
+	{
+		let payload = 0x00AB_CDEF;
+
+		// Basic format: All defined opcodes keep their payload, the reserved opcode 0xE is kept as a whole:
+		for opcode in 0x0..=0xE
+		{
+			let word = Word((opcode << 28) | payload);
+			assert_eq!(Word::from(Instruction::from(word)), word, "basic opcode {:X}", opcode);
+		}
+
+		// Extended format: Instructions without payload drop it, rotations and reserved opcodes are kept as a whole:
+		for opcode in 0x0..=0xF
+		{
+			let word = Word(0xF000_0000 | (opcode << 24) | payload);
+			let canonical = match opcode
+			{
+				0x0 | 0x1 | 0x4 | 0xF 	=> Word(0xF000_0000 | (opcode << 24)),
+				_ 						=> word,
+			};
+
+			assert_eq!(Word::from(Instruction::from(word)), canonical, "extended opcode {:X}", opcode);
+			assert_eq!(Instruction::canonical_word(word), canonical);
+			assert_eq!(Instruction::canonical_word(canonical), canonical);
+		}
+	}
+}
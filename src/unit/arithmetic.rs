@@ -1,11 +1,15 @@
+use std::error::Error;
+use std::fmt;
+use std::mem;
 use std::num::Wrapping;
 use crate::types::*;
 
-// How many microcycles does the ALU need to complete work?
-const MICROCYCLES_PER_OP: u8 = 1;
+// How many microcycles does the ALU need to complete work (by default)?
+pub const DEFAULT_MICROCYCLES_PER_OP: u8 = 1;
 
 // All the operations that can be performed by the ALU:
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operation
 {
 	Add,
@@ -13,14 +17,133 @@ pub enum Operation
 	Or,
 	Xor,
 	Equals,
+	Subtract,
 	Not,
 	RotateRight,
+	RotateLeft,
+
+	// Like "Equals", but the result is also stored in the EQUAL flag (see "Unit::equal"):
+	Compare,
+
+	// An operation that has been registered at an arithmetic unit (index into its table, see "register_op"):
+	Custom(usize),
+}
+
+// The function that calculates the result of a custom operation from X and Y:
+pub type CustomOperationFn = fn(Word, Word) -> Word;
+
+// A fault that occurs when the ALU is signaled with a custom operation it does not know (e. g. a handle of another arithmetic unit):
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault
+{
+	UnknownOperation(usize),
+}
+
+impl fmt::Display for Fault
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			Fault::UnknownOperation(index) 	=> write!(f, "The custom ALU operation #{:} has not been registered at this arithmetic unit.", index),
+		}
+	}
+}
+
+impl Error for Fault { }
+
+impl Operation
+{
+	// Calculate the result of the operation for the given inputs.
+	// Custom operations are only known to the arithmetic unit that registered them (see "Unit::apply"), so they fault here.
+	pub fn apply(self, x: Word, y: Word) -> Result<Word, Fault>
+	{
+		Ok(Word(match self
+		{
+			Operation::Add 				=> (Wrapping(x.0) + Wrapping(y.0)).0,
+			Operation::And 				=> x.0 & y.0,
+			Operation::Or 				=> x.0 | y.0,
+			Operation::Xor 				=> x.0 ^ y.0,
+			Operation::Equals 			|
+			Operation::Compare 			=> if x == y { 0xFF_FF_FF_FFu32 } else { 0u32 },
+			Operation::Subtract 		=> (Wrapping(x.0) - Wrapping(y.0)).0,
+			Operation::Not 				=> !x.0,
+			Operation::RotateRight 		=> x.rotate_right(y.0).0,
+			Operation::RotateLeft 		=> x.rotate_left(y.0).0,
+			Operation::Custom(index) 	=> return Err(Fault::UnknownOperation(index)),
+		}))
+	}
+
+	// How many microcycles does the ALU need to complete the operation (unless the arithmetic unit overrides it)?
+	// All operations share the default for now. The execute microcycles rely on it.
+	pub fn latency(&self) -> u8
+	{
+		match self
+		{
+			Operation::Add 			|
+			Operation::And 			|
+			Operation::Or 			|
+			Operation::Xor 			|
+			Operation::Equals 		|
+			Operation::Subtract 	|
+			Operation::Not 			|
+			Operation::RotateRight 	|
+			Operation::RotateLeft 	|
+			Operation::Compare 		|
+			Operation::Custom(_) 	=> DEFAULT_MICROCYCLES_PER_OP,
+		}
+	}
+}
+
+impl Operation
+{
+	// Calculate the CARRY and OVERFLOW flags for the given inputs.
+	// Only additions and subtractions affect the flags.
+	pub fn flags(self, x: Word, y: Word) -> Option<(Flag, Flag)>
+	{
+		let is_negative = |w: u32| (w & Word::SIGN_BIT.0) != 0;
+
+		match self
+		{
+			Operation::Add =>
+			{
+				let (result, carry) = x.0.overflowing_add(y.0);
+
+				// Signed overflow: Both operands have the same sign, but the result has not.
+				let overflow = (is_negative(x.0) == is_negative(y.0)) && (is_negative(result) != is_negative(x.0));
+				Some((Flag(carry), Flag(overflow)))
+			},
+			Operation::Subtract =>
+			{
+				let (result, borrow) = x.0.overflowing_sub(y.0);
+
+				// Signed overflow: The operands have different signs and the result has the sign of the subtrahend.
+				let overflow = (is_negative(x.0) != is_negative(y.0)) && (is_negative(result) != is_negative(x.0));
+				Some((Flag(borrow), Flag(overflow)))
+			},
+			_ => None,
+		}
+	}
+
+	// Calculate the EQUAL flag for the given inputs.
+	// Only comparisons affect the flag.
+	pub fn equal_flag(self, x: Word, y: Word) -> Option<Flag>
+	{
+		match self
+		{
+			Operation::Compare 	=> Some(Flag(x == y)),
+			_ 					=> None,
+		}
+	}
 }
 
 // A pending ALU calculation.
 // Each microcycle decrements the number of remaining cycles.
 // As soon as it falls to 0, the ALU result is available in Z.
 // Work is executed on copies of X and Y. Changing them during its progress won't change the outcome.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Work
 {
 	pub op: Operation,
@@ -45,8 +168,32 @@ pub struct Unit
 	pub y: Word,
 	pub z: Word,
 
+	// The CARRY flag holds the 33rd bit of the last unsigned addition (resp. the borrow of the last subtraction).
+	// The OVERFLOW flag indicates that the last addition / subtraction overflowed in terms of signed numbers.
+	// Other operations leave both flags untouched.
+	carry: Flag,
+	overflow: Flag,
+
+	// The EQUAL flag holds the outcome of the last comparison (X == Y). It is read by JME.
+	// In contrast to EQL, CMP does not touch ACC.
+	equal: Flag,
+
 	// Pending work:
-	work: Option<Work>
+	work: Option<Work>,
+
+	// A latency that overrides the ones of the single operations (if set):
+	microcycles_per_op: Option<u8>,
+
+	// The registered custom operations (name and function):
+	custom_ops: Vec<(String, CustomOperationFn)>,
+}
+
+impl Default for Unit
+{
+	fn default() -> Self
+	{
+		Unit::new()
+	}
 }
 
 impl Unit
@@ -60,7 +207,53 @@ impl Unit
 			x: Word(0),
 			y: Word(0),
 			z: Word(0),
+			carry: Flag(false),
+			overflow: Flag(false),
+			equal: Flag(false),
 			work: None,
+			microcycles_per_op: None,
+			custom_ops: Vec::new(),
+		}
+	}
+
+	// Restore the initial register values and drop pending work.
+	// The configured latency and the custom operations are kept.
+	pub fn reset(&mut self)
+	{
+		*self = Unit
+		{
+			microcycles_per_op: self.microcycles_per_op,
+			custom_ops: mem::take(&mut self.custom_ops),
+			..Unit::new()
+		};
+	}
+
+	// Register a custom operation in addition to the built-in ones.
+	// The returned handle can be signaled like any other operation.
+	pub fn register_op(&mut self, name: &str, f: CustomOperationFn) -> Operation
+	{
+		self.custom_ops.push((String::from(name), f));
+		Operation::Custom(self.custom_ops.len() - 1)
+	}
+
+	// The name of a custom operation (None for built-in operations and unknown handles):
+	pub fn custom_op_name(&self, op: Operation) -> Option<&str>
+	{
+		match op
+		{
+			Operation::Custom(index) 	=> self.custom_ops.get(index).map(|(name, _)| name.as_str()),
+			_ 							=> None,
+		}
+	}
+
+	// Calculate the result of an operation (built-in or custom) for the given inputs.
+	// Custom operations must have been registered at this unit.
+	pub fn apply(&self, op: Operation, x: Word, y: Word) -> Result<Word, Fault>
+	{
+		match op
+		{
+			Operation::Custom(index) 	=> self.custom_ops.get(index).map(|&(_, f)| f(x, y)).ok_or(Fault::UnknownOperation(index)),
+			_ 							=> op.apply(x, y),
 		}
 	}
 
@@ -68,11 +261,70 @@ impl Unit
 	{
 		self.work.as_ref()
 	}
+
+	pub fn carry(&self) -> Flag
+	{
+		self.carry
+	}
+
+	pub fn overflow(&self) -> Flag
+	{
+		self.overflow
+	}
+
+	pub fn equal(&self) -> Flag
+	{
+		self.equal
+	}
+
+	pub fn microcycles_per_op(&self) -> Option<u8>
+	{
+		self.microcycles_per_op
+	}
+
+	// The number of microcycles the ALU needs to complete the given operation:
+	pub fn latency(&self, op: Operation) -> u8
+	{
+		self.microcycles_per_op.unwrap_or_else(|| op.latency())
+	}
+
+	// Start an operation on (copies of) X and Y. The result arrives in Z after the configured latency.
+	// Custom operations must have been registered at this unit.
+	pub fn signal_alu(&mut self, op: Operation) -> Result<(), Fault>
+	{
+		assert!(self.work.is_none(), "ALU operation is already in progress.");
+
+		if let Operation::Custom(index) = op
+		{
+			if index >= self.custom_ops.len()
+			{
+				return Err(Fault::UnknownOperation(index));
+			}
+		}
+
+		self.work = Some(Work
+		{
+			op,
+			x: self.x,
+			y: self.y,
+			remaining_cycles: self.latency(op),
+		});
+
+		Ok(())
+	}
+
+	// Override the latency of all ALU operations (None restores the latencies of the single operations).
+	// The execute microcycles are designed for the default. Raising it makes results arrive too late (see the strict mode of the MiMA).
+	pub fn set_microcycles_per_op(&mut self, microcycles_per_op: Option<u8>)
+	{
+		self.microcycles_per_op = microcycles_per_op;
+	}
 }
 
 impl Unit
 {
-	pub(crate) fn poll_work(&mut self)
+	// Pending work may have been restored from a snapshot of another unit, so its operation can still be unknown:
+	pub(crate) fn poll_work(&mut self) -> Result<(), Fault>
 	{
 		if let Some(work) = self.work.as_mut()
 		{
@@ -83,42 +335,107 @@ impl Unit
 			else
 			{
 				let work = self.work.take().unwrap();
-				self.finalize_work(work);
+				self.finalize_work(work)?;
 			}
 		}
+
+		Ok(())
 	}
 
-	pub(crate) fn signal_alu(&mut self, op: Operation)
+	pub(crate) fn restore_work(&mut self, work: Option<Work>)
 	{
-		assert!(self.work.is_none(), "ALU operation is already in progress.");
+		self.work = work;
+	}
 
-		self.work = Some(Work
-		{
-			op,
-			x: self.x,
-			y: self.y,
-			remaining_cycles: MICROCYCLES_PER_OP,
-		});
+	pub(crate) fn restore_flags(&mut self, carry: Flag, overflow: Flag, equal: Flag)
+	{
+		self.carry = carry;
+		self.overflow = overflow;
+		self.equal = equal;
+	}
+
+	// Perform an operation on X and Y without latency (used by the fast interpreter, which only executes built-in operations):
+	pub(crate) fn perform_immediately(&mut self, op: Operation)
+	{
+		self.finalize_work(Work { op, x: self.x, y: self.y, remaining_cycles: 0 }).expect("Built-in ALU operations cannot fault.");
 	}
 }
 
 impl Unit
 {
-	fn finalize_work(&mut self, work: Work)
+	fn finalize_work(&mut self, work: Work) -> Result<(), Fault>
 	{
-		self.z = Word(match work.op
+		self.z = self.apply(work.op, work.x, work.y)?;
+
+		if let Some((carry, overflow)) = work.op.flags(work.x, work.y)
 		{
-			Operation::Add 			=> (Wrapping(work.x.0) + Wrapping(work.y.0)).0,
-			Operation::And 			=> work.x.0 & work.y.0,
-			Operation::Or 			=> work.x.0 | work.y.0,
-			Operation::Xor 			=> work.x.0 ^ work.y.0,
-			Operation::Equals 		=> if work.x == work.y { 0xFF_FF_FF_FFu32 } else { 0u32 },
-			Operation::Not 			=> !work.x.0,
-			Operation::RotateRight 	=>
-			{
-				let rot = work.y.0 % 32;
-				(work.x.0 >> rot) | (work.x.0 << rot)
-			},
-		});
+			self.carry = carry;
+			self.overflow = overflow;
+		}
+
+		if let Some(equal) = work.op.equal_flag(work.x, work.y)
+		{
+			self.equal = equal;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn nand(x: Word, y: Word) -> Word
+	{
+		Word(!(x.0 & y.0))
+	}
+
+	#[test]
+	fn custom_operation_leaves_result_in_z()
+	{
+		let mut unit = Unit::new();
+		let op = unit.register_op("nand", nand);
+		assert_eq!(unit.custom_op_name(op), Some("nand"));
+
+		unit.x = Word(0b1100);
+		unit.y = Word(0b1010);
+		unit.signal_alu(op).unwrap();
+
+		while unit.work().is_some()
+		{
+			unit.poll_work().unwrap();
+		}
+
+		assert_eq!(unit.z, Word(!0b1000));
+	}
+
+	#[test]
+	fn foreign_custom_operation_is_rejected()
+	{
+		let mut other = Unit::new();
+		let op = other.register_op("nand", nand);
+
+		let mut unit = Unit::new();
+		assert_eq!(unit.signal_alu(op), Err(Fault::UnknownOperation(0)));
+		assert!(unit.work().is_none());
+
+		assert_eq!(unit.apply(op, Word(1), Word(1)), Err(Fault::UnknownOperation(0)));
+		assert_eq!(op.apply(Word(1), Word(1)), Err(Fault::UnknownOperation(0)));
+		assert_eq!(other.apply(op, Word(1), Word(1)), Ok(Word(!1)));
+	}
+
+	#[test]
+	fn rotations_by_0_and_31()
+	{
+		let x = Word(0x8000_0001);
+
+		assert_eq!(Operation::RotateRight.apply(x, Word(0)), Ok(x));
+		assert_eq!(Operation::RotateLeft.apply(x, Word(0)), Ok(x));
+
+		// Rotating by 31 is the same as rotating by 1 in the other direction:
+		assert_eq!(Operation::RotateRight.apply(x, Word(31)), Ok(Word(0x0000_0003)));
+		assert_eq!(Operation::RotateLeft.apply(x, Word(31)), Ok(Word(0xC000_0000)));
 	}
 }
@@ -2,6 +2,8 @@ use crate::types::*;
 
 // The control unit encapsulates a status field.
 // It contains various flags.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Status
 {
 	// The RUN flag indicates if the MiMA is running (true) or halted (false).
@@ -11,6 +13,14 @@ pub struct Status
 	pub tra: Flag,
 }
 
+impl Default for Status
+{
+	fn default() -> Self
+	{
+		Status::new()
+	}
+}
+
 impl Status
 {
 	pub fn new() -> Status
@@ -43,6 +53,18 @@ pub struct Unit
 
 	// The current instruction (only available during microcycles [6, 12]):
 	instruction: Option<Instruction>,
+
+	// Monotonic counters of the performed microcycles and the completed instructions:
+	total_microcycles: u64,
+	total_instructions: u64,
+}
+
+impl Default for Unit
+{
+	fn default() -> Self
+	{
+		Unit::new()
+	}
 }
 
 impl Unit
@@ -56,9 +78,17 @@ impl Unit
 			status: Status::new(),
 			microcycle: 1,
 			instruction: None,
+			total_microcycles: 0,
+			total_instructions: 0,
 		}
 	}
 
+	// Restore the initial state (IAR = 0, RUN set, before microcycle 1):
+	pub fn reset(&mut self)
+	{
+		*self = Unit::new();
+	}
+
 	pub fn status(&self) -> &Status
 	{
 		&self.status
@@ -74,6 +104,16 @@ impl Unit
 		self.instruction
 	}
 
+	pub fn total_microcycles(&self) -> u64
+	{
+		self.total_microcycles
+	}
+
+	pub fn total_instructions(&self) -> u64
+	{
+		self.total_instructions
+	}
+
 	pub fn is_running(&self) -> bool
 	{
 		self.status.run.0
@@ -84,6 +124,8 @@ impl Unit
 {
 	pub(crate) fn end_microcycle(&mut self)
 	{
+		self.total_microcycles += 1;
+
 		match self.microcycle
 		{
 			5 =>
@@ -102,6 +144,7 @@ impl Unit
 
 				// The execute phase ends now. Drop the instruction.
 				self.instruction = None;
+				self.total_instructions += 1;
 			},
 
 			_ => ()
@@ -119,6 +162,27 @@ impl Unit
 		}
 	}
 
+	// Count a whole instruction (12 microcycles) that has been executed without microcycles (by the fast interpreter):
+	pub(crate) fn count_instruction(&mut self)
+	{
+		self.total_microcycles += 12;
+		self.total_instructions += 1;
+	}
+
+	pub(crate) fn restore_state(&mut self, status: Status, microcycle: u8, instruction: Option<Instruction>)
+	{
+		assert!((1..=12).contains(&microcycle), "The microcycle counter must be in [1, 12].");
+
+		self.status = status;
+		self.microcycle = microcycle;
+		self.instruction = instruction;
+	}
+
+	pub(crate) fn halt(&mut self)
+	{
+		self.status.run = Flag(false);
+	}
+
 	pub(crate) fn start_xfer(&mut self)
 	{
 		assert!(!self.status.tra.0, "A transfer is already in progress.");
@@ -1,11 +1,16 @@
+use std::cmp;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::io::{self, Read, Write};
 use std::mem;
+use std::ops::Range;
 use crate::types::*;
 use crate::assembly::*;
 
 // The two types of memory:
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type
 {
 	Linear,
@@ -32,11 +37,17 @@ impl Type
 	}
 }
 
-// How many microcycles does the memory need to complete work?
-const MICROCYCLES_PER_ACCESS: u8 = 3;
+// How many microcycles does the memory need to complete work (by default)?
+// The timing of a read that is signaled in microcycle n works like this:
+// - In microcycle n, the descriptor signals the access (3 remaining cycles).
+// - At the start of the microcycles n + 1, n + 2 and n + 3, polling counts down to 0.
+// - At the start of microcycle n + 4, polling finalizes the work, so SIR holds the result before the bus transfer of that microcycle.
+// For example, the execute stage signals its operand read in microcycle 6 and consumes SIR in microcycle 10.
+pub const DEFAULT_MICROCYCLES_PER_ACCESS: u8 = 3;
 
 // The two ways of accessing memory:
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Access
 {
 	Read,
@@ -47,6 +58,8 @@ pub enum Access
 // Each microcycle decrements the number of remaining cycles.
 // As soon as it falls to 0, a read result is available in SIR.
 // Work is executed on copies of SAR and SIR. Changing them during its progress won't change the outcome.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Work
 {
 	pub mem_type: Type,
@@ -56,12 +69,77 @@ pub struct Work
 	pub remaining_cycles: u8,
 }
 
-// This error type occurs when we load object code with unknown symbols:
+impl Work
+{
+	// The address that has been latched from SAR when the access has been signaled:
+	pub fn address(&self) -> Word
+	{
+		self.sar
+	}
+}
+
+// A device that is mapped into the device I/O address space.
+// Offsets are relative to the start of the range the device is attached to.
+// Devices are called in the middle of a microcycle, but they never get hold of the MiMA.
+// The borrow checker rules out that they drive it (and thereby start a microcycle from within another one).
+pub trait Device
+{
+	fn read(&mut self, offset: Word) -> Word;
+	fn write(&mut self, offset: Word, value: Word);
+}
+
+// Reads from device I/O addresses without an attached device yield this value:
+pub const UNMAPPED_DEVICE_READ_VALUE: Word = Word(0);
+
+// A device together with the address range it occupies:
+struct MappedDevice
+{
+	range: Range<Word>,
+	device: Box<dyn Device>,
+}
+
+// The labels a device exports for linking.
+// Label offsets are relative to the base address of the device.
+struct DeviceLabels
+{
+	base: Word,
+	offsets: HashMap<String, Word>,
+}
+
+// This error type occurs when we load code or memory images that do not fit into the linear memory.
+// The variants hold the offending number of words resp. the offending address.
+#[derive(Debug)]
+pub enum LoadError
+{
+	Oversized(usize),
+	WrongImageSize(usize),
+	BadAddress(Word),
+}
+
+impl fmt::Display for LoadError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			LoadError::Oversized(len) 		=> write!(f, "Code of {:} words exceeds the size of the linear address space ({:} words == {:} bytes).",
+													len, LINEAR_ADDRESS_SPACE_WORDS, LINEAR_ADDRESS_SPACE_WORDS * mem::size_of::<Word>()),
+			LoadError::WrongImageSize(len) 	=> write!(f, "Memory image of {:} words does not match the size of the linear address space ({:} words == {:} bytes).",
+													len, LINEAR_ADDRESS_SPACE_WORDS, LINEAR_ADDRESS_SPACE_WORDS * mem::size_of::<Word>()),
+			LoadError::BadAddress(addr) 	=> write!(f, "The address {:} is not part of the linear address space.", addr),
+		}
+	}
+}
+
+impl Error for LoadError { }
+
+// This error type occurs when we load object code with unknown symbols (or object code that is too large):
 #[derive(Debug)]
 pub enum LinkError<'oc>
 {
 	UnknownDevice(&'oc str),
 	UnknownDeviceLabel(&'oc str, &'oc str),
+	Load(LoadError),
 }
 
 impl<'oc> fmt::Display for LinkError<'oc>
@@ -72,12 +150,91 @@ impl<'oc> fmt::Display for LinkError<'oc>
 		{
 			LinkError::UnknownDevice(prefix) 				=> write!(f, "Symbol table references unknown device prefix: \"{:}\"", prefix),
 			LinkError::UnknownDeviceLabel(prefix, name) 	=> write!(f, "Symbol table references unknown label name \"{:}\" of attached device \"{:}\".", name, prefix),
+			LinkError::Load(err) 							=> write!(f, "{:}", err),
 		}
 	}
 }
 
 impl<'oc> Error for LinkError<'oc> { }
 
+impl<'oc> From<LoadError> for LinkError<'oc>
+{
+	fn from(err: LoadError) -> LinkError<'oc>
+	{
+		LinkError::Load(err)
+	}
+}
+
+// This error type occurs when we dump a range that is not part of the linear memory:
+#[derive(Debug)]
+pub enum DumpError
+{
+	BadRange(Range<Word>),
+}
+
+impl fmt::Display for DumpError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			DumpError::BadRange(range) 	=> write!(f, "The range [{:}, {:}) is not part of the linear address space.", range.start, range.end),
+		}
+	}
+}
+
+impl Error for DumpError { }
+
+// A fault that occurs when the memory is accessed at an address outside of the linear address space or a program writes to protected memory:
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault
+{
+	OutOfRange(Word),
+	WriteProtected(Word),
+}
+
+impl fmt::Display for Fault
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			Fault::OutOfRange(addr) 		=> write!(f, "The address {:} is not part of the linear address space.", addr),
+			Fault::WriteProtected(addr) 	=> write!(f, "The address {:} is write-protected.", addr),
+		}
+	}
+}
+
+impl Error for Fault { }
+
+// Memory images are read and written in chunks of this size:
+const MEM_IMAGE_CHUNK_BYTES: usize = 64 * 1024;
+
+// Read until the buffer is full or the reader is exhausted and return the number of bytes read:
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize>
+{
+	let mut filled = 0;
+
+	while filled < buf.len()
+	{
+		match r.read(&mut buf[filled..])
+		{
+			Ok(0) 													=> break,
+			Ok(n) 													=> filled += n,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted 	=> (),
+			Err(err) 												=> return Err(err),
+		}
+	}
+
+	Ok(filled)
+}
+
+fn wrong_image_size(bytes: usize) -> io::Error
+{
+	io::Error::new(io::ErrorKind::InvalidData, LoadError::WrongImageSize(bytes / mem::size_of::<Word>()))
+}
+
 pub struct Unit
 {
 	// "Speicheradressregister" (SAR)
@@ -94,6 +251,18 @@ pub struct Unit
 	// The non-DMA memory.
 	// This is a linear, heap-allocated blob of host memory
 	linear_memory: Box<[Word]>,
+
+	// The attached devices (with disjoint address ranges):
+	devices: Vec<MappedDevice>,
+
+	// The labels of the devices (by device prefix):
+	device_labels: HashMap<String, DeviceLabels>,
+
+	// The latency of memory accesses:
+	microcycles_per_access: u8,
+
+	// The read-only ranges of the linear memory (they may overlap):
+	protected: Vec<Range<Word>>,
 }
 
 // Resolved symbols are generated from an object code symbol table:
@@ -116,6 +285,14 @@ impl ResolvedSymbol
 }
 
 
+impl Default for Unit
+{
+	fn default() -> Self
+	{
+		Unit::new()
+	}
+}
+
 impl Unit
 {
 	pub fn new() -> Unit
@@ -128,70 +305,299 @@ impl Unit
 
 			// Initialize all words to "Halt" to avoid stupid overflows:
 			linear_memory: vec![Instruction::Halt.into(); LINEAR_ADDRESS_SPACE_WORDS].into_boxed_slice(),
+			devices: Vec::new(),
+			device_labels: HashMap::new(),
+			microcycles_per_access: DEFAULT_MICROCYCLES_PER_ACCESS,
+			protected: Vec::new(),
+		}
+	}
+
+	// Zero SAR and SIR and drop pending work.
+	// The memory contents, the attached devices and the configured latency are kept.
+	pub fn reset(&mut self)
+	{
+		self.sar = Word(0);
+		self.sir = Word(0);
+		self.work = None;
+	}
+
+	// Fill the linear memory with "Halt" again (like a freshly constructed memory unit):
+	pub fn reset_memory(&mut self)
+	{
+		for word in self.linear_memory.iter_mut()
+		{
+			*word = Instruction::Halt.into();
 		}
 	}
 
+	// Attach a device to a sub-range of the device I/O address space.
+	// The range must not be empty and must not overlap with the range of another device.
+	pub fn attach_device(&mut self, range: Range<Word>, device: Box<dyn Device>)
+	{
+		assert!(range.start < range.end, "The range of a device must not be empty.");
+		assert!(DEVICE_IO_ADDRESS_SPACE_RANGE.start <= range.start && range.end <= DEVICE_IO_ADDRESS_SPACE_RANGE.end,
+				"Device range [0x{:08X}, 0x{:08X}] is not part of the device I/O address space [0x{:08X}, 0x{:08X}].",
+				range.start.0, range.end.0 - 1, DEVICE_IO_ADDRESS_SPACE_RANGE.start.0, DEVICE_IO_ADDRESS_SPACE_RANGE.end.0 - 1);
+		assert!(self.devices.iter().all(|mapped| range.end <= mapped.range.start || mapped.range.end <= range.start),
+				"Device range [0x{:08X}, 0x{:08X}] overlaps with an attached device.", range.start.0, range.end.0 - 1);
+
+		self.devices.push(MappedDevice { range, device });
+	}
+
+	// Register the labels of a device under the given prefix.
+	// Object code can reference them as "prefix.name". They resolve to the base address plus the label offset.
+	pub fn register_device_labels(&mut self, prefix: &str, base: Word, labels: &[(&str, Word)])
+	{
+		let offsets = labels.iter().map(|&(name, offset)| (String::from(name), offset)).collect();
+		self.device_labels.insert(String::from(prefix), DeviceLabels { base, offsets });
+	}
+
 	pub fn work(&self) -> Option<&Work>
 	{
 		self.work.as_ref()
 	}
 
+	pub fn microcycles_per_access(&self) -> u8
+	{
+		self.microcycles_per_access
+	}
+
+	// Change the latency of memory accesses.
+	// The fetch and execute microcycles are designed for the default. Raising it makes reads complete too late, which faults the MiMA (see "Fault::ResultNotReady").
+	pub fn set_microcycles_per_access(&mut self, microcycles_per_access: u8)
+	{
+		self.microcycles_per_access = microcycles_per_access;
+	}
+
+	// Is a read still in flight (i. e. SIR does not hold its result yet)?
+	pub fn is_read_pending(&self) -> bool
+	{
+		self.work.as_ref().is_some_and(|work| matches!(work.access, Access::Read))
+	}
+
+	// Make a range of the linear memory read-only (e. g. to simulate a ROM).
+	// Programs that write to it fault, but loading code and "write_word" bypass the protection.
+	// Like the devices, the protection survives "reset" and "reset_memory".
+	pub fn protect(&mut self, range: Range<Word>)
+	{
+		assert!(range.end <= LINEAR_ADDRESS_SPACE_RANGE.end, "Only the linear memory can be protected.");
+
+		if range.start < range.end
+		{
+			self.protected.push(range);
+		}
+	}
+
+	// Make a range of the linear memory writable again (the protected ranges around it are kept):
+	pub fn unprotect(&mut self, range: Range<Word>)
+	{
+		self.protected = self.protected.iter()
+			.flat_map(|protected| vec![protected.start..Word(cmp::min(protected.end.0, range.start.0)), Word(cmp::max(protected.start.0, range.end.0))..protected.end])
+			.filter(|remaining| remaining.start < remaining.end)
+			.collect();
+	}
+
+	pub fn is_protected(&self, address: Word) -> bool
+	{
+		self.protected.iter().any(|protected| protected.contains(&address))
+	}
+
 	pub fn linear_memory(&self) -> &[Word]
 	{
 		&self.linear_memory
 	}
 
+	// Read / write a single word of the linear memory (without latency).
+	// Addresses outside of the linear address space are a fault instead of a panic.
+	// Together with "load_raw_code", "write_word" seeds single words before running (e. g. to simulate an input).
+	pub fn read_word(&self, address: Word) -> Result<Word, Fault>
+	{
+		if !LINEAR_ADDRESS_SPACE_RANGE.contains(&address)
+		{
+			return Err(Fault::OutOfRange(address));
+		}
+
+		Ok(self.read_linear(address))
+	}
+
+	pub fn write_word(&mut self, address: Word, value: Word) -> Result<(), Fault>
+	{
+		if !LINEAR_ADDRESS_SPACE_RANGE.contains(&address)
+		{
+			return Err(Fault::OutOfRange(address));
+		}
+
+		self.write_linear(address, value);
+		Ok(())
+	}
+
+	// Dump a range of the linear memory as hex, 4 words per row (prefixed with the address of the first one):
+	pub fn dump(&self, range: Range<Word>) -> Result<String, DumpError>
+	{
+		const WORDS_PER_ROW: usize = 4;
+
+		if (range.start > range.end) || (range.end > LINEAR_ADDRESS_SPACE_RANGE.end)
+		{
+			return Err(DumpError::BadRange(range));
+		}
+
+		let words = &self.linear_memory[(range.start.0 as usize)..(range.end.0 as usize)];
+		let mut dump = String::new();
+
+		for (row_index, row) in words.chunks(WORDS_PER_ROW).enumerate()
+		{
+			let addr = Word(range.start.0 + (row_index * WORDS_PER_ROW) as u32);
+			let cells: Vec<_> = row.iter().map(|word| format!("{:}", word)).collect();
+
+			dump.push_str(&format!("{:}: {:}\n", addr, cells.join(" ")));
+		}
+
+		Ok(dump)
+	}
+
 	pub fn load_code<'oc>(&mut self, code: &'oc ObjectCode) -> Result<(), LinkError<'oc>>
 	{
 		// Resolve the symbol table:
 		let resolved_symbols = self.resolve_symbol_table(&code.symbol_table)?;
 
 		// Load the raw object code:
-		self.load_raw_code(&code.raw_code);
+		self.load_raw_code(&code.raw_code)?;
 
-		// Now insert the resolved symbols:
+		// Now insert the resolved symbols.
+		// The placeholder payload is replaced by the device address, the opcode in the uppermost four bits is preserved.
 		for symbol in resolved_symbols
 		{
-			self.linear_memory[symbol.instruction_address.0 as usize].0 &= symbol.device_address.0 & 0x0F_FF_FF_FFu32;
+			let word = &mut self.linear_memory[symbol.instruction_address.0 as usize];
+			debug_assert!((word.0 & 0x0F_FF_FF_FFu32) == ObjectCode::PLACEHOLDER_ADDR.0, "Symbol at 0x{:08X} does not reference a placeholder address.", symbol.instruction_address.0);
+
+			word.0 = (word.0 & 0xF0_00_00_00u32) | (symbol.device_address.0 & 0x0F_FF_FF_FFu32);
 		}
 
 		Ok(())
 	}
 
-	pub fn load_raw_code(&mut self, raw_code: &[Word])
+	pub fn load_raw_code(&mut self, raw_code: &[Word]) -> Result<(), LoadError>
 	{
-		assert!(raw_code.len() <= LINEAR_ADDRESS_SPACE_WORDS, "Raw code must not exceed the size of the linear address space ({} words == {} bytes).",
-				LINEAR_ADDRESS_SPACE_WORDS, LINEAR_ADDRESS_SPACE_WORDS * mem::size_of::<Word>());
+		// Raw code must not exceed the size of the linear address space:
+		if raw_code.len() > LINEAR_ADDRESS_SPACE_WORDS
+		{
+			return Err(LoadError::Oversized(raw_code.len()));
+		}
 
 		// Copy the new image to offset 0:
 		self.linear_memory[..raw_code.len()].clone_from_slice(raw_code);
+
+		Ok(())
 	}
 
-	pub fn load_mem_image(&mut self, mem_image: Box<[Word]>)
+	pub fn load_mem_image(&mut self, mem_image: Box<[Word]>) -> Result<(), LoadError>
 	{
-		assert!(mem_image.len() == LINEAR_ADDRESS_SPACE_WORDS, "Memory image must exactly match the size of the linear address space ({} words == {} bytes).",
-				LINEAR_ADDRESS_SPACE_WORDS, LINEAR_ADDRESS_SPACE_WORDS * mem::size_of::<Word>());
+		// The memory image must exactly match the size of the linear address space:
+		if mem_image.len() != LINEAR_ADDRESS_SPACE_WORDS
+		{
+			return Err(LoadError::WrongImageSize(mem_image.len()));
+		}
 
 		// Move the box into ours:
 		self.linear_memory = mem_image;
+
+		Ok(())
+	}
+
+	// Load a sparse memory image: Every cell that is not listed holds "Halt".
+	pub fn load_cells(&mut self, cells: &[(Word, Word)]) -> Result<(), LoadError>
+	{
+		if let Some(&(addr, _)) = cells.iter().find(|(addr, _)| !LINEAR_ADDRESS_SPACE_RANGE.contains(addr))
+		{
+			return Err(LoadError::BadAddress(addr));
+		}
+
+		self.reset_memory();
+
+		for &(addr, value) in cells.iter()
+		{
+			self.write_linear(addr, value);
+		}
+
+		Ok(())
+	}
+
+	// Read a memory image (little-endian u32 words) that has been written by "write_mem_image_to_writer".
+	// The image must contain exactly one word per linear memory cell, otherwise the error wraps a "LoadError::WrongImageSize".
+	pub fn load_mem_image_from_reader<R: Read>(&mut self, mut r: R) -> io::Result<()>
+	{
+		let mut mem_image = vec![Word(0); LINEAR_ADDRESS_SPACE_WORDS].into_boxed_slice();
+		let mut bytes = [0u8; MEM_IMAGE_CHUNK_BYTES];
+		let mut bytes_read = 0;
+
+		// Read in chunks (one read call per word would be painfully slow for unbuffered readers):
+		for chunk in mem_image.chunks_mut(MEM_IMAGE_CHUNK_BYTES / mem::size_of::<Word>())
+		{
+			let chunk_bytes = &mut bytes[..mem::size_of_val(chunk)];
+			let n = read_full(&mut r, chunk_bytes)?;
+			bytes_read += n;
+
+			if n < chunk_bytes.len()
+			{
+				return Err(wrong_image_size(bytes_read));
+			}
+
+			for (word, word_bytes) in chunk.iter_mut().zip(chunk_bytes.chunks_exact(mem::size_of::<Word>()))
+			{
+				*word = Word(u32::from_le_bytes([word_bytes[0], word_bytes[1], word_bytes[2], word_bytes[3]]));
+			}
+		}
+
+		// There must not be any trailing bytes:
+		let trailing_bytes = io::copy(&mut r, &mut io::sink())? as usize;
+
+		if trailing_bytes > 0
+		{
+			return Err(wrong_image_size(bytes_read + trailing_bytes));
+		}
+
+		self.linear_memory = mem_image;
+		Ok(())
+	}
+
+	// Write the whole linear memory as little-endian u32 words:
+	pub fn write_mem_image_to_writer<W: Write>(&self, mut w: W) -> io::Result<()>
+	{
+		let mut bytes = Vec::with_capacity(MEM_IMAGE_CHUNK_BYTES);
+
+		for chunk in self.linear_memory.chunks(MEM_IMAGE_CHUNK_BYTES / mem::size_of::<Word>())
+		{
+			bytes.clear();
+			bytes.extend(chunk.iter().flat_map(|word| word.0.to_le_bytes()));
+
+			w.write_all(&bytes)?;
+		}
+
+		w.flush()
 	}
 
-	pub fn load_instructions(&mut self, instructions: &[Instruction])
+	pub fn load_instructions(&mut self, instructions: &[Instruction]) -> Result<(), LoadError>
 	{
-		assert!(instructions.len() <= LINEAR_ADDRESS_SPACE_WORDS, "Asembled instructions must not exceed the size of the linear address space ({} words == {} bytes).",
-				LINEAR_ADDRESS_SPACE_WORDS, LINEAR_ADDRESS_SPACE_WORDS * mem::size_of::<Word>());
+		// Assembled instructions must not exceed the size of the linear address space:
+		if instructions.len() > LINEAR_ADDRESS_SPACE_WORDS
+		{
+			return Err(LoadError::Oversized(instructions.len()));
+		}
 
 		// Assemble the instructions to offset 0:
 		for (i, &instruction) in instructions.iter().enumerate()
 		{
 			self.linear_memory[i] = instruction.into();
 		}
+
+		Ok(())
 	}
 }
 
 impl Unit
 {
-	pub(crate) fn poll_work(&mut self)
+	// Completing linear work at an address outside of the linear memory faults (the work is dropped).
+	pub(crate) fn poll_work(&mut self) -> Result<(), Fault>
 	{
 		// Perform memory work if necessary:
 		if let Some(work) = self.work.as_mut()
@@ -203,7 +609,7 @@ impl Unit
 				// Linear memory or device I/O?
 				match work.mem_type
 				{
-					Type::Linear 	=> self.finalize_work_linear(work),
+					Type::Linear 	=> self.finalize_work_linear(work)?,
 					Type::DeviceIO 	=> self.finalize_work_device_io(work),
 				}
 			}
@@ -212,6 +618,33 @@ impl Unit
 				work.remaining_cycles -= 1;
 			}
 		}
+
+		Ok(())
+	}
+
+	pub(crate) fn restore_work(&mut self, work: Option<Work>)
+	{
+		self.work = work;
+	}
+
+	// Access the memory immediately (without pending work).
+	// This is used by the fast interpreter, which bypasses the microcycles.
+	pub(crate) fn read_immediately(&mut self, address: Word) -> Word
+	{
+		match Type::from_address(address)
+		{
+			Type::Linear 	=> self.read_linear(address),
+			Type::DeviceIO 	=> self.read_device_io(address),
+		}
+	}
+
+	pub(crate) fn write_immediately(&mut self, address: Word, value: Word)
+	{
+		match Type::from_address(address)
+		{
+			Type::Linear 	=> self.write_linear(address, value),
+			Type::DeviceIO 	=> self.write_device_io(address, value),
+		}
 	}
 
 	pub(crate) fn signal_memory(&mut self, access: Access)
@@ -224,36 +657,126 @@ impl Unit
 			access,
 			sar: self.sar,
 			sir: self.sir,
-			remaining_cycles: MICROCYCLES_PER_ACCESS,
+			remaining_cycles: self.microcycles_per_access,
 		});
 	}
 }
 
 impl Unit
 {
-	fn finalize_work_linear(&mut self, work: Work)
+	fn finalize_work_linear(&mut self, work: Work) -> Result<(), Fault>
 	{
 		// Access the linear memory:
 		match work.access
 		{
-			Access::Read 	=> self.sir = self.linear_memory[work.sar.0 as usize],
-			Access::Write 	=> self.linear_memory[work.sar.0 as usize] = work.sir,
+			Access::Read 									=> self.sir = self.read_word(work.sar)?,
+			Access::Write if self.is_protected(work.sar) 	=> return Err(Fault::WriteProtected(work.sar)),
+			Access::Write 									=> self.write_word(work.sar, work.sir)?,
 		}
+
+		Ok(())
 	}
 
 	fn finalize_work_device_io(&mut self, work: Work)
 	{
-		// TODO
+		// Access the attached devices:
 		match work.access
 		{
-			Access::Read 	=> self.sir = Word(42),
-			Access::Write 	=> (),
+			Access::Read 	=> self.sir = self.read_device_io(work.sar),
+			Access::Write 	=> self.write_device_io(work.sar, work.sir),
+		}
+	}
+
+	fn read_linear(&self, address: Word) -> Word
+	{
+		self.linear_memory[address.0 as usize]
+	}
+
+	fn write_linear(&mut self, address: Word, value: Word)
+	{
+		self.linear_memory[address.0 as usize] = value;
+	}
+
+	fn read_device_io(&mut self, address: Word) -> Word
+	{
+		// Dispatch to the device that is mapped to the address (with its local offset).
+		// Unmapped reads yield a well-defined value.
+		match self.devices.iter_mut().find(|mapped| mapped.range.contains(&address))
+		{
+			Some(mapped) 	=> mapped.device.read(Word(address.0 - mapped.range.start.0)),
+			None 			=> UNMAPPED_DEVICE_READ_VALUE,
+		}
+	}
+
+	fn write_device_io(&mut self, address: Word, value: Word)
+	{
+		// Dispatch to the device that is mapped to the address (with its local offset).
+		// Unmapped writes are ignored.
+		if let Some(mapped) = self.devices.iter_mut().find(|mapped| mapped.range.contains(&address))
+		{
+			mapped.device.write(Word(address.0 - mapped.range.start.0), value);
 		}
 	}
 
 	fn resolve_symbol_table<'oc>(&self, symbol_table: &'oc [Symbol]) -> Result<Vec<ResolvedSymbol>, LinkError<'oc>>
 	{
-		//TODO
-		Ok(symbol_table.iter().map(|sym| ResolvedSymbol::new(sym.instruction_address, Word(0x0F_FF_FF_FFu32))).collect())
+		symbol_table.iter().map(|sym|
+		{
+			// Look up the device and the label inside it:
+			let device = self.device_labels.get(&sym.label.prefix).ok_or(LinkError::UnknownDevice(&sym.label.prefix))?;
+			let offset = device.offsets.get(&sym.label.name).ok_or(LinkError::UnknownDeviceLabel(&sym.label.prefix, &sym.label.name))?;
+
+			Ok(ResolvedSymbol::new(sym.instruction_address, Word(device.base.0.wrapping_add(offset.0))))
+		}).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn oversized_raw_code_is_rejected()
+	{
+		let mut unit = Unit::new();
+		let raw_code = vec![Word(0); LINEAR_ADDRESS_SPACE_WORDS + 1];
+
+		assert!(matches!(unit.load_raw_code(&raw_code), Err(LoadError::Oversized(len)) if len == LINEAR_ADDRESS_SPACE_WORDS + 1));
+	}
+
+	#[test]
+	fn wrong_sized_image_is_rejected()
+	{
+		let mut unit = Unit::new();
+		let mem_image = vec![Word(0); 16].into_boxed_slice();
+
+		assert!(matches!(unit.load_mem_image(mem_image), Err(LoadError::WrongImageSize(16))));
+	}
+
+	#[test]
+	fn linking_device_label_keeps_opcode()
+	{
+		let (code, _) = ObjectCode::assemble("ldv uart.status\nstv uart.data\nhlt").unwrap();
+
+		let mut unit = Unit::new();
+		unit.register_device_labels("uart", Word(0xC000100), &[("status", Word(0)), ("data", Word(1))]);
+		unit.load_code(&code).unwrap();
+
+		assert_eq!(unit.linear_memory()[0], Instruction::LoadValue(Word(0xC000100)).into());
+		assert_eq!(unit.linear_memory()[1], Instruction::StoreValue(Word(0xC000101)).into());
+	}
+
+	#[test]
+	fn word_access_outside_linear_memory_faults()
+	{
+		let mut unit = Unit::new();
+		let device_address = DEVICE_IO_ADDRESS_SPACE_RANGE.start;
+
+		assert_eq!(unit.write_word(Word(0x10), Word(7)), Ok(()));
+		assert_eq!(unit.read_word(Word(0x10)), Ok(Word(7)));
+
+		assert_eq!(unit.read_word(device_address), Err(Fault::OutOfRange(device_address)));
+		assert_eq!(unit.write_word(device_address, Word(7)), Err(Fault::OutOfRange(device_address)));
 	}
 }
@@ -2,6 +2,6 @@ mod arithmetic;
 mod control;
 mod memory;
 
-pub use arithmetic::{Operation as ALUOperation, Work as ALUWork, Unit as ArithmeticUnit};
+pub use arithmetic::{Operation as ALUOperation, CustomOperationFn as ALUCustomOperationFn, Work as ALUWork, Fault as ALUFault, DEFAULT_MICROCYCLES_PER_OP, Unit as ArithmeticUnit};
 pub use control::{Status as ControlStatus, Unit as ControlUnit};
-pub use memory::{Type as MemoryType, Access as MemoryAccess, Work as MemoryWork, LinkError, Unit as MemoryUnit};
+pub use memory::{Type as MemoryType, Access as MemoryAccess, Work as MemoryWork, LoadError, LinkError, DumpError, Fault as MemoryFault, Device, UNMAPPED_DEVICE_READ_VALUE, DEFAULT_MICROCYCLES_PER_ACCESS, Unit as MemoryUnit};
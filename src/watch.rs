@@ -0,0 +1,195 @@
+use std::error::Error;
+use std::fmt;
+use nom::
+{
+	IResult,
+	branch::alt,
+	bytes::complete::tag_no_case,
+	character::complete::{alpha1, char as single_char, digit1, hex_digit1, one_of, space0},
+	combinator::{all_consuming, map, map_opt, map_res},
+	multi::fold_many0,
+	sequence::{delimited, pair, preceded},
+};
+use crate::types::{*, Registers as Regs};
+use crate::mima::Mima;
+
+// A watch expression is evaluated against the current machine state (e. g. "ACC - IAR" or "mem[0x10]").
+// It consists of registers, memory cells and literals combined via '+' and '-' (wrapping).
+pub struct WatchExpression
+{
+	source: String,
+	expression: Expression,
+}
+
+#[derive(Clone)]
+enum Expression
+{
+	Register(Regs),
+	Memory(Box<Expression>),
+	Literal(Word),
+	Add(Box<Expression>, Box<Expression>),
+	Subtract(Box<Expression>, Box<Expression>),
+}
+
+// This error type occurs when a watch expression cannot be parsed:
+#[derive(Debug)]
+pub struct WatchSyntaxError(pub String);
+
+impl fmt::Display for WatchSyntaxError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		write!(f, "Invalid watch expression: \"{:}\"", self.0)
+	}
+}
+
+impl Error for WatchSyntaxError { }
+
+// This error type occurs when a watch expression reads memory outside of the linear address space:
+#[derive(Debug)]
+pub enum WatchFault
+{
+	BadAddress(Word),
+}
+
+impl fmt::Display for WatchFault
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self
+		{
+			WatchFault::BadAddress(addr) 	=> write!(f, "{:} is not an address of the linear memory.", addr),
+		}
+	}
+}
+
+impl Error for WatchFault { }
+
+impl WatchExpression
+{
+	pub fn parse(source: &str) -> Result<WatchExpression, WatchSyntaxError>
+	{
+		match all_consuming(delimited(space0, expression, space0))(source)
+		{
+			Ok((_, expression)) 	=> Ok(WatchExpression { source: String::from(source.trim()), expression }),
+			Err(_) 					=> Err(WatchSyntaxError(String::from(source))),
+		}
+	}
+
+	pub fn source(&self) -> &str
+	{
+		&self.source
+	}
+
+	// Evaluate the expression against the current state of the MiMA.
+	// Device I/O is never accessed (reads could have side effects).
+	pub fn evaluate(&self, mima: &Mima) -> Result<Word, WatchFault>
+	{
+		self.expression.evaluate(mima)
+	}
+}
+
+impl Expression
+{
+	fn evaluate(&self, mima: &Mima) -> Result<Word, WatchFault>
+	{
+		match self
+		{
+			Expression::Register(reg) 	=> Ok(mima.register(*reg)),
+			Expression::Literal(word) 	=> Ok(*word),
+			Expression::Memory(addr) =>
+			{
+				let addr = addr.evaluate(mima)?;
+				mima.memory_unit.linear_memory().get(addr.0 as usize).copied().ok_or(WatchFault::BadAddress(addr))
+			},
+			Expression::Add(lhs, rhs) 		=> Ok(Word(lhs.evaluate(mima)?.0.wrapping_add(rhs.evaluate(mima)?.0))),
+			Expression::Subtract(lhs, rhs) 	=> Ok(Word(lhs.evaluate(mima)?.0.wrapping_sub(rhs.evaluate(mima)?.0))),
+		}
+	}
+}
+
+fn expression(i: &str) -> IResult<&str, Expression>
+{
+	// A chain of operands, combined left-associatively:
+	let (i, first) = operand(i)?;
+	let operator = delimited(space0, one_of("+-"), space0);
+
+	fold_many0(pair(operator, operand), move || first.clone(), |lhs, (op, rhs)| match op
+	{
+		'+' => Expression::Add(Box::new(lhs), Box::new(rhs)),
+		_ 	=> Expression::Subtract(Box::new(lhs), Box::new(rhs)),
+	})(i)
+}
+
+fn operand(i: &str) -> IResult<&str, Expression>
+{
+	// "mem" must be tried before the register names:
+	alt((memory, register, literal))(i)
+}
+
+fn memory(i: &str) -> IResult<&str, Expression>
+{
+	let address = delimited(pair(single_char('['), space0), expression, pair(space0, single_char(']')));
+	map(preceded(pair(tag_no_case("mem"), space0), address), |addr| Expression::Memory(Box::new(addr)))(i)
+}
+
+fn register(i: &str) -> IResult<&str, Expression>
+{
+	let select_register = |name: &str| match name.to_uppercase().as_str()
+	{
+		"ACC" 	=> Some(Regs::ACC),
+		"ONE" 	=> Some(Regs::ONE),
+		"X" 	=> Some(Regs::X),
+		"Y" 	=> Some(Regs::Y),
+		"Z" 	=> Some(Regs::Z),
+		"IAR" 	=> Some(Regs::IAR),
+		"IR" 	=> Some(Regs::IR),
+		"SAR" 	=> Some(Regs::SAR),
+		"SIR" 	=> Some(Regs::SIR),
+		_ 		=> None,
+	};
+
+	map(map_opt(alpha1, select_register), Expression::Register)(i)
+}
+
+fn literal(i: &str) -> IResult<&str, Expression>
+{
+	let hex = map_res(preceded(tag_no_case("0x"), hex_digit1), |s| u32::from_str_radix(s, 16));
+	let dec = map_res(digit1, |s: &str| s.parse::<u32>());
+
+	map(alt((hex, dec)), |n| Expression::Literal(Word(n)))(i)
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::assembly::ObjectCode;
+
+	fn evaluate(mima: &Mima, source: &str) -> Result<Word, WatchFault>
+	{
+		WatchExpression::parse(source).unwrap().evaluate(mima)
+	}
+
+	#[test]
+	fn evaluate_against_machine_state()
+	{
+		let (code, _) = ObjectCode::assemble("ldc 5\nhlt").unwrap();
+		let mut mima = Mima::new();
+		mima.load_code(&code).unwrap();
+		mima.run_with_limit(100);
+
+		assert_eq!(evaluate(&mima, "ACC").unwrap(), Word(5));
+		assert_eq!(evaluate(&mima, "mem[0]").unwrap(), Instruction::LoadConstant(Word(5)).into());
+		assert_eq!(evaluate(&mima, "acc - IAR + 0x10").unwrap(), Word(5u32.wrapping_sub(2) + 0x10));
+		assert!(matches!(evaluate(&mima, "mem[0xC000000]"), Err(WatchFault::BadAddress(Word(0xC000000)))));
+	}
+
+	#[test]
+	fn reject_bad_syntax()
+	{
+		assert!(WatchExpression::parse("ACC +").is_err());
+		assert!(WatchExpression::parse("mem[0x10").is_err());
+		assert!(WatchExpression::parse("PC").is_err());
+	}
+}
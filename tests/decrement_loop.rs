@@ -0,0 +1,56 @@
+// Run the decrement-and-branch idiom of the Fibonacci example (ADD decr, JMN out) on its own.
+
+use mimasim::{assembly::ObjectCode, mima::Mima, types::*};
+
+const COUNT_ADDRESS: Word = Word(0);
+const ITERATIONS_ADDRESS: Word = Word(2);
+
+// Like the Fibonacci loop, but the body only counts the iterations:
+fn program(count: u32) -> String
+{
+	format!("START loop
+
+count: DAT {:}
+decr:  DAT -1
+iters: DAT 0
+one:   DAT 1
+
+loop:
+LDV count
+ADD decr
+JMN out
+STV count
+
+LDV iters
+ADD one
+STV iters
+JMP loop
+
+out:
+HLT", count)
+}
+
+// Run the loop and return the number of iterations and the final count:
+fn run_loop(count: u32) -> (u32, u32)
+{
+	let (object_code, _) = ObjectCode::assemble(&program(count)).unwrap();
+
+	let mut mima = Mima::new();
+	mima.load_code(&object_code).unwrap();
+	mima.run_to_halt(10_000).unwrap();
+	assert!(mima.fault().is_none());
+
+	let iterations = mima.memory_unit.read_word(ITERATIONS_ADDRESS).unwrap();
+	let count = mima.memory_unit.read_word(COUNT_ADDRESS).unwrap();
+
+	(iterations.0, count.0)
+}
+
+#[test]
+fn loop_runs_count_times()
+{
+	// "count - 1" only becomes negative after "count" iterations and is not stored then:
+	assert_eq!(run_loop(5), (5, 0));
+	assert_eq!(run_loop(1), (1, 0));
+	assert_eq!(run_loop(0), (0, 0));
+}
@@ -0,0 +1,62 @@
+// Run the Fibonacci example program end to end (assembly, linking, devices and running).
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use mimasim::{assembly::ObjectCode, mima::Mima, types::*, unit::Device};
+
+const CONSOLE_BASE: Word = DEVICE_IO_ADDRESS_SPACE_RANGE.start;
+const CONSOLE_GETC: Word = Word(0);
+const CONSOLE_PUTC: Word = Word(1);
+
+// Like the console of the example, but the printed words are captured:
+struct CapturingConsole
+{
+	input: Vec<u8>,
+	output: Rc<RefCell<Vec<u32>>>,
+}
+
+impl Device for CapturingConsole
+{
+	fn read(&mut self, offset: Word) -> Word
+	{
+		match offset
+		{
+			CONSOLE_GETC if !self.input.is_empty() 	=> Word(self.input.remove(0) as u32),
+			_ 										=> Word(0),
+		}
+	}
+
+	fn write(&mut self, offset: Word, value: Word)
+	{
+		if offset == CONSOLE_PUTC
+		{
+			self.output.borrow_mut().push(value.0);
+		}
+	}
+}
+
+fn run_fibonacci(count: u8) -> Vec<u32>
+{
+	let (object_code, _) = ObjectCode::assemble(include_str!("../examples/fibonacci.asm")).unwrap();
+	let output = Rc::new(RefCell::new(vec![]));
+
+	let mut mima = Mima::new();
+	let console = CapturingConsole { input: vec![b'0' + count], output: Rc::clone(&output) };
+
+	mima.memory_unit.attach_device(CONSOLE_BASE..Word(CONSOLE_BASE.0 + 2), Box::new(console));
+	mima.memory_unit.register_device_labels("stdin", CONSOLE_BASE, &[("getc", CONSOLE_GETC)]);
+	mima.memory_unit.register_device_labels("stdout", CONSOLE_BASE, &[("putc", CONSOLE_PUTC)]);
+
+	mima.load_code(&object_code).unwrap();
+	mima.run_to_halt(10_000).unwrap();
+
+	output.take()
+}
+
+#[test]
+fn prints_first_terms()
+{
+	assert_eq!(run_fibonacci(9), [0, 1, 1, 2, 3, 5, 8, 13, 21]);
+	assert_eq!(run_fibonacci(1), [0]);
+	assert!(run_fibonacci(0).is_empty());
+}